@@ -1,42 +1,227 @@
 //! Authentication handlers
+//!
+//! Logins issue an HS256 token whose `jti` names a row in `auth_sessions`, so a
+//! token stops working as soon as its session is revoked, even before it expires.
 
+use chrono::{Duration, Utc};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LoginRequest {
-    pub email: String,
-    pub password: String,
-}
+use super::models::AuthSession;
+use super::tokens::{self, Claims};
+use super::{sessions, signing_secret, AuthConfig};
+use crate::handlers::users::authenticate_user_with;
+use crate::lockout::login_throttle;
+use crate::models::{LoginRequest, PublicUser};
+use crate::repositories::{SqlLogRepository, SqlUserRepository};
+use crate::session::Session;
+use crate::totp::VaultTotpSecrets;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginResponse {
     pub token: String,
     pub user_id: String,
+    pub session_id: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub user: PublicUser,
+}
+
+/// Returns the active session `token` belongs to, or `None` if the token is
+/// invalid, expired, its session has been revoked, or its user has been
+/// deactivated or deleted.
+pub(crate) async fn session_for_token(token: &str) -> Result<Option<AuthSession>, String> {
+    let secret = signing_secret(&AuthConfig::from_env()).await?;
+    let now = Utc::now();
+
+    let Ok(claims) = tokens::decode(token, &secret, now) else {
+        return Ok(None);
+    };
+
+    sessions::find_active_session(claims.jti, claims.sub, now)
+        .await
+        .map_err(|e| format!("Failed to load session: {}", e))
+}
+
+async fn require_session(token: &str) -> Result<AuthSession, String> {
+    session_for_token(token)
+        .await?
+        .ok_or_else(|| "Not authenticated".to_string())
 }
 
 /// Handle user login
 #[tauri::command]
-pub async fn auth_login(request: LoginRequest) -> Result<LoginResponse, String> {
-    // TODO: Implement actual authentication logic
+pub async fn auth_login(session: Session, request: LoginRequest) -> Result<LoginResponse, String> {
     tracing::info!("Login attempt for user: {}", request.email);
 
-    // Mock response for now
+    let config = AuthConfig::from_env();
+    let secret = signing_secret(&config).await?;
+    let repo = SqlUserRepository::from_global().map_err(|e| e.to_string())?;
+    let logs = SqlLogRepository::from_global().map_err(|e| e.to_string())?;
+
+    let user = authenticate_user_with(
+        &repo,
+        &logs,
+        login_throttle(),
+        &VaultTotpSecrets,
+        config.require_email_verification,
+        &session.label,
+        request,
+    )
+    .await?
+    .ok_or_else(|| "Invalid email or password".to_string())?;
+
+    let now = Utc::now();
+    let expires_at = now + Duration::hours(config.jwt_expiry_hours as i64);
+    let session = sessions::create_session(user.id, now, expires_at)
+        .await
+        .map_err(|e| format!("Failed to create session: {}", e))?;
+
+    let token = tokens::encode(
+        &Claims {
+            sub: user.id,
+            jti: session.id,
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        },
+        &secret,
+    );
+
     Ok(LoginResponse {
-        token: "mock-jwt-token".to_string(),
-        user_id: "user123".to_string(),
+        token,
+        user_id: user.id.to_string(),
+        session_id: session.id.to_string(),
+        expires_at: session.expires_at,
+        user,
     })
 }
 
 /// Handle user logout
+///
+/// Revokes the token's session. Expired tokens are accepted so a client can
+/// always sign out cleanly.
 #[tauri::command]
-pub async fn auth_logout() -> Result<(), String> {
+pub async fn auth_logout(token: String) -> Result<(), String> {
+    let secret = signing_secret(&AuthConfig::from_env()).await?;
+    let claims = tokens::verify(&token, &secret).map_err(|e| e.to_string())?;
+
+    sessions::revoke_session(claims.jti, Utc::now())
+        .await
+        .map_err(|e| format!("Failed to revoke session: {}", e))?;
+
     tracing::info!("User logged out");
     Ok(())
 }
 
 /// Check if user is authenticated
 #[tauri::command]
-pub async fn auth_check() -> Result<bool, String> {
-    // TODO: Implement actual token validation
-    Ok(false)
-}
\ No newline at end of file
+pub async fn auth_check(token: String) -> Result<bool, String> {
+    Ok(session_for_token(&token).await?.is_some())
+}
+
+/// List the active sessions of the token's user
+#[tauri::command]
+pub async fn auth_list_sessions(token: String) -> Result<Vec<AuthSession>, String> {
+    let session = require_session(&token).await?;
+
+    sessions::list_active_sessions(session.user_id, Utc::now())
+        .await
+        .map_err(|e| format!("Failed to list sessions: {}", e))
+}
+
+/// Revoke one of the token user's sessions, such as a login on another device
+#[tauri::command]
+pub async fn auth_revoke_session(token: String, session_id: String) -> Result<bool, String> {
+    let session = require_session(&token).await?;
+    let session_id = Uuid::parse_str(&session_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let target = sessions::find_session(session_id)
+        .await
+        .map_err(|e| format!("Failed to load session: {}", e))?;
+    if target.map(|target| target.user_id) != Some(session.user_id) {
+        return Err("Session not found".to_string());
+    }
+
+    sessions::revoke_session(session_id, Utc::now())
+        .await
+        .map_err(|e| format!("Failed to revoke session: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_utils::{pool, reset_all_tables};
+    use crate::handlers::users::create_user;
+    use crate::models::CreateUser;
+    use crate::session::SessionStore;
+    use anyhow::Result as AnyResult;
+    use serial_test::serial;
+
+    async fn login(email: &str, password: &str) -> Result<LoginResponse, String> {
+        let session = Session::for_tests("main", std::sync::Arc::new(SessionStore::new()));
+        auth_login(
+            session,
+            LoginRequest {
+                email: email.to_string(),
+                password: password.to_string(),
+                totp_code: None,
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn login_check_and_logout_follow_the_session() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        std::env::set_var("JWT_SECRET", "test-signing-secret");
+
+        let user = create_user(CreateUser {
+            email: "session@example.com".to_string(),
+            username: "session_user".to_string(),
+            password: "Sup3r$ecret".to_string(),
+            first_name: None,
+            last_name: None,
+        })
+        .await
+        .expect("user creation should succeed");
+
+        assert!(login("session@example.com", "wrong-password").await.is_err());
+
+        let first = login("session@example.com", "Sup3r$ecret").await.expect("login should succeed");
+        let second = login("session@example.com", "Sup3r$ecret").await.expect("login should succeed");
+        assert_eq!(first.user_id, user.id.to_string());
+        assert_eq!(first.user.email, user.email);
+        assert!(auth_check(first.token.clone()).await.unwrap());
+        assert!(!auth_check("not-a-token".to_string()).await.unwrap());
+
+        let active = auth_list_sessions(first.token.clone()).await.unwrap();
+        assert_eq!(active.len(), 2);
+
+        assert!(auth_revoke_session(first.token.clone(), second.session_id.clone())
+            .await
+            .unwrap());
+        assert!(!auth_check(second.token.clone()).await.unwrap());
+        assert!(auth_list_sessions(second.token).await.is_err());
+
+        auth_logout(first.token.clone()).await.expect("logout should succeed");
+        assert!(!auth_check(first.token).await.unwrap());
+
+        // Deactivating or deleting the account ends its sessions.
+        let third = login("session@example.com", "Sup3r$ecret").await.expect("login should succeed");
+        sqlx::query("UPDATE users SET is_active = false WHERE id = $1")
+            .bind(user.id)
+            .execute(pool.as_ref())
+            .await?;
+        assert!(!auth_check(third.token.clone()).await.unwrap());
+
+        sqlx::query("UPDATE users SET is_active = true, deleted_at = NOW() WHERE id = $1")
+            .bind(user.id)
+            .execute(pool.as_ref())
+            .await?;
+        assert!(!auth_check(third.token).await.unwrap());
+
+        std::env::remove_var("JWT_SECRET");
+        Ok(())
+    }
+}
@@ -0,0 +1,2 @@
+-- Reverts 001: Users table
+DROP TABLE IF EXISTS users;
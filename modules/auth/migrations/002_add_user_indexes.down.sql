@@ -0,0 +1,4 @@
+-- Reverts 002: User indexes
+DROP INDEX IF EXISTS idx_users_created_at;
+DROP INDEX IF EXISTS idx_users_username;
+DROP INDEX IF EXISTS idx_users_email;
@@ -0,0 +1,4 @@
+-- Reverts 006: User roles and permissions
+DROP TABLE IF EXISTS role_permissions;
+DROP INDEX IF EXISTS idx_users_role;
+ALTER TABLE users DROP COLUMN IF EXISTS role;
@@ -0,0 +1,25 @@
+-- =====================================================================
+-- 006: User roles and permissions
+-- =====================================================================
+-- Every user has one role; role_permissions lists what each role may do.
+-- Deployments can grant or revoke rows here without a code change.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role VARCHAR(32) NOT NULL DEFAULT 'user';
+
+CREATE INDEX IF NOT EXISTS idx_users_role ON users(role);
+
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role VARCHAR(32) NOT NULL,
+    permission VARCHAR(64) NOT NULL,
+    PRIMARY KEY (role, permission)
+);
+
+INSERT INTO role_permissions (role, permission) VALUES
+    ('admin', 'users:read'),
+    ('admin', 'users:write'),
+    ('admin', 'users:delete'),
+    ('admin', 'logs:read'),
+    ('admin', 'logs:purge'),
+    ('operator', 'users:read'),
+    ('operator', 'logs:read')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,3 @@
+-- Reverts 007: Account lockout
+ALTER TABLE users DROP COLUMN IF EXISTS locked_until;
+ALTER TABLE users DROP COLUMN IF EXISTS failed_login_attempts;
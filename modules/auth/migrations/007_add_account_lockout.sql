@@ -0,0 +1,8 @@
+-- =====================================================================
+-- 007: Account lockout
+-- =====================================================================
+-- Consecutive failed logins per account, and the time until which the
+-- account refuses logins after too many of them.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS failed_login_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locked_until TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,2 @@
+-- Reverts 008: Password reset tokens table
+DROP TABLE IF EXISTS password_reset_tokens;
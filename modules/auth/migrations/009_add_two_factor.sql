@@ -0,0 +1,20 @@
+-- =====================================================================
+-- 009: Two-factor authentication
+-- =====================================================================
+-- Whether a user logs in with a TOTP second factor, and the last time
+-- step accepted so a code cannot be used twice. The TOTP secrets live in
+-- the Stronghold vault, not here. Recovery codes are single-use and only
+-- their SHA-256 hashes are stored.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_last_step BIGINT;
+
+CREATE TABLE IF NOT EXISTS recovery_codes (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR(64) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    used_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_recovery_codes_user_id ON recovery_codes(user_id);
@@ -0,0 +1,3 @@
+-- Reverts 010: Email verification
+DROP TABLE IF EXISTS email_verification_tokens;
+ALTER TABLE users DROP COLUMN IF EXISTS email_verified;
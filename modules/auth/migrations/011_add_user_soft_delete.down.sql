@@ -0,0 +1,3 @@
+-- Reverts 011: User soft delete
+DROP INDEX IF EXISTS idx_users_deleted_at;
+ALTER TABLE users DROP COLUMN IF EXISTS deleted_at;
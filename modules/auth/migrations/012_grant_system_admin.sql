@@ -0,0 +1,23 @@
+-- =====================================================================
+-- 012: System administration permission
+-- =====================================================================
+-- Database credentials, migrations, the logging configuration and vault
+-- secrets require system:admin. Installs upgraded from before roles existed
+-- may have no admin at all, so the oldest active account is promoted to
+-- keep the application manageable.
+
+INSERT INTO role_permissions (role, permission) VALUES
+    ('admin', 'system:admin')
+ON CONFLICT DO NOTHING;
+
+UPDATE users SET role = 'admin'
+WHERE id = (
+    SELECT id FROM users
+    WHERE is_active = TRUE AND deleted_at IS NULL
+    ORDER BY created_at
+    LIMIT 1
+)
+AND NOT EXISTS (
+    SELECT 1 FROM users
+    WHERE role = 'admin' AND is_active = TRUE AND deleted_at IS NULL
+);
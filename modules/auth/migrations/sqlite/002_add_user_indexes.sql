@@ -0,0 +1,7 @@
+-- =====================================================================
+-- 002: User indexes (SQLite)
+-- =====================================================================
+
+CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);
+CREATE INDEX IF NOT EXISTS idx_users_username ON users(username);
+CREATE INDEX IF NOT EXISTS idx_users_created_at ON users(created_at);
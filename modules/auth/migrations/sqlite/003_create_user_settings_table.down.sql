@@ -0,0 +1,2 @@
+-- Reverts 003: User settings table (SQLite)
+DROP TABLE IF EXISTS user_settings;
@@ -0,0 +1,18 @@
+-- =====================================================================
+-- 003: User settings table (SQLite)
+-- =====================================================================
+-- User-specific preferences, one row per user. settings_data holds JSON text.
+
+CREATE TABLE IF NOT EXISTS user_settings (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    theme TEXT NOT NULL DEFAULT 'light',
+    language TEXT NOT NULL DEFAULT 'en',
+    notifications_enabled BOOLEAN NOT NULL DEFAULT 1,
+    settings_data TEXT NOT NULL DEFAULT '{}',
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    UNIQUE(user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_settings_user_id ON user_settings(user_id);
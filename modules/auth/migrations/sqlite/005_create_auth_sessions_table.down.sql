@@ -0,0 +1,2 @@
+-- Reverts 005: Auth sessions table (SQLite)
+DROP TABLE IF EXISTS auth_sessions;
@@ -0,0 +1,15 @@
+-- =====================================================================
+-- 005: Auth sessions table (SQLite)
+-- =====================================================================
+-- One row per issued token. The token's jti is the session id, so a
+-- session can be revoked before the token expires.
+
+CREATE TABLE IF NOT EXISTS auth_sessions (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    expires_at TEXT NOT NULL,
+    revoked_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_auth_sessions_user_id ON auth_sessions(user_id);
@@ -0,0 +1,8 @@
+-- =====================================================================
+-- 007: Account lockout (SQLite)
+-- =====================================================================
+-- Consecutive failed logins per account, and the time until which the
+-- account refuses logins after too many of them.
+
+ALTER TABLE users ADD COLUMN failed_login_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN locked_until TEXT;
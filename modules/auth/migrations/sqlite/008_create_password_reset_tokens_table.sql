@@ -0,0 +1,16 @@
+-- =====================================================================
+-- 008: Password reset tokens table (SQLite)
+-- =====================================================================
+-- One-time tokens for resetting a forgotten password. Only the SHA-256
+-- hash of each token is stored; a token is spent once used_at is set.
+
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    expires_at TEXT NOT NULL,
+    used_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_password_reset_tokens_user_id ON password_reset_tokens(user_id);
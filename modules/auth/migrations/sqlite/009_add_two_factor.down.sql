@@ -0,0 +1,4 @@
+-- Reverts 009: Two-factor authentication (SQLite)
+DROP TABLE IF EXISTS recovery_codes;
+ALTER TABLE users DROP COLUMN totp_last_step;
+ALTER TABLE users DROP COLUMN totp_enabled;
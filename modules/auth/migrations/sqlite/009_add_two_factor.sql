@@ -0,0 +1,20 @@
+-- =====================================================================
+-- 009: Two-factor authentication (SQLite)
+-- =====================================================================
+-- Whether a user logs in with a TOTP second factor, and the last time
+-- step accepted so a code cannot be used twice. The TOTP secrets live in
+-- the Stronghold vault, not here. Recovery codes are single-use and only
+-- their SHA-256 hashes are stored.
+
+ALTER TABLE users ADD COLUMN totp_enabled BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN totp_last_step INTEGER;
+
+CREATE TABLE IF NOT EXISTS recovery_codes (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    used_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_recovery_codes_user_id ON recovery_codes(user_id);
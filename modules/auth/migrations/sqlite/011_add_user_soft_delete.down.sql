@@ -0,0 +1,3 @@
+-- Reverts 011: User soft delete (SQLite)
+DROP INDEX IF EXISTS idx_users_deleted_at;
+ALTER TABLE users DROP COLUMN deleted_at;
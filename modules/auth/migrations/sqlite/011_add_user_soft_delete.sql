@@ -0,0 +1,9 @@
+-- =====================================================================
+-- 011: User soft delete (SQLite)
+-- =====================================================================
+-- Deleted accounts keep their row, with the time of deletion, until the
+-- retention period has passed and they are purged.
+
+ALTER TABLE users ADD COLUMN deleted_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_users_deleted_at ON users(deleted_at);
@@ -0,0 +1,3 @@
+-- Reverts 012: System administration permission (SQLite)
+-- A promoted account keeps its admin role.
+DELETE FROM role_permissions WHERE permission = 'system:admin';
@@ -5,13 +5,23 @@
 pub mod handlers;
 pub mod models;
 
+// Further sources live in `src/`, which the module CLI copies alongside these files.
+#[path = "src/sessions.rs"]
+pub mod sessions;
+#[path = "src/tokens.rs"]
+pub mod tokens;
+
 pub use handlers::*;
 pub use models::*;
 
 use serde::{Deserialize, Serialize};
+use std::env;
+
+use crate::stronghold;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AuthConfig {
+    /// HMAC key for session tokens; empty uses a generated key kept in the vault.
     pub jwt_secret: String,
     pub jwt_expiry_hours: u64,
     pub password_min_length: u8,
@@ -23,7 +33,7 @@ pub struct AuthConfig {
 impl Default for AuthConfig {
     fn default() -> Self {
         Self {
-            jwt_secret: "your-secret-key-here".to_string(),
+            jwt_secret: String::new(),
             jwt_expiry_hours: 24,
             password_min_length: 8,
             enable_registration: true,
@@ -33,6 +43,73 @@ impl Default for AuthConfig {
     }
 }
 
+impl AuthConfig {
+    /// Creates configuration from `JWT_*` and `AUTH_*` environment variables,
+    /// keeping the defaults for unset or unparseable values.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let parse_bool = |key: &str, default: bool| {
+            env::var(key)
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            jwt_secret: env::var("JWT_SECRET").unwrap_or(defaults.jwt_secret),
+            jwt_expiry_hours: env::var("JWT_EXPIRY_HOURS")
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+                .filter(|hours| *hours > 0)
+                .unwrap_or(defaults.jwt_expiry_hours),
+            password_min_length: env::var("AUTH_PASSWORD_MIN_LENGTH")
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(defaults.password_min_length),
+            enable_registration: parse_bool("AUTH_ENABLE_REGISTRATION", defaults.enable_registration),
+            require_email_verification: parse_bool(
+                "AUTH_REQUIRE_EMAIL_VERIFICATION",
+                defaults.require_email_verification,
+            ),
+            hash_algorithm: env::var("AUTH_HASH_ALGORITHM").unwrap_or(defaults.hash_algorithm),
+        }
+    }
+}
+
+/// Stronghold client and key of the generated token signing key.
+const SECRET_CLIENT: &str = "auth";
+const JWT_SECRET_KEY: &str = "jwt_secret";
+
+/// Returns the key session tokens are signed with.
+///
+/// Uses `jwt_secret` when configured; otherwise a random key is generated on
+/// first use and kept in the Stronghold vault.
+pub async fn signing_secret(config: &AuthConfig) -> Result<Vec<u8>, String> {
+    if !config.jwt_secret.is_empty() {
+        return Ok(config.jwt_secret.as_bytes().to_vec());
+    }
+
+    let vault = stronghold::vault()
+        .map_err(|e| format!("JWT_SECRET is not set and no vault is available: {}", e))?;
+    let manager = vault.lock().await;
+
+    if let Some(secret) = manager
+        .get_secret(SECRET_CLIENT, JWT_SECRET_KEY)
+        .map_err(|e| format!("Failed to read signing key: {}", e))?
+    {
+        return Ok(secret);
+    }
+
+    let mut secret = vec![0u8; 64];
+    rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut secret);
+    manager
+        .put_secret(SECRET_CLIENT, JWT_SECRET_KEY, secret.clone())
+        .and_then(|_| manager.save())
+        .map_err(|e| format!("Failed to store signing key: {}", e))?;
+
+    Ok(secret)
+}
+
 /// Initialize the auth module
 pub fn init() {
     tracing::info!("Authentication module initialized");
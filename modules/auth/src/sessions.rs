@@ -0,0 +1,104 @@
+//! Persistence for issued auth sessions.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::models::AuthSession;
+use crate::database::{get_pool_ref, with_pool};
+
+const SESSION_COLUMNS: &str = "id, user_id, created_at, expires_at, revoked_at";
+
+/// Records a new session for `user_id`.
+pub async fn create_session(
+    user_id: Uuid,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+) -> Result<AuthSession> {
+    let pool = get_pool_ref()?;
+    let sql = format!(
+        "INSERT INTO auth_sessions (id, user_id, created_at, expires_at)
+         VALUES ($1, $2, $3, $4)
+         RETURNING {}",
+        SESSION_COLUMNS
+    );
+
+    let session = with_pool!(pool.as_ref(), |pool| sqlx::query_as::<_, AuthSession>(&sql)
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(created_at)
+        .bind(expires_at)
+        .fetch_one(pool)
+        .await)?;
+
+    Ok(session)
+}
+
+/// Finds a session by id, whether or not it is still active.
+pub async fn find_session(id: Uuid) -> Result<Option<AuthSession>> {
+    let pool = get_pool_ref()?;
+    let sql = format!("SELECT {} FROM auth_sessions WHERE id = $1", SESSION_COLUMNS);
+
+    let session = with_pool!(pool.as_ref(), |pool| sqlx::query_as::<_, AuthSession>(&sql)
+        .bind(id)
+        .fetch_optional(pool)
+        .await)?;
+
+    Ok(session)
+}
+
+/// Finds session `id` of `user_id` if it is unrevoked and unexpired at `now`
+/// and belongs to an active account that has not been deleted.
+pub async fn find_active_session(id: Uuid, user_id: Uuid, now: DateTime<Utc>) -> Result<Option<AuthSession>> {
+    let pool = get_pool_ref()?;
+    let sql = "SELECT s.id, s.user_id, s.created_at, s.expires_at, s.revoked_at
+         FROM auth_sessions s
+         JOIN users u ON u.id = s.user_id
+         WHERE s.id = $1 AND s.user_id = $2 AND s.revoked_at IS NULL AND s.expires_at > $3
+           AND u.deleted_at IS NULL AND u.is_active = $4";
+
+    let session = with_pool!(pool.as_ref(), |pool| sqlx::query_as::<_, AuthSession>(sql)
+        .bind(id)
+        .bind(user_id)
+        .bind(now)
+        .bind(true)
+        .fetch_optional(pool)
+        .await)?;
+
+    Ok(session)
+}
+
+/// Lists the unrevoked, unexpired sessions of a user, newest first.
+pub async fn list_active_sessions(user_id: Uuid, now: DateTime<Utc>) -> Result<Vec<AuthSession>> {
+    let pool = get_pool_ref()?;
+    let sql = format!(
+        "SELECT {} FROM auth_sessions
+         WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > $2
+         ORDER BY created_at DESC",
+        SESSION_COLUMNS
+    );
+
+    let sessions = with_pool!(pool.as_ref(), |pool| sqlx::query_as::<_, AuthSession>(&sql)
+        .bind(user_id)
+        .bind(now)
+        .fetch_all(pool)
+        .await)?;
+
+    Ok(sessions)
+}
+
+/// Revokes a session, returning whether it was active.
+pub async fn revoke_session(id: Uuid, now: DateTime<Utc>) -> Result<bool> {
+    let pool = get_pool_ref()?;
+
+    let rows_affected = with_pool!(pool.as_ref(), |pool| sqlx::query(
+        "UPDATE auth_sessions SET revoked_at = $2 WHERE id = $1 AND revoked_at IS NULL"
+    )
+    .bind(id)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map(|result| result.rows_affected()))?;
+
+    Ok(rows_affected > 0)
+}
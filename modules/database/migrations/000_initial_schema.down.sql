@@ -0,0 +1,2 @@
+-- Reverts 000: Initial schema
+DROP EXTENSION IF EXISTS "uuid-ossp";
@@ -0,0 +1,7 @@
+-- =====================================================================
+-- 000: Initial schema
+-- =====================================================================
+-- Enables the extensions every other module relies on.
+
+-- Enable UUID extension for generating unique identifiers
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
@@ -0,0 +1,2 @@
+-- Reverts 000: Initial schema (SQLite)
+SELECT 1;
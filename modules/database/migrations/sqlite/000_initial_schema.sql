@@ -0,0 +1,7 @@
+-- =====================================================================
+-- 000: Initial schema (SQLite)
+-- =====================================================================
+-- SQLite needs no extensions: UUIDs are generated by the application and
+-- stored as 16-byte blobs. Kept so versions match the PostgreSQL ledger.
+
+SELECT 1;
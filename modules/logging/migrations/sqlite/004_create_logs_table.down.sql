@@ -0,0 +1,2 @@
+-- Reverts 004: Application logs table (SQLite)
+DROP TABLE IF EXISTS app_logs;
@@ -0,0 +1,17 @@
+-- =====================================================================
+-- 004: Application logs table (SQLite)
+-- =====================================================================
+-- Centralized logging for application events and errors. metadata holds JSON text.
+
+CREATE TABLE IF NOT EXISTS app_logs (
+    id BLOB PRIMARY KEY NOT NULL,
+    level TEXT NOT NULL,
+    message TEXT NOT NULL,
+    metadata TEXT NOT NULL DEFAULT '{}',
+    user_id BLOB REFERENCES users(id) ON DELETE SET NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_app_logs_level ON app_logs(level);
+CREATE INDEX IF NOT EXISTS idx_app_logs_created_at ON app_logs(created_at);
+CREATE INDEX IF NOT EXISTS idx_app_logs_user_id ON app_logs(user_id);
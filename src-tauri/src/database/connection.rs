@@ -2,26 +2,27 @@
 
 use anyhow::Result;
 use once_cell::sync::OnceCell;
-use sqlx::PgPool;
 use std::sync::{Arc, RwLock};
 use crate::stronghold::StrongholdManager;
 use crate::config::AppConfig;
+use super::DbPool;
 
 /// Global connection pool storage using OnceCell for thread-safe initialization.
-static POOL: OnceCell<RwLock<Option<Arc<PgPool>>>> = OnceCell::new();
+static POOL: OnceCell<RwLock<Option<Arc<DbPool>>>> = OnceCell::new();
 
 /// Returns the global pool slot, initializing it if necessary.
-fn pool_slot() -> &'static RwLock<Option<Arc<PgPool>>> {
+fn pool_slot() -> &'static RwLock<Option<Arc<DbPool>>> {
     POOL.get_or_init(|| RwLock::new(None))
 }
 
-/// Initializes the database connection using Stronghold for secure credential storage.
-/// Currently uses direct config access as a fallback.
-pub async fn initialize_database(_stronghold: &mut StrongholdManager) -> Result<()> {
+/// Initializes the database connection with credentials from the Stronghold vault.
+///
+/// A `DATABASE_URL` from the environment is moved into the vault on first use.
+pub async fn initialize_database(stronghold: &mut StrongholdManager) -> Result<()> {
     let config = AppConfig::from_env();
-    let db_url = config.database_url.clone();
+    let db_url = super::credentials::resolve_database_url(stronghold, &config)?;
 
-    let pool = super::create_pool_with_url(&db_url).await?;
+    let pool = super::create_pool_with_config(&db_url, &config.database_pool).await?;
     super::test_connection(&pool).await?;
 
     let arc = Arc::new(pool);
@@ -34,7 +35,7 @@ pub async fn initialize_database(_stronghold: &mut StrongholdManager) -> Result<
 }
 
 /// Initializes the global connection pool with a pre-created pool.
-pub async fn initialize_pool(pool: PgPool) {
+pub async fn initialize_pool(pool: DbPool) {
     let arc = Arc::new(pool);
     if let Ok(mut guard) = pool_slot().write() {
         *guard = Some(arc);
@@ -42,7 +43,7 @@ pub async fn initialize_pool(pool: PgPool) {
 }
 
 /// Returns the current database connection pool if initialized.
-pub fn get_pool() -> Option<Arc<PgPool>> {
+pub fn get_pool() -> Option<Arc<DbPool>> {
     pool_slot()
         .read()
         .ok()
@@ -50,7 +51,7 @@ pub fn get_pool() -> Option<Arc<PgPool>> {
 }
 
 /// Returns the database connection pool or an error if not initialized.
-pub fn get_pool_ref() -> Result<Arc<PgPool>> {
+pub fn get_pool_ref() -> Result<Arc<DbPool>> {
     get_pool().ok_or_else(|| anyhow::anyhow!("Database pool not initialized"))
 }
 
@@ -1,66 +1,441 @@
-//! Database migration management for creating and maintaining schema.
+//! Versioned database migrations loaded from module manifests.
+//!
+//! Every module lists its migration files in the `migrations` array of its
+//! `module.json`. The manifests and SQL files are embedded at compile time, ordered
+//! by their numeric version prefix and applied once each inside a transaction.
+//! Applied migrations are recorded in the `schema_migrations` ledger with a SHA-256
+//! checksum so an edit to an already-applied file is reported instead of ignored.
+//!
+//! Each script may have a paired `<stem>.down.sql` that reverses it, which
+//! [`rollback_migrations`] uses to move the schema back to an earlier version.
+//!
+//! Versions are global across modules: two files may not share a prefix. SQLite
+//! uses the same versions with dialect-specific scripts from `migrations/sqlite`.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeMap, HashMap};
+
+use super::DatabaseBackend;
+
+/// Advisory lock key that serializes migration runs across processes.
+const MIGRATION_LOCK_KEY: i64 = 4_815_162_342;
+
+/// Module manifests whose migration lists make up the application schema.
+const MODULE_MANIFESTS: &[&str] = &[
+    include_str!("../../../modules/database/module.json"),
+    include_str!("../../../modules/auth/module.json"),
+    include_str!("../../../modules/logging/module.json"),
+];
+
+/// Embeds a migration and its paired down script from a module's migration directory.
+macro_rules! migration_source {
+    ($module:literal, $stem:literal) => {
+        migration_source!($module, "migrations", $stem)
+    };
+    ($module:literal, $dir:literal, $stem:literal) => {
+        (
+            concat!($stem, ".sql"),
+            include_str!(concat!("../../../modules/", $module, "/", $dir, "/", $stem, ".sql")),
+            Some(include_str!(concat!(
+                "../../../modules/",
+                $module,
+                "/",
+                $dir,
+                "/",
+                $stem,
+                ".down.sql"
+            ))),
+        )
+    };
+}
+
+/// Embedded up and down SQL for every migration file referenced by a module manifest.
+const MIGRATION_SOURCES: &[(&str, &str, Option<&str>)] = &[
+    migration_source!("database", "000_initial_schema"),
+    migration_source!("auth", "001_create_users_table"),
+    migration_source!("auth", "002_add_user_indexes"),
+    migration_source!("auth", "003_create_user_settings_table"),
+    migration_source!("logging", "004_create_logs_table"),
+    migration_source!("auth", "005_create_auth_sessions_table"),
+    migration_source!("auth", "006_add_user_roles"),
+    migration_source!("auth", "007_add_account_lockout"),
+    migration_source!("auth", "008_create_password_reset_tokens_table"),
+    migration_source!("auth", "009_add_two_factor"),
+    migration_source!("auth", "010_add_email_verification"),
+    migration_source!("auth", "011_add_user_soft_delete"),
+    migration_source!("auth", "012_grant_system_admin"),
+];
+
+/// SQLite dialect of [`MIGRATION_SOURCES`], with the same file names and versions.
+#[cfg(feature = "module-sqlite")]
+const SQLITE_MIGRATION_SOURCES: &[(&str, &str, Option<&str>)] = &[
+    migration_source!("database", "migrations/sqlite", "000_initial_schema"),
+    migration_source!("auth", "migrations/sqlite", "001_create_users_table"),
+    migration_source!("auth", "migrations/sqlite", "002_add_user_indexes"),
+    migration_source!("auth", "migrations/sqlite", "003_create_user_settings_table"),
+    migration_source!("logging", "migrations/sqlite", "004_create_logs_table"),
+    migration_source!("auth", "migrations/sqlite", "005_create_auth_sessions_table"),
+    migration_source!("auth", "migrations/sqlite", "006_add_user_roles"),
+    migration_source!("auth", "migrations/sqlite", "007_add_account_lockout"),
+    migration_source!("auth", "migrations/sqlite", "008_create_password_reset_tokens_table"),
+    migration_source!("auth", "migrations/sqlite", "009_add_two_factor"),
+    migration_source!("auth", "migrations/sqlite", "010_add_email_verification"),
+    migration_source!("auth", "migrations/sqlite", "011_add_user_soft_delete"),
+    migration_source!("auth", "migrations/sqlite", "012_grant_system_admin"),
+];
+
+/// Ledger table recording which migrations have been applied.
+pub(crate) const CREATE_LEDGER: &str = r#"CREATE TABLE IF NOT EXISTS schema_migrations (
+    version BIGINT PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    module_id VARCHAR(100) NOT NULL,
+    checksum CHAR(64) NOT NULL,
+    applied_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+)"#;
+
+/// Subset of `module.json` needed to discover migrations.
+#[derive(Debug, Deserialize)]
+struct ModuleManifest {
+    id: String,
+    #[serde(default)]
+    migrations: Vec<String>,
+}
+
+/// A versioned migration ready to be applied.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub version: i64,
+    pub name: String,
+    pub module_id: String,
+    pub sql: &'static str,
+    pub down_sql: Option<&'static str>,
+    pub checksum: String,
+}
 
-use anyhow::Result;
-use sqlx::PgPool;
+/// A row of the `schema_migrations` ledger.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub module_id: String,
+    pub checksum: String,
+    pub applied_at: DateTime<Utc>,
+}
 
-/// Runs all database migrations to set up the application schema.
+/// Computes the checksum stored in the ledger for a migration script.
 ///
-/// Creates tables for users, user settings, and application logs along with
-/// necessary indexes for performance. In production, consider using sqlx-cli
-/// for more sophisticated migration management.
-pub async fn run_migrations(pool: &PgPool) -> Result<()> {
-    let migrations = [
-        r#"CREATE EXTENSION IF NOT EXISTS "uuid-ossp""#,
-
-        r#"CREATE TABLE IF NOT EXISTS users (
-            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
-            email VARCHAR(255) UNIQUE NOT NULL,
-            username VARCHAR(100) UNIQUE NOT NULL,
-            password_hash VARCHAR(255) NOT NULL,
-            first_name VARCHAR(100),
-            last_name VARCHAR(100),
-            is_active BOOLEAN DEFAULT true,
-            created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
-            updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
-        )"#,
-
-        r#"CREATE TABLE IF NOT EXISTS user_settings (
-            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
-            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-            theme VARCHAR(20) DEFAULT 'light',
-            language VARCHAR(10) DEFAULT 'en',
-            notifications_enabled BOOLEAN DEFAULT true,
-            settings_data JSONB DEFAULT '{}',
-            created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
-            updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
-            UNIQUE(user_id)
-        )"#,
-
-        r#"CREATE TABLE IF NOT EXISTS app_logs (
-            id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
-            level VARCHAR(20) NOT NULL,
-            message TEXT NOT NULL,
-            metadata JSONB DEFAULT '{}',
-            user_id UUID REFERENCES users(id) ON DELETE SET NULL,
-            created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
-        )"#,
-
-        r#"CREATE INDEX IF NOT EXISTS idx_users_email ON users(email)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_users_username ON users(username)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_users_created_at ON users(created_at)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_user_settings_user_id ON user_settings(user_id)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_app_logs_level ON app_logs(level)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_app_logs_created_at ON app_logs(created_at)"#,
-        r#"CREATE INDEX IF NOT EXISTS idx_app_logs_user_id ON app_logs(user_id)"#,
-    ];
+/// Line endings are normalized first so a Windows checkout with CRLF endings
+/// produces the same checksum as the file that was originally applied.
+pub fn checksum(sql: &str) -> String {
+    let normalized = sql.replace("\r\n", "\n");
+    let digest = Sha256::digest(normalized.as_bytes());
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Splits a file name such as `001_create_users_table.sql` into its version and name.
+fn parse_file_name(file_name: &str) -> Result<(i64, String)> {
+    let stem = file_name
+        .strip_suffix(".sql")
+        .ok_or_else(|| anyhow!("Migration file '{}' must end in .sql", file_name))?;
+    let (version, name) = stem
+        .split_once('_')
+        .ok_or_else(|| anyhow!("Migration file '{}' must be named <version>_<name>.sql", file_name))?;
+    let version = version
+        .parse::<i64>()
+        .with_context(|| format!("Migration file '{}' has a non-numeric version", file_name))?;
+
+    Ok((version, name.to_string()))
+}
+
+/// Loads every migration listed in the embedded module manifests, sorted by version.
+pub fn load_migrations(backend: DatabaseBackend) -> Result<Vec<Migration>> {
+    let sources = match backend {
+        DatabaseBackend::Postgres => MIGRATION_SOURCES,
+        #[cfg(feature = "module-sqlite")]
+        DatabaseBackend::Sqlite => SQLITE_MIGRATION_SOURCES,
+        #[cfg(not(feature = "module-sqlite"))]
+        DatabaseBackend::Sqlite => bail!("SQLite migrations require the module-sqlite feature"),
+    };
+    let sources: HashMap<&str, (&'static str, Option<&'static str>)> = sources
+        .iter()
+        .map(|(file_name, up, down)| (*file_name, (*up, *down)))
+        .collect();
+    let mut migrations: BTreeMap<i64, Migration> = BTreeMap::new();
+
+    for manifest in MODULE_MANIFESTS {
+        let manifest: ModuleManifest =
+            serde_json::from_str(manifest).context("Failed to parse module manifest")?;
+
+        for file_name in &manifest.migrations {
+            let (sql, down_sql) = sources.get(file_name.as_str()).copied().ok_or_else(|| {
+                anyhow!(
+                    "Module '{}' lists migration '{}' but no SQL file is embedded for it",
+                    manifest.id,
+                    file_name
+                )
+            })?;
+            let (version, name) = parse_file_name(file_name)?;
+
+            if let Some(existing) = migrations.get(&version) {
+                bail!(
+                    "Migration version {} is used by both '{}' ({}) and '{}' ({})",
+                    version,
+                    existing.name,
+                    existing.module_id,
+                    name,
+                    manifest.id
+                );
+            }
+
+            migrations.insert(
+                version,
+                Migration {
+                    version,
+                    name,
+                    module_id: manifest.id.clone(),
+                    sql,
+                    down_sql,
+                    checksum: checksum(sql),
+                },
+            );
+        }
+    }
+
+    Ok(migrations.into_values().collect())
+}
+
+/// Verifies the ledger against the known migrations and returns those still to apply.
+///
+/// Fails if an applied migration's checksum no longer matches its script.
+pub(crate) fn pending_migrations<'a>(
+    migrations: &'a [Migration],
+    applied: &[AppliedMigration],
+) -> Result<Vec<&'a Migration>> {
+    let applied: HashMap<i64, &AppliedMigration> = applied
+        .iter()
+        .map(|migration| (migration.version, migration))
+        .collect();
 
     for migration in migrations {
-        sqlx::query(migration).execute(pool).await?;
+        if let Some(record) = applied.get(&migration.version) {
+            if record.checksum != migration.checksum {
+                bail!(
+                    "Checksum mismatch for migration {:03}_{}: the script changed after it was applied",
+                    migration.version,
+                    migration.name
+                );
+            }
+        }
+    }
+
+    for version in applied.keys() {
+        if !migrations.iter().any(|migration| migration.version == *version) {
+            tracing::warn!(
+                "Database has migration {} applied that this build does not know about",
+                version
+            );
+        }
+    }
+
+    Ok(migrations
+        .iter()
+        .filter(|migration| !applied.contains_key(&migration.version))
+        .collect())
+}
+
+/// Returns the migrations to revert, newest first, to reach `target_version`.
+///
+/// Fails if any of them is unknown to this build, has no down script or has a
+/// checksum that no longer matches, so a rollback never stops halfway.
+pub(crate) fn rollback_plan<'a>(
+    migrations: &'a [Migration],
+    applied: &[AppliedMigration],
+    target_version: i64,
+) -> Result<Vec<&'a Migration>> {
+    let mut to_revert = Vec::new();
+
+    for record in applied.iter().rev().filter(|record| record.version > target_version) {
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.version == record.version)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Cannot roll back migration {:03}_{}: it is not known to this build",
+                    record.version,
+                    record.name
+                )
+            })?;
+        if migration.down_sql.is_none() {
+            bail!(
+                "Cannot roll back migration {:03}_{}: it has no down script",
+                migration.version,
+                migration.name
+            );
+        }
+        if record.checksum != migration.checksum {
+            bail!(
+                "Checksum mismatch for migration {:03}_{}: the script changed after it was applied",
+                migration.version,
+                migration.name
+            );
+        }
+        to_revert.push(migration);
+    }
+
+    Ok(to_revert)
+}
+
+/// Returns the ledger of applied migrations, ordered by version.
+pub async fn applied_migrations(pool: &PgPool) -> Result<Vec<AppliedMigration>> {
+    sqlx::query(CREATE_LEDGER).execute(pool).await?;
+
+    let applied = sqlx::query_as::<_, AppliedMigration>(
+        "SELECT version, name, module_id, checksum, applied_at
+         FROM schema_migrations
+         ORDER BY version",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(applied)
+}
+
+/// Runs all pending migrations to bring the schema up to date.
+///
+/// Each migration runs in its own transaction together with its ledger insert,
+/// so a failing script leaves neither a partial schema change nor a ledger row.
+/// Fails without applying anything if an applied migration's checksum no
+/// longer matches the embedded script.
+pub async fn run_migrations(pool: &PgPool) -> Result<()> {
+    let migrations = load_migrations(DatabaseBackend::Postgres)?;
+    let applied = applied_migrations(pool).await?;
+
+    for migration in pending_migrations(&migrations, &applied)? {
+        apply_migration(pool, migration).await?;
     }
 
     Ok(())
 }
 
+/// Applies a single migration and records it in the ledger atomically.
+async fn apply_migration(pool: &PgPool, migration: &Migration) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *tx)
+        .await?;
+
+    // Another process may have applied it while we waited for the lock.
+    let already_applied: Option<i64> =
+        sqlx::query_scalar("SELECT version FROM schema_migrations WHERE version = $1")
+            .bind(migration.version)
+            .fetch_optional(&mut *tx)
+            .await?;
+    if already_applied.is_some() {
+        return Ok(());
+    }
+
+    sqlx::raw_sql(migration.sql)
+        .execute(&mut *tx)
+        .await
+        .with_context(|| {
+            format!(
+                "Migration {:03}_{} failed",
+                migration.version, migration.name
+            )
+        })?;
+
+    sqlx::query(
+        "INSERT INTO schema_migrations (version, name, module_id, checksum)
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(migration.version)
+    .bind(&migration.name)
+    .bind(&migration.module_id)
+    .bind(&migration.checksum)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    tracing::info!(
+        "Applied migration {:03}_{} ({})",
+        migration.version,
+        migration.name,
+        migration.module_id
+    );
+
+    Ok(())
+}
+
+/// Rolls the schema back to `target_version` by running down scripts in reverse order.
+///
+/// Every applied migration with a version above the target is reverted, each in
+/// its own transaction together with the removal of its ledger row. Nothing is
+/// reverted if any of those migrations is unknown to this build, has no down
+/// script or has a checksum that no longer matches. Pass a negative target to
+/// revert everything. Returns the reverted versions in the order they ran.
+pub async fn rollback_migrations(pool: &PgPool, target_version: i64) -> Result<Vec<i64>> {
+    let migrations = load_migrations(DatabaseBackend::Postgres)?;
+    let applied = applied_migrations(pool).await?;
+
+    let mut reverted = Vec::new();
+    for migration in rollback_plan(&migrations, &applied, target_version)? {
+        revert_migration(pool, migration).await?;
+        reverted.push(migration.version);
+    }
+
+    Ok(reverted)
+}
+
+/// Runs a migration's down script and removes it from the ledger atomically.
+async fn revert_migration(pool: &PgPool, migration: &Migration) -> Result<()> {
+    let down_sql = migration
+        .down_sql
+        .ok_or_else(|| anyhow!("Migration {:03}_{} has no down script", migration.version, migration.name))?;
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::raw_sql(down_sql)
+        .execute(&mut *tx)
+        .await
+        .with_context(|| {
+            format!(
+                "Rollback of migration {:03}_{} failed",
+                migration.version, migration.name
+            )
+        })?;
+
+    let removed = sqlx::query("DELETE FROM schema_migrations WHERE version = $1")
+        .bind(migration.version)
+        .execute(&mut *tx)
+        .await?;
+    if removed.rows_affected() == 0 {
+        // Another process reverted it while we waited for the lock.
+        tx.rollback().await?;
+        return Ok(());
+    }
+
+    tx.commit().await?;
+    tracing::info!(
+        "Rolled back migration {:03}_{} ({})",
+        migration.version,
+        migration.name,
+        migration.module_id
+    );
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,7 +471,17 @@ mod tests {
         .map(|row| row.get::<String, _>(0))
         .collect();
 
-        let expected_tables = vec!["app_logs", "user_settings", "users"];
+        let expected_tables = vec![
+            "app_logs",
+            "auth_sessions",
+            "email_verification_tokens",
+            "password_reset_tokens",
+            "recovery_codes",
+            "role_permissions",
+            "schema_migrations",
+            "user_settings",
+            "users",
+        ];
         assert_eq!(tables, expected_tables);
 
         Ok(())
@@ -133,9 +518,15 @@ mod tests {
             "idx_app_logs_created_at",
             "idx_app_logs_level",
             "idx_app_logs_user_id",
+            "idx_auth_sessions_user_id",
+            "idx_email_verification_tokens_user_id",
+            "idx_password_reset_tokens_user_id",
+            "idx_recovery_codes_user_id",
             "idx_user_settings_user_id",
             "idx_users_created_at",
+            "idx_users_deleted_at",
             "idx_users_email",
+            "idx_users_role",
             "idx_users_username",
         ];
 
@@ -169,7 +560,168 @@ mod tests {
         .await?
         .get(0);
 
-        assert_eq!(table_count, 3);
+        assert_eq!(table_count, 9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn manifests_yield_unique_sorted_versions() {
+        let migrations = load_migrations(DatabaseBackend::Postgres).expect("embedded manifests should load");
+        let versions: Vec<i64> = migrations.iter().map(|m| m.version).collect();
+
+        assert_eq!(versions, vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+        assert_eq!(migrations[1].name, "create_users_table");
+        assert_eq!(migrations[1].module_id, "auth");
+        assert!(migrations.iter().all(|m| m.checksum.len() == 64));
+    }
+
+    #[test]
+    fn checksum_ignores_line_ending_style() {
+        assert_eq!(checksum("SELECT 1;\r\nSELECT 2;\r\n"), checksum("SELECT 1;\nSELECT 2;\n"));
+        assert_ne!(checksum("SELECT 1;"), checksum("SELECT 2;"));
+    }
+
+    #[test]
+    fn parse_file_name_rejects_malformed_names() {
+        assert_eq!(
+            parse_file_name("002_add_user_indexes.sql").unwrap(),
+            (2, "add_user_indexes".to_string())
+        );
+        assert!(parse_file_name("add_user_indexes.sql").is_err());
+        assert!(parse_file_name("abc_add_user_indexes.sql").is_err());
+        assert!(parse_file_name("002_add_user_indexes.txt").is_err());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn ledger_records_each_migration_once() -> AnyResult<()> {
+        let pool = pool().await?;
+        sqlx::query("DROP SCHEMA public CASCADE")
+            .execute(pool.as_ref())
+            .await?;
+        sqlx::query("CREATE SCHEMA public")
+            .execute(pool.as_ref())
+            .await?;
+
+        run_migrations(pool.as_ref()).await?;
+        run_migrations(pool.as_ref()).await?;
+
+        let applied = applied_migrations(pool.as_ref()).await?;
+        let expected = load_migrations(DatabaseBackend::Postgres)?;
+
+        assert_eq!(applied.len(), expected.len());
+        for (record, migration) in applied.iter().zip(expected.iter()) {
+            assert_eq!(record.version, migration.version);
+            assert_eq!(record.name, migration.name);
+            assert_eq!(record.checksum, migration.checksum);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn modified_migration_is_rejected() -> AnyResult<()> {
+        let pool = pool().await?;
+        sqlx::query("DROP SCHEMA public CASCADE")
+            .execute(pool.as_ref())
+            .await?;
+        sqlx::query("CREATE SCHEMA public")
+            .execute(pool.as_ref())
+            .await?;
+
+        run_migrations(pool.as_ref()).await?;
+
+        // Simulate the script for version 1 having been edited after it was applied
+        sqlx::query("UPDATE schema_migrations SET checksum = $1 WHERE version = 1")
+            .bind("0".repeat(64))
+            .execute(pool.as_ref())
+            .await?;
+
+        let error = run_migrations(pool.as_ref())
+            .await
+            .expect_err("checksum mismatch should fail the run");
+        assert!(error.to_string().contains("Checksum mismatch"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn every_migration_has_a_down_script() {
+        let migrations = load_migrations(DatabaseBackend::Postgres).expect("embedded manifests should load");
+        assert!(migrations.iter().all(|m| m.down_sql.is_some()));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn rollback_reverts_to_target_and_reapplies() -> AnyResult<()> {
+        let pool = pool().await?;
+        sqlx::query("DROP SCHEMA public CASCADE")
+            .execute(pool.as_ref())
+            .await?;
+        sqlx::query("CREATE SCHEMA public")
+            .execute(pool.as_ref())
+            .await?;
+
+        run_migrations(pool.as_ref()).await?;
+
+        let reverted = rollback_migrations(pool.as_ref(), 2).await?;
+        assert_eq!(reverted, vec![12, 11, 10, 9, 8, 7, 6, 5, 4, 3]);
+
+        let tables: Vec<String> = sqlx::query(
+            "SELECT table_name FROM information_schema.tables
+             WHERE table_schema = 'public' AND table_type = 'BASE TABLE'
+             ORDER BY table_name"
+        )
+        .fetch_all(pool.as_ref())
+        .await?
+        .into_iter()
+        .map(|row| row.get::<String, _>(0))
+        .collect();
+        assert_eq!(tables, vec!["schema_migrations", "users"]);
+
+        let versions: Vec<i64> = applied_migrations(pool.as_ref())
+            .await?
+            .into_iter()
+            .map(|m| m.version)
+            .collect();
+        assert_eq!(versions, vec![0, 1, 2]);
+
+        // Rolling back to the current version is a no-op
+        assert!(rollback_migrations(pool.as_ref(), 2).await?.is_empty());
+
+        run_migrations(pool.as_ref()).await?;
+        assert_eq!(applied_migrations(pool.as_ref()).await?.len(), 13);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn rollback_refuses_unknown_migrations() -> AnyResult<()> {
+        let pool = pool().await?;
+        sqlx::query("DROP SCHEMA public CASCADE")
+            .execute(pool.as_ref())
+            .await?;
+        sqlx::query("CREATE SCHEMA public")
+            .execute(pool.as_ref())
+            .await?;
+
+        run_migrations(pool.as_ref()).await?;
+        sqlx::query(
+            "INSERT INTO schema_migrations (version, name, module_id, checksum)
+             VALUES (999, 'from_a_newer_build', 'auth', $1)"
+        )
+        .bind("0".repeat(64))
+        .execute(pool.as_ref())
+        .await?;
+
+        let error = rollback_migrations(pool.as_ref(), 3)
+            .await
+            .expect_err("unknown migration should block the rollback");
+        assert!(error.to_string().contains("not known to this build"));
+        assert_eq!(applied_migrations(pool.as_ref()).await?.len(), 14);
 
         Ok(())
     }
@@ -214,6 +766,13 @@ mod tests {
             ("is_active".to_string(), "boolean".to_string(), "YES".to_string()),
             ("created_at".to_string(), "timestamp with time zone".to_string(), "YES".to_string()),
             ("updated_at".to_string(), "timestamp with time zone".to_string(), "YES".to_string()),
+            ("role".to_string(), "character varying".to_string(), "NO".to_string()),
+            ("failed_login_attempts".to_string(), "integer".to_string(), "NO".to_string()),
+            ("locked_until".to_string(), "timestamp with time zone".to_string(), "YES".to_string()),
+            ("totp_enabled".to_string(), "boolean".to_string(), "NO".to_string()),
+            ("totp_last_step".to_string(), "bigint".to_string(), "YES".to_string()),
+            ("email_verified".to_string(), "boolean".to_string(), "NO".to_string()),
+            ("deleted_at".to_string(), "timestamp with time zone".to_string(), "YES".to_string()),
         ];
 
         assert_eq!(columns, expected_structure);
@@ -1,47 +1,214 @@
 //! Database connection management and utilities.
 //!
 //! Provides PostgreSQL connection pooling, migrations, and database utilities
-//! with environment-aware configuration.
+//! with environment-aware configuration. With the `module-sqlite` feature an
+//! embedded SQLite database can be used instead; the scheme of `DATABASE_URL`
+//! selects the backend.
 
-use anyhow::Result;
-use sqlx::{postgres::PgPoolOptions, PgPool};
-use std::time::Duration;
-use crate::config::AppConfig;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::PgPool;
+use std::str::FromStr;
+use crate::config::{AppConfig, DatabasePoolConfig};
 
 pub mod connection;
+pub mod credentials;
 pub mod migrations;
+#[cfg(feature = "module-sqlite")]
+pub mod sqlite;
+pub mod supervisor;
 #[cfg(test)]
 pub mod test_utils;
 
 pub use connection::*;
 
-/// Creates a database connection pool using configuration from environment.
-pub async fn create_pool() -> Result<PgPool> {
+/// Database engines the application can store its data in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseBackend {
+    Postgres,
+    Sqlite,
+}
+
+impl DatabaseBackend {
+    /// Determines the backend from the scheme of a connection URL.
+    pub fn from_url(database_url: &str) -> Result<Self> {
+        let scheme = database_url
+            .split_once(':')
+            .map(|(scheme, _)| scheme.to_lowercase())
+            .unwrap_or_default();
+
+        match scheme.as_str() {
+            "postgres" | "postgresql" => Ok(Self::Postgres),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => bail!("Unsupported database URL scheme '{}'", scheme),
+        }
+    }
+}
+
+/// Point-in-time connection counts for a pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStats {
+    /// Open connections, idle or in use.
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    pub max_connections: u32,
+}
+
+impl PoolStats {
+    fn of<DB: sqlx::Database>(pool: &sqlx::Pool<DB>) -> Self {
+        let size = pool.size();
+        let idle = u32::try_from(pool.num_idle()).unwrap_or(u32::MAX).min(size);
+
+        Self {
+            size,
+            idle,
+            in_use: size - idle,
+            max_connections: pool.options().get_max_connections(),
+        }
+    }
+}
+
+/// Connection pool for whichever backend `DATABASE_URL` selected.
+#[derive(Debug, Clone)]
+pub enum DbPool {
+    Postgres(PgPool),
+    #[cfg(feature = "module-sqlite")]
+    Sqlite(sqlx::SqlitePool),
+}
+
+impl DbPool {
+    /// Returns the backend this pool is connected to.
+    pub fn backend(&self) -> DatabaseBackend {
+        match self {
+            DbPool::Postgres(_) => DatabaseBackend::Postgres,
+            #[cfg(feature = "module-sqlite")]
+            DbPool::Sqlite(_) => DatabaseBackend::Sqlite,
+        }
+    }
+
+    /// Returns the underlying PostgreSQL pool, if this is a PostgreSQL connection.
+    pub fn as_postgres(&self) -> Option<&PgPool> {
+        match self {
+            DbPool::Postgres(pool) => Some(pool),
+            #[cfg(feature = "module-sqlite")]
+            DbPool::Sqlite(_) => None,
+        }
+    }
+
+    /// Returns current connection counts for the pool.
+    pub fn stats(&self) -> PoolStats {
+        match self {
+            DbPool::Postgres(pool) => PoolStats::of(pool),
+            #[cfg(feature = "module-sqlite")]
+            DbPool::Sqlite(pool) => PoolStats::of(pool),
+        }
+    }
+
+    /// Applies all pending migrations for this backend.
+    pub async fn run_migrations(&self) -> Result<()> {
+        match self {
+            DbPool::Postgres(pool) => migrations::run_migrations(pool).await,
+            #[cfg(feature = "module-sqlite")]
+            DbPool::Sqlite(pool) => sqlite::run_migrations(pool).await,
+        }
+    }
+
+    /// Rolls the schema back to `target_version`, returning the reverted versions.
+    pub async fn rollback_migrations(&self, target_version: i64) -> Result<Vec<i64>> {
+        match self {
+            DbPool::Postgres(pool) => migrations::rollback_migrations(pool, target_version).await,
+            #[cfg(feature = "module-sqlite")]
+            DbPool::Sqlite(pool) => sqlite::rollback_migrations(pool, target_version).await,
+        }
+    }
+
+    /// Returns the migrations recorded in the `schema_migrations` ledger.
+    pub async fn applied_migrations(&self) -> Result<Vec<migrations::AppliedMigration>> {
+        match self {
+            DbPool::Postgres(pool) => migrations::applied_migrations(pool).await,
+            #[cfg(feature = "module-sqlite")]
+            DbPool::Sqlite(pool) => sqlite::applied_migrations(pool).await,
+        }
+    }
+}
+
+/// Runs the same sqlx expression against whichever pool backs a [`DbPool`].
+///
+/// The body is expanded once per backend, so queries written in the SQL subset
+/// shared by PostgreSQL and SQLite (`$N` placeholders, ids and timestamps bound
+/// from Rust) only have to be written once.
+macro_rules! with_pool {
+    ($db:expr, |$pool:ident| $body:expr) => {
+        match $db {
+            $crate::database::DbPool::Postgres($pool) => $body,
+            #[cfg(feature = "module-sqlite")]
+            $crate::database::DbPool::Sqlite($pool) => $body,
+        }
+    };
+}
+pub(crate) use with_pool;
+
+/// Creates a database connection pool using the vault credentials, or the
+/// environment configuration when no vault is available.
+pub async fn create_pool() -> Result<DbPool> {
     let config = AppConfig::from_env();
-    create_pool_with_url(&config.database_url).await
+    let database_url = credentials::current_database_url(&config).await?;
+    create_pool_with_config(&database_url, &config.database_pool).await
 }
 
 /// Creates a database connection pool with a specific database URL.
 ///
 /// # Arguments
-/// * `database_url` - PostgreSQL connection string
+/// * `database_url` - PostgreSQL or SQLite connection string
 ///
 /// # Returns
-/// * `Result<PgPool>` - Connection pool or error
-pub async fn create_pool_with_url(database_url: &str) -> Result<PgPool> {
+/// * `Result<DbPool>` - Connection pool or error
+pub async fn create_pool_with_url(database_url: &str) -> Result<DbPool> {
     let config = AppConfig::from_env();
+    create_pool_with_config(database_url, &config.database_pool).await
+}
+
+/// Creates a database connection pool with explicit sizing and timeouts.
+pub async fn create_pool_with_config(
+    database_url: &str,
+    pool_config: &DatabasePoolConfig,
+) -> Result<DbPool> {
+    match DatabaseBackend::from_url(database_url)? {
+        DatabaseBackend::Postgres => {
+            let mut options = PgConnectOptions::from_str(database_url)?;
+            if let Some(timeout) = pool_config.statement_timeout {
+                let millis = timeout.as_millis().to_string();
+                options = options.options([("statement_timeout", millis.as_str())]);
+            }
 
-    let pool = PgPoolOptions::new()
-        .max_connections(if config.is_production() { 50 } else { 20 })
-        .acquire_timeout(Duration::from_secs(60))
-        .connect(database_url)
-        .await?;
+            let pool = PgPoolOptions::new()
+                .max_connections(pool_config.max_connections)
+                .min_connections(pool_config.min_connections)
+                .acquire_timeout(pool_config.acquire_timeout)
+                .idle_timeout(pool_config.idle_timeout)
+                .connect_with(options)
+                .await?;
 
-    Ok(pool)
+            Ok(DbPool::Postgres(pool))
+        }
+        #[cfg(feature = "module-sqlite")]
+        DatabaseBackend::Sqlite => Ok(DbPool::Sqlite(
+            sqlite::create_pool(database_url, pool_config).await?,
+        )),
+        #[cfg(not(feature = "module-sqlite"))]
+        DatabaseBackend::Sqlite => {
+            bail!("SQLite database URLs require the module-sqlite feature")
+        }
+    }
 }
 
-pub async fn test_connection(pool: &PgPool) -> Result<bool> {
-    let row: (i32,) = sqlx::query_as("SELECT 1").fetch_one(pool).await?;
+pub async fn test_connection(pool: &DbPool) -> Result<bool> {
+    let row: (i32,) = with_pool!(pool, |pool| {
+        sqlx::query_as("SELECT 1").fetch_one(pool).await?
+    });
 
     Ok(row.0 == 1)
 }
@@ -0,0 +1,303 @@
+//! Embedded SQLite backend for installations without a PostgreSQL server.
+//!
+//! Uses the same versioned migrations as PostgreSQL, written in the SQLite
+//! dialect, and the same `schema_migrations` ledger layout.
+
+use anyhow::{anyhow, Context, Result};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::migrations::{
+    load_migrations, pending_migrations, rollback_plan, AppliedMigration, Migration,
+    CREATE_LEDGER,
+};
+use super::DatabaseBackend;
+use crate::config::{app_data_dir, DatabasePoolConfig};
+
+/// Opens a SQLite pool, creating the database file if it does not exist yet.
+///
+/// Relative paths such as `sqlite:ez-tauri.db` are resolved against the
+/// application data directory rather than the process working directory.
+/// SQLite has no statement timeout, so `statement_timeout` is not applied.
+pub async fn create_pool(database_url: &str, pool_config: &DatabasePoolConfig) -> Result<SqlitePool> {
+    let mut options = SqliteConnectOptions::from_str(database_url)
+        .with_context(|| format!("Invalid SQLite database URL '{}'", database_url))?
+        .create_if_missing(true)
+        .foreign_keys(true);
+
+    let in_memory = database_url.contains(":memory:") || database_url.contains("mode=memory");
+    let mut pool_options = SqlitePoolOptions::new().acquire_timeout(pool_config.acquire_timeout);
+
+    if in_memory {
+        // Every connection to the shared in-memory database must stay open,
+        // otherwise the database is dropped with the last one.
+        pool_options = pool_options
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None);
+    } else {
+        let filename = options.get_filename().to_path_buf();
+        let filename = if filename.is_relative() {
+            app_data_dir().join(filename)
+        } else {
+            filename
+        };
+
+        if let Some(parent) = filename.parent() {
+            std::fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create database directory {}", parent.display())
+            })?;
+        }
+
+        options = options
+            .filename(filename)
+            .journal_mode(SqliteJournalMode::Wal)
+            .busy_timeout(Duration::from_secs(5));
+        pool_options = pool_options
+            .max_connections(pool_config.max_connections)
+            .min_connections(pool_config.min_connections)
+            .idle_timeout(pool_config.idle_timeout);
+    }
+
+    let pool = pool_options.connect_with(options).await?;
+
+    Ok(pool)
+}
+
+/// Returns the ledger of applied migrations, ordered by version.
+pub async fn applied_migrations(pool: &SqlitePool) -> Result<Vec<AppliedMigration>> {
+    sqlx::query(CREATE_LEDGER).execute(pool).await?;
+
+    let applied = sqlx::query_as::<_, AppliedMigration>(
+        "SELECT version, name, module_id, checksum, applied_at
+         FROM schema_migrations
+         ORDER BY version",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(applied)
+}
+
+/// Runs all pending SQLite migrations, each in its own transaction.
+pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
+    let migrations = load_migrations(DatabaseBackend::Sqlite)?;
+    let applied = applied_migrations(pool).await?;
+
+    for migration in pending_migrations(&migrations, &applied)? {
+        apply_migration(pool, migration).await?;
+    }
+
+    Ok(())
+}
+
+/// Applies a single migration and records it in the ledger atomically.
+async fn apply_migration(pool: &SqlitePool, migration: &Migration) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let already_applied: Option<i64> =
+        sqlx::query_scalar("SELECT version FROM schema_migrations WHERE version = $1")
+            .bind(migration.version)
+            .fetch_optional(&mut *tx)
+            .await?;
+    if already_applied.is_some() {
+        return Ok(());
+    }
+
+    sqlx::raw_sql(migration.sql)
+        .execute(&mut *tx)
+        .await
+        .with_context(|| {
+            format!(
+                "Migration {:03}_{} failed",
+                migration.version, migration.name
+            )
+        })?;
+
+    sqlx::query(
+        "INSERT INTO schema_migrations (version, name, module_id, checksum)
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(migration.version)
+    .bind(&migration.name)
+    .bind(&migration.module_id)
+    .bind(&migration.checksum)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    tracing::info!(
+        "Applied migration {:03}_{} ({})",
+        migration.version,
+        migration.name,
+        migration.module_id
+    );
+
+    Ok(())
+}
+
+/// Rolls the schema back to `target_version` by running down scripts in reverse order.
+pub async fn rollback_migrations(pool: &SqlitePool, target_version: i64) -> Result<Vec<i64>> {
+    let migrations = load_migrations(DatabaseBackend::Sqlite)?;
+    let applied = applied_migrations(pool).await?;
+
+    let mut reverted = Vec::new();
+    for migration in rollback_plan(&migrations, &applied, target_version)? {
+        revert_migration(pool, migration).await?;
+        reverted.push(migration.version);
+    }
+
+    Ok(reverted)
+}
+
+/// Runs a migration's down script and removes it from the ledger atomically.
+async fn revert_migration(pool: &SqlitePool, migration: &Migration) -> Result<()> {
+    let down_sql = migration
+        .down_sql
+        .ok_or_else(|| anyhow!("Migration {:03}_{} has no down script", migration.version, migration.name))?;
+    let mut tx = pool.begin().await?;
+
+    sqlx::raw_sql(down_sql)
+        .execute(&mut *tx)
+        .await
+        .with_context(|| {
+            format!(
+                "Rollback of migration {:03}_{} failed",
+                migration.version, migration.name
+            )
+        })?;
+
+    sqlx::query("DELETE FROM schema_migrations WHERE version = $1")
+        .bind(migration.version)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    tracing::info!(
+        "Rolled back migration {:03}_{} ({})",
+        migration.version,
+        migration.name,
+        migration.module_id
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result as AnyResult;
+    use sqlx::Row;
+
+    async fn memory_pool() -> AnyResult<SqlitePool> {
+        create_pool("sqlite::memory:", &DatabasePoolConfig::defaults_for(&Default::default())).await
+    }
+
+    #[tokio::test]
+    async fn migrations_create_all_required_tables() -> AnyResult<()> {
+        let pool = memory_pool().await?;
+
+        run_migrations(&pool).await?;
+        run_migrations(&pool).await?;
+
+        let tables: Vec<String> = sqlx::query(
+            "SELECT name FROM sqlite_master
+             WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+             ORDER BY name"
+        )
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .map(|row| row.get::<String, _>(0))
+        .collect();
+
+        assert_eq!(
+            tables,
+            vec![
+                "app_logs",
+                "auth_sessions",
+                "email_verification_tokens",
+                "password_reset_tokens",
+                "recovery_codes",
+                "role_permissions",
+                "schema_migrations",
+                "user_settings",
+                "users"
+            ]
+        );
+        assert_eq!(applied_migrations(&pool).await?.len(), 13);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rollback_reverts_to_target() -> AnyResult<()> {
+        let pool = memory_pool().await?;
+        run_migrations(&pool).await?;
+
+        let reverted = rollback_migrations(&pool, 0).await?;
+        assert_eq!(reverted, vec![12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1]);
+
+        let versions: Vec<i64> = applied_migrations(&pool)
+            .await?
+            .into_iter()
+            .map(|m| m.version)
+            .collect();
+        assert_eq!(versions, vec![0]);
+
+        run_migrations(&pool).await?;
+        assert_eq!(applied_migrations(&pool).await?.len(), 13);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn upgrades_without_an_admin_promote_the_oldest_active_user() -> AnyResult<()> {
+        let pool = memory_pool().await?;
+        run_migrations(&pool).await?;
+        rollback_migrations(&pool, 11).await?;
+
+        for (email, created_at, is_active) in [
+            ("inactive@example.com", "2020-01-01T00:00:00.000+00:00", false),
+            ("oldest@example.com", "2021-01-01T00:00:00.000+00:00", true),
+            ("newest@example.com", "2022-01-01T00:00:00.000+00:00", true),
+        ] {
+            sqlx::query(
+                "INSERT INTO users (id, email, username, password_hash, is_active, created_at)
+                 VALUES ($1, $2, $2, 'hash', $3, $4)"
+            )
+            .bind(uuid::Uuid::new_v4())
+            .bind(email)
+            .bind(is_active)
+            .bind(created_at)
+            .execute(&pool)
+            .await?;
+        }
+
+        run_migrations(&pool).await?;
+
+        let admins: Vec<String> = sqlx::query("SELECT email FROM users WHERE role = 'admin'")
+            .fetch_all(&pool)
+            .await?
+            .into_iter()
+            .map(|row| row.get::<String, _>(0))
+            .collect();
+        assert_eq!(admins, vec!["oldest@example.com"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn foreign_keys_are_enforced() -> AnyResult<()> {
+        let pool = memory_pool().await?;
+        run_migrations(&pool).await?;
+
+        let result = sqlx::query("INSERT INTO user_settings (id, user_id) VALUES ($1, $2)")
+            .bind(uuid::Uuid::new_v4())
+            .bind(uuid::Uuid::new_v4())
+            .execute(&pool)
+            .await;
+
+        assert!(result.is_err());
+        Ok(())
+    }
+}
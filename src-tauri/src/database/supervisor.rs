@@ -0,0 +1,248 @@
+//! Background supervisor that keeps the global connection pool alive.
+//!
+//! Connects with exponential backoff when the database is unreachable, swaps
+//! the new pool in through [`initialize_pool`], then health-checks it and
+//! starts over when the check fails. Connectivity changes are reported to a
+//! callback, which the application forwards as a Tauri event.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::time::Duration;
+
+use super::connection::{get_pool, initialize_pool};
+use super::{create_pool, create_pool_with_url, test_connection, DbPool};
+
+/// Name of the Tauri event emitted when database connectivity changes.
+pub const DATABASE_STATUS_EVENT: &str = "database-status";
+
+/// Connectivity snapshot sent to the frontend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseStatus {
+    pub connected: bool,
+    /// Connection attempts made since the database was last reachable.
+    pub attempt: u32,
+    pub error: Option<String>,
+}
+
+/// Timing for reconnect attempts and health checks.
+#[derive(Debug, Clone)]
+pub struct SupervisorConfig {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub health_check_interval: Duration,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            health_check_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+impl SupervisorConfig {
+    /// Delay before retrying after the given failed attempt (1-based), doubling up to `max_delay`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        self.initial_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay)
+    }
+}
+
+/// Spawns the supervisor on the Tauri async runtime.
+///
+/// Every attempt goes through [`create_pool`], so configuration changes are
+/// picked up on the next reconnect.
+pub fn spawn_supervisor<F>(config: SupervisorConfig, on_change: F) -> tauri::async_runtime::JoinHandle<()>
+where
+    F: Fn(&DatabaseStatus) + Send + Sync + 'static,
+{
+    tauri::async_runtime::spawn(async move {
+        Supervisor::new(None, config, on_change).run().await;
+    })
+}
+
+struct Supervisor<F> {
+    /// Fixed connection URL; `None` reads the configured URL on every attempt.
+    database_url: Option<String>,
+    config: SupervisorConfig,
+    on_change: F,
+    connected: Option<bool>,
+}
+
+impl<F> Supervisor<F>
+where
+    F: Fn(&DatabaseStatus) + Send + Sync,
+{
+    fn new(database_url: Option<String>, config: SupervisorConfig, on_change: F) -> Self {
+        Self {
+            database_url,
+            config,
+            on_change,
+            connected: None,
+        }
+    }
+
+    async fn run(&mut self) {
+        loop {
+            self.connect().await;
+            self.monitor().await;
+        }
+    }
+
+    /// Retries until a pool is connected and installed as the global pool.
+    async fn connect(&mut self) {
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            match self.open_pool().await {
+                Ok(pool) => {
+                    if let Err(e) = pool.run_migrations().await {
+                        tracing::error!("Failed to run migrations: {}", e);
+                    } else {
+                        tracing::info!("Migrations completed successfully");
+                    }
+
+                    initialize_pool(pool).await;
+                    tracing::info!("Database initialized successfully");
+                    self.report(DatabaseStatus {
+                        connected: true,
+                        attempt,
+                        error: None,
+                    });
+                    return;
+                }
+                Err(e) => {
+                    let delay = self.config.backoff(attempt);
+                    tracing::error!(
+                        "Failed to initialize database (attempt {}): {}. Retrying in {:?}",
+                        attempt,
+                        e,
+                        delay
+                    );
+                    self.report(DatabaseStatus {
+                        connected: false,
+                        attempt,
+                        error: Some(e.to_string()),
+                    });
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// Returns once the current pool fails a health check.
+    async fn monitor(&mut self) {
+        loop {
+            tokio::time::sleep(self.config.health_check_interval).await;
+
+            let result = match get_pool() {
+                Some(pool) => test_connection(&pool).await,
+                None => Err(anyhow::anyhow!("Database pool not initialized")),
+            };
+
+            if let Err(e) = result {
+                tracing::warn!("Database health check failed: {}", e);
+                self.report(DatabaseStatus {
+                    connected: false,
+                    attempt: 0,
+                    error: Some(e.to_string()),
+                });
+                return;
+            }
+        }
+    }
+
+    async fn open_pool(&self) -> Result<DbPool> {
+        let pool = match &self.database_url {
+            Some(url) => create_pool_with_url(url).await?,
+            None => create_pool().await?,
+        };
+        test_connection(&pool).await?;
+        Ok(pool)
+    }
+
+    /// Invokes the callback only when connectivity differs from the last report.
+    fn report(&mut self, status: DatabaseStatus) {
+        if self.connected != Some(status.connected) {
+            self.connected = Some(status.connected);
+            (self.on_change)(&status);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn fast_config() -> SupervisorConfig {
+        SupervisorConfig {
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(4),
+            health_check_interval: Duration::from_millis(5),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_until_capped() {
+        let config = SupervisorConfig::default();
+
+        let delays: Vec<u64> = (1..=8).map(|attempt| config.backoff(attempt).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(config.backoff(u32::MAX), Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn repeated_failures_are_reported_once() {
+        let statuses = Arc::new(Mutex::new(Vec::new()));
+        let recorded = statuses.clone();
+        let mut supervisor = Supervisor::new(
+            Some("mysql://unsupported".to_string()),
+            fast_config(),
+            move |status: &DatabaseStatus| recorded.lock().unwrap().push(status.clone()),
+        );
+
+        let finished = tokio::time::timeout(Duration::from_millis(50), supervisor.connect()).await;
+        assert!(finished.is_err(), "connect should keep retrying");
+
+        let statuses = statuses.lock().unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert!(!statuses[0].connected);
+        assert_eq!(statuses[0].attempt, 1);
+        assert!(statuses[0].error.as_deref().unwrap().contains("Unsupported database URL"));
+    }
+
+    #[cfg(feature = "module-sqlite")]
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn successful_connection_installs_global_pool() {
+        crate::database::connection::reset_pool_for_tests();
+        let statuses = Arc::new(Mutex::new(Vec::new()));
+        let recorded = statuses.clone();
+        let mut supervisor = Supervisor::new(
+            Some("sqlite::memory:".to_string()),
+            fast_config(),
+            move |status: &DatabaseStatus| recorded.lock().unwrap().push(status.clone()),
+        );
+
+        supervisor.connect().await;
+
+        let pool = get_pool().expect("supervisor should install the pool");
+        assert!(test_connection(&pool).await.unwrap());
+        assert_eq!(
+            *statuses.lock().unwrap(),
+            vec![DatabaseStatus {
+                connected: true,
+                attempt: 1,
+                error: None,
+            }]
+        );
+    }
+}
@@ -13,7 +13,7 @@ use testcontainers_modules::postgres::Postgres;
 use tokio::sync::OnceCell;
 use tokio::time::sleep;
 
-use super::{connection, migrations};
+use super::{connection, migrations, DbPool};
 
 /// Container context for managing test database lifecycle.
 struct ContainerContext {
@@ -65,11 +65,14 @@ pub async fn pool() -> Result<Arc<PgPool>> {
     connection::reset_pool_for_tests();
 
     std::env::set_var("DATABASE_URL", &ctx.connection_string);
-    connection::initialize_database()
-        .await
-        .map_err(|e| anyhow!(e))?;
+    let pool = super::create_pool_with_url(&ctx.connection_string).await?;
+    connection::initialize_pool(pool).await;
 
-    let pool = connection::get_pool_ref()?;
+    let pool = match connection::get_pool_ref()?.as_ref() {
+        DbPool::Postgres(pool) => Arc::new(pool.clone()),
+        #[cfg(feature = "module-sqlite")]
+        DbPool::Sqlite(_) => return Err(anyhow!("expected a PostgreSQL test pool")),
+    };
 
     migrations::run_migrations(pool.as_ref())
         .await
@@ -78,6 +81,20 @@ pub async fn pool() -> Result<Arc<PgPool>> {
     Ok(pool)
 }
 
+/// Creates a fresh in-memory SQLite pool with migrations applied and installs
+/// it as the global pool, so handlers can be tested without Docker.
+#[cfg(feature = "module-sqlite")]
+pub async fn sqlite_pool() -> Result<sqlx::SqlitePool> {
+    connection::reset_pool_for_tests();
+
+    let pool_config = crate::config::DatabasePoolConfig::defaults_for(&Default::default());
+    let pool = super::sqlite::create_pool("sqlite::memory:", &pool_config).await?;
+    super::sqlite::run_migrations(&pool).await?;
+    connection::initialize_pool(DbPool::Sqlite(pool.clone())).await;
+
+    Ok(pool)
+}
+
 /// Resets all tables in the test database for clean test isolation.
 pub async fn reset_all_tables(pool: &PgPool) -> Result<()> {
     sqlx::query("TRUNCATE TABLE app_logs RESTART IDENTITY CASCADE")
@@ -0,0 +1,44 @@
+//! Settings and messages for email address verification.
+//!
+//! Verification tokens are generated and hashed like password reset tokens
+//! (see [`crate::password_reset`]) and are emailed through the configured
+//! [`crate::mail`] transport.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::mail::MailMessage;
+
+/// Lifetime of verification tokens when `AUTH_VERIFICATION_TOKEN_TTL_HOURS` is unset.
+const DEFAULT_TOKEN_TTL_HOURS: i64 = 48;
+
+/// Whether logins are refused until the email address is verified, from
+/// `AUTH_REQUIRE_EMAIL_VERIFICATION` (the variable `AuthConfig` reads too).
+pub fn verification_required() -> bool {
+    std::env::var("AUTH_REQUIRE_EMAIL_VERIFICATION")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(false)
+}
+
+/// How long new verification tokens stay valid, from `AUTH_VERIFICATION_TOKEN_TTL_HOURS`.
+pub fn token_ttl() -> Duration {
+    let hours = std::env::var("AUTH_VERIFICATION_TOKEN_TTL_HOURS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|hours: &i64| *hours > 0)
+        .unwrap_or(DEFAULT_TOKEN_TTL_HOURS);
+    Duration::hours(hours)
+}
+
+/// The email carrying a verification token.
+pub fn verification_message(email: &str, token: &str, expires_at: DateTime<Utc>) -> MailMessage {
+    MailMessage {
+        to: email.to_string(),
+        subject: "Verify your email address".to_string(),
+        body: format!(
+            "Use this token to verify your email address:\n\n{}\n\nIt expires at {}. If you did not create an account, you can ignore this email.",
+            token,
+            expires_at.to_rfc3339()
+        ),
+    }
+}
@@ -1,7 +1,11 @@
 //! Database connection and health check handlers.
 
-use crate::database::{get_pool_ref, test_connection};
+use crate::config::AppConfig;
+use crate::database::credentials::{current_database_url, replace_database_url, with_password};
+use crate::database::migrations::AppliedMigration;
+use crate::database::{get_pool_ref, test_connection, DbPool, PoolStats};
 use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::stronghold;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
@@ -13,6 +17,7 @@ pub struct DatabaseStatus {
     pub database_name: Option<String>,
     pub version: Option<String>,
     pub error: Option<String>,
+    pub pool: Option<PoolStats>,
 }
 
 /// Checks database connectivity and returns connection status information.
@@ -25,11 +30,22 @@ pub async fn check_database_connection() -> Result<DatabaseStatus, AppError> {
 
     match test_connection(pool.as_ref()).await {
         Ok(_) => {
-            let db_info_result = sqlx::query_as::<_, (String, String)>(
-                "SELECT current_database(), version()"
-            )
-            .fetch_one(pool.as_ref())
-            .await;
+            let db_info_result = match pool.as_ref() {
+                DbPool::Postgres(pool) => sqlx::query_as::<_, (String, String)>(
+                    "SELECT current_database(), version()"
+                )
+                .fetch_one(pool)
+                .await,
+                #[cfg(feature = "module-sqlite")]
+                DbPool::Sqlite(pool) => sqlx::query_as::<_, (String, String)>(
+                    "SELECT CASE WHEN file = '' THEN name ELSE file END,
+                            'SQLite ' || sqlite_version()
+                     FROM pragma_database_list
+                     WHERE name = 'main'"
+                )
+                .fetch_one(pool)
+                .await,
+            };
 
             match db_info_result {
                 Ok((db_name, version)) => {
@@ -39,6 +55,7 @@ pub async fn check_database_connection() -> Result<DatabaseStatus, AppError> {
                         database_name: Some(db_name),
                         version: Some(version),
                         error: None,
+                        pool: Some(pool.stats()),
                     })
                 }
                 Err(e) => {
@@ -48,6 +65,7 @@ pub async fn check_database_connection() -> Result<DatabaseStatus, AppError> {
                         database_name: None,
                         version: None,
                         error: Some(format!("Failed to get database info: {}", e)),
+                        pool: Some(pool.stats()),
                     })
                 }
             }
@@ -59,21 +77,62 @@ pub async fn check_database_connection() -> Result<DatabaseStatus, AppError> {
                 database_name: None,
                 version: None,
                 error: Some(e.to_string()),
+                pool: Some(pool.stats()),
             })
         }
     }
 }
 
+/// Connects with the credentials stored in the Stronghold vault and runs migrations.
 #[tauri::command]
 pub async fn initialize_database() -> AppResult<String> {
     tracing::info!("Initializing database");
 
-    // Note: This would need access to stronghold, but we can't easily pass it through
-    // For now, we'll return an error indicating it needs to be called differently
-    Err(AppError::new(
-        ErrorCode::InvalidInput,
-        "Database initialization requires stronghold access".to_string()
-    ))
+    let vault = stronghold::vault()
+        .into_app_error(ErrorCode::ConfigurationError)?;
+    let mut manager = vault.lock().await;
+
+    crate::database::initialize_database(&mut manager)
+        .await
+        .into_app_error(ErrorCode::DatabaseConnection)?;
+    drop(manager);
+
+    run_migrations().await?;
+    Ok("Database initialized successfully".to_string())
+}
+
+/// Replaces the stored connection string after verifying that it connects.
+#[tauri::command]
+pub async fn set_database_credentials(database_url: String) -> AppResult<String> {
+    tracing::info!("Updating stored database credentials");
+
+    replace_database_url(database_url.trim())
+        .await
+        .into_app_error(ErrorCode::DatabaseConnection)?;
+
+    Ok("Database credentials updated".to_string())
+}
+
+/// Replaces the password of the stored PostgreSQL connection string.
+#[tauri::command]
+pub async fn rotate_database_password(password: String) -> AppResult<String> {
+    tracing::info!("Rotating database password");
+
+    if password.is_empty() {
+        return Err(AppError::invalid_input("password", "Password cannot be empty"));
+    }
+
+    let current = current_database_url(&AppConfig::from_env())
+        .await
+        .into_app_error(ErrorCode::ConfigurationError)?;
+    let rotated = with_password(&current, &password)
+        .into_app_error(ErrorCode::InvalidInput)?;
+
+    replace_database_url(&rotated)
+        .await
+        .into_app_error(ErrorCode::DatabaseConnection)?;
+
+    Ok("Database password rotated".to_string())
 }
 
 #[tauri::command]
@@ -83,7 +142,7 @@ pub async fn run_migrations() -> AppResult<String> {
     let pool = get_pool_ref()
         .into_app_error(ErrorCode::DatabaseConnection)?;
 
-    crate::database::migrations::run_migrations(pool.as_ref())
+    pool.run_migrations()
         .await
         .into_app_error(ErrorCode::DatabaseMigration)
         .map(|_| {
@@ -91,6 +150,39 @@ pub async fn run_migrations() -> AppResult<String> {
             "Migrations completed successfully".to_string()
         })
 }
+
+/// Rolls the schema back so that `target_version` is the newest applied migration.
+#[tauri::command]
+pub async fn rollback_migrations(target_version: i64) -> AppResult<String> {
+    tracing::info!("Rolling back database migrations to version {}", target_version);
+
+    let pool = get_pool_ref()
+        .into_app_error(ErrorCode::DatabaseConnection)?;
+
+    let reverted = pool
+        .rollback_migrations(target_version)
+        .await
+        .into_app_error(ErrorCode::DatabaseMigration)?;
+
+    tracing::info!("Rolled back {} migration(s)", reverted.len());
+    Ok(format!(
+        "Rolled back {} migration(s) to version {}",
+        reverted.len(),
+        target_version
+    ))
+}
+
+/// Returns the migrations recorded in the `schema_migrations` ledger.
+#[tauri::command]
+pub async fn get_applied_migrations() -> AppResult<Vec<AppliedMigration>> {
+    let pool = get_pool_ref()
+        .into_app_error(ErrorCode::DatabaseConnection)?;
+
+    pool.applied_migrations()
+        .await
+        .into_app_error(ErrorCode::DatabaseQuery)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -112,6 +204,10 @@ mod tests {
         assert_eq!(status.database_name.as_deref(), Some("tauri_app"));
         assert!(status.version.is_some());
         assert!(status.error.is_none());
+        let stats = status.pool.expect("connected status should include pool stats");
+        assert!(stats.size >= 1);
+        assert_eq!(stats.size, stats.idle + stats.in_use);
+        assert!(stats.size <= stats.max_connections);
         Ok(())
     }
 
@@ -127,6 +223,39 @@ mod tests {
         run_migrations()
             .await
             .expect("second migration run should be idempotent");
+
+        let applied = get_applied_migrations()
+            .await
+            .expect("ledger should be readable");
+        assert_eq!(
+            applied.len(),
+            crate::database::migrations::load_migrations(crate::database::DatabaseBackend::Postgres)?
+                .len()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn rollback_command_reports_reverted_count() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+        run_migrations()
+            .await
+            .expect("migrations should apply before rollback");
+
+        let expected = crate::database::migrations::load_migrations(crate::database::DatabaseBackend::Postgres)?
+            .iter()
+            .filter(|migration| migration.version > 3)
+            .count();
+        let message = rollback_migrations(3)
+            .await
+            .expect("rollback should succeed");
+        assert_eq!(message, format!("Rolled back {} migration(s) to version 3", expected));
+
+        run_migrations()
+            .await
+            .expect("migrations should reapply after rollback");
         Ok(())
     }
 }
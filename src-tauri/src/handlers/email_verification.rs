@@ -0,0 +1,346 @@
+//! Email verification command handlers.
+//!
+//! New accounts are sent a one-time token by email, and `verify_email` spends
+//! it to mark the address verified. When `AUTH_REQUIRE_EMAIL_VERIFICATION` is
+//! on, logins are refused until then. Requesting a new token never reveals
+//! whether the email belongs to an account.
+
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::email_verification::{token_ttl, verification_message};
+use crate::handlers::logs::log_security_event;
+use crate::mail::{mail_transport, MailTransport};
+use crate::password_reset::{generate_token, hash_token};
+use crate::repositories::{
+    EmailVerificationRepository, LogRepository, SqlEmailVerificationRepository, SqlLogRepository,
+    SqlUserRepository, UserRepository,
+};
+use crate::validation::validate_email;
+
+/// Message returned by `request_email_verification` whether or not the account exists.
+pub const VERIFICATION_REQUESTED: &str =
+    "If an unverified account exists for that email, a verification token has been sent";
+
+const INVALID_VERIFICATION_TOKEN: &str = "Invalid or expired verification token";
+
+fn repositories() -> Result<(SqlUserRepository, SqlEmailVerificationRepository, SqlLogRepository), String> {
+    Ok((
+        SqlUserRepository::from_global().map_err(|e| e.to_string())?,
+        SqlEmailVerificationRepository::from_global().map_err(|e| e.to_string())?,
+        SqlLogRepository::from_global().map_err(|e| e.to_string())?,
+    ))
+}
+
+/// Issues a verification token for `email` and mails it, replacing any
+/// earlier token of the user.
+pub(crate) async fn send_verification_with(
+    tokens: &dyn EmailVerificationRepository,
+    transport: &dyn MailTransport,
+    user_id: Uuid,
+    email: &str,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    // Only the newest token works.
+    tokens
+        .invalidate_for_user(user_id, now)
+        .await
+        .map_err(|e| format!("Failed to issue verification token: {}", e))?;
+
+    let token = generate_token();
+    let expires_at = now + token_ttl();
+    tokens
+        .create(user_id, &hash_token(&token), expires_at)
+        .await
+        .map_err(|e| format!("Failed to issue verification token: {}", e))?;
+
+    transport
+        .send(&verification_message(email, &token, expires_at))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to send verification email to user {}: {}", user_id, e);
+            "Failed to send verification email".to_string()
+        })
+}
+
+/// Sends a verification token after an account is created or its email
+/// changes. Failures are only logged; the user can ask for another token.
+pub(crate) async fn send_verification(user_id: Uuid, email: &str) {
+    let tokens = match SqlEmailVerificationRepository::from_global() {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            tracing::warn!("Could not send verification email to user {}: {}", user_id, e);
+            return;
+        }
+    };
+
+    if let Err(e) = send_verification_with(&tokens, mail_transport().as_ref(), user_id, email, Utc::now()).await {
+        tracing::warn!("Could not send verification email to user {}: {}", user_id, e);
+    }
+}
+
+/// Sends a new verification token to `email` if it belongs to an active,
+/// unverified account.
+///
+/// The response is the same either way, so it cannot be used to probe for
+/// accounts.
+#[tauri::command]
+pub async fn request_email_verification(email: String) -> Result<String, String> {
+    let (users, tokens, _) = repositories()?;
+    request_email_verification_with(&users, &tokens, mail_transport().as_ref(), &email, Utc::now()).await?;
+
+    Ok(VERIFICATION_REQUESTED.to_string())
+}
+
+pub(crate) async fn request_email_verification_with(
+    users: &dyn UserRepository,
+    tokens: &dyn EmailVerificationRepository,
+    transport: &dyn MailTransport,
+    email: &str,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let email = validate_email(email).map_err(|e| format!("Invalid email: {}", e))?;
+
+    let user = users
+        .find_by_email(&email)
+        .await
+        .map_err(|e| format!("Failed to request verification: {}", e))?
+        .filter(|user| user.can_sign_in() && !user.email_verified);
+    let Some(user) = user else {
+        return Ok(());
+    };
+
+    // A failure is not reported either, as it would reveal the account.
+    if let Err(e) = send_verification_with(tokens, transport, user.id, &user.email, now).await {
+        tracing::warn!("Could not send verification email to user {}: {}", user.id, e);
+    }
+
+    Ok(())
+}
+
+/// Marks the email address a verification token was sent to as verified.
+#[tauri::command]
+pub async fn verify_email(token: String) -> Result<String, String> {
+    let (users, tokens, logs) = repositories()?;
+    verify_email_with(&users, &tokens, &logs, &token, Utc::now()).await?;
+
+    Ok("Email verified successfully".to_string())
+}
+
+pub(crate) async fn verify_email_with(
+    users: &dyn UserRepository,
+    tokens: &dyn EmailVerificationRepository,
+    logs: &dyn LogRepository,
+    token: &str,
+    now: DateTime<Utc>,
+) -> Result<Uuid, String> {
+    let verification = tokens
+        .find_by_hash(&hash_token(token))
+        .await
+        .map_err(|e| format!("Failed to verify email: {}", e))?
+        .filter(|verification| verification.is_usable(now))
+        .ok_or_else(|| INVALID_VERIFICATION_TOKEN.to_string())?;
+
+    let consumed = tokens
+        .consume(verification.id, now)
+        .await
+        .map_err(|e| format!("Failed to verify email: {}", e))?;
+    if !consumed {
+        return Err(INVALID_VERIFICATION_TOKEN.to_string());
+    }
+
+    let updated = users
+        .mark_email_verified(verification.user_id)
+        .await
+        .map_err(|e| format!("Failed to verify email: {}", e))?;
+    if !updated {
+        return Err(INVALID_VERIFICATION_TOKEN.to_string());
+    }
+
+    log_security_event(
+        logs,
+        "email_verified",
+        "Email address verified".to_string(),
+        Some(verification.user_id),
+        json!({ "tokenId": verification.id }),
+    )
+    .await;
+
+    Ok(verification.user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::users::{authenticate_user_with, create_user_with, update_user_with, EMAIL_NOT_VERIFIED};
+    use crate::lockout::LoginThrottle;
+    use crate::mail::MailMessage;
+    use crate::models::{CreateUser, LoginRequest, UpdateUser};
+    use crate::repositories::memory::{
+        InMemoryEmailVerificationRepository, InMemoryLogRepository, InMemoryUserRepository,
+    };
+    use crate::totp::InMemoryTotpSecrets;
+    use chrono::Duration;
+    use std::sync::Mutex;
+
+    /// Keeps sent messages for the test to read.
+    #[derive(Default)]
+    struct RecordingTransport {
+        sent: Mutex<Vec<MailMessage>>,
+    }
+
+    impl RecordingTransport {
+        /// The token in the most recent message.
+        fn last_token(&self) -> String {
+            let sent = self.sent.lock().unwrap();
+            let body = &sent.last().expect("a message should have been sent").body;
+            body.lines().nth(2).unwrap().to_string()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl MailTransport for RecordingTransport {
+        async fn send(&self, message: &MailMessage) -> anyhow::Result<()> {
+            self.sent.lock().unwrap().push(message.clone());
+            Ok(())
+        }
+    }
+
+    struct FailingTransport;
+
+    #[async_trait::async_trait]
+    impl MailTransport for FailingTransport {
+        async fn send(&self, _message: &MailMessage) -> anyhow::Result<()> {
+            anyhow::bail!("connection refused")
+        }
+    }
+
+    async fn create_account(users: &InMemoryUserRepository) -> Uuid {
+        create_user_with(
+            users,
+            CreateUser {
+                email: "verify@example.com".to_string(),
+                username: "verify_user".to_string(),
+                password: "Sup3r$ecret".to_string(),
+                first_name: None,
+                last_name: None,
+            },
+        )
+        .await
+        .expect("user creation should succeed")
+        .id
+    }
+
+    async fn log_in(users: &InMemoryUserRepository, require_verified_email: bool) -> Result<bool, String> {
+        authenticate_user_with(
+            users,
+            &InMemoryLogRepository::new(),
+            &LoginThrottle::default(),
+            &InMemoryTotpSecrets::default(),
+            require_verified_email,
+            "test",
+            LoginRequest {
+                email: "verify@example.com".to_string(),
+                password: "Sup3r$ecret".to_string(),
+                totp_code: None,
+            },
+        )
+        .await
+        .map(|user| user.is_some())
+    }
+
+    #[tokio::test]
+    async fn verification_tokens_are_single_use_and_expire() {
+        let users = InMemoryUserRepository::new();
+        let tokens = InMemoryEmailVerificationRepository::new();
+        let logs = InMemoryLogRepository::new();
+        let transport = RecordingTransport::default();
+        let user_id = create_account(&users).await;
+        let now = Utc::now();
+
+        request_email_verification_with(&users, &tokens, &transport, "nobody@example.com", now)
+            .await
+            .expect("unknown emails should not be reported");
+        assert!(transport.sent.lock().unwrap().is_empty());
+
+        send_verification_with(&tokens, &transport, user_id, "verify@example.com", now)
+            .await
+            .unwrap();
+        let superseded = transport.last_token();
+        request_email_verification_with(&users, &tokens, &transport, "verify@example.com", now)
+            .await
+            .unwrap();
+        let token = transport.last_token();
+        assert_eq!(transport.sent.lock().unwrap()[1].to, "verify@example.com");
+
+        let reused = verify_email_with(&users, &tokens, &logs, &superseded, now).await;
+        assert_eq!(reused.unwrap_err(), INVALID_VERIFICATION_TOKEN);
+
+        let expired = now + token_ttl() + Duration::seconds(1);
+        let late = verify_email_with(&users, &tokens, &logs, &token, expired).await;
+        assert_eq!(late.unwrap_err(), INVALID_VERIFICATION_TOKEN);
+
+        let verified = verify_email_with(&users, &tokens, &logs, &token, now)
+            .await
+            .expect("verification should succeed");
+        assert_eq!(verified, user_id);
+        assert!(users.find_by_id(user_id).await.unwrap().unwrap().email_verified);
+
+        let again = verify_email_with(&users, &tokens, &logs, &token, now).await;
+        assert_eq!(again.unwrap_err(), INVALID_VERIFICATION_TOKEN);
+
+        // Verified accounts are not sent new tokens.
+        request_email_verification_with(&users, &tokens, &transport, "verify@example.com", now)
+            .await
+            .unwrap();
+        assert_eq!(transport.sent.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_deliveries_look_like_unknown_emails() {
+        let users = InMemoryUserRepository::new();
+        let tokens = InMemoryEmailVerificationRepository::new();
+        create_account(&users).await;
+
+        let response =
+            request_email_verification_with(&users, &tokens, &FailingTransport, "verify@example.com", Utc::now()).await;
+        assert_eq!(response, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn logins_wait_for_verification_when_required() {
+        let users = InMemoryUserRepository::new();
+        let tokens = InMemoryEmailVerificationRepository::new();
+        let logs = InMemoryLogRepository::new();
+        let transport = RecordingTransport::default();
+        let user_id = create_account(&users).await;
+        let now = Utc::now();
+
+        assert_eq!(log_in(&users, false).await, Ok(true));
+        assert_eq!(log_in(&users, true).await.unwrap_err(), EMAIL_NOT_VERIFIED);
+
+        send_verification_with(&tokens, &transport, user_id, "verify@example.com", now)
+            .await
+            .unwrap();
+        verify_email_with(&users, &tokens, &logs, &transport.last_token(), now)
+            .await
+            .unwrap();
+        assert_eq!(log_in(&users, true).await, Ok(true));
+
+        // A new address has to be verified again.
+        let updated = update_user_with(
+            &users,
+            &user_id.to_string(),
+            UpdateUser {
+                email: Some("verify@example.org".to_string()),
+                username: None,
+                first_name: None,
+                last_name: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(!updated.email_verified);
+    }
+}
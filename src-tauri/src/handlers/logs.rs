@@ -1,46 +1,80 @@
 //! Application log management command handlers.
 
-use crate::database::get_pool_ref;
 use crate::models::{AppLog, CreateAppLog, LogQuery};
+use crate::repositories::{LogFilter, LogRepository, NewLog, SqlLogRepository};
 use crate::validation::{validate_log_level, validate_log_message};
-use sqlx::QueryBuilder;
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+fn log_repository() -> Result<SqlLogRepository, String> {
+    SqlLogRepository::from_global().map_err(|e| e.to_string())
+}
 
 /// Creates a new application log entry in the database.
 #[tauri::command]
 pub async fn create_log(log_data: CreateAppLog) -> Result<AppLog, String> {
-    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    create_log_with(&log_repository()?, log_data).await
+}
 
+pub(crate) async fn create_log_with(
+    repo: &dyn LogRepository,
+    log_data: CreateAppLog,
+) -> Result<AppLog, String> {
     let level = validate_log_level(&log_data.level).map_err(|e| format!("Invalid log level: {}", e))?;
     let message = validate_log_message(&log_data.message).map_err(|e| format!("Invalid log message: {}", e))?;
     let metadata = log_data.metadata.unwrap_or_else(|| serde_json::json!({}));
 
-    let log = sqlx::query_as::<_, AppLog>(
-        r#"
-        INSERT INTO app_logs (level, message, metadata, user_id)
-        VALUES ($1, $2, $3, $4)
-        RETURNING id,
-                  level,
-                  message,
-                  metadata,
-                  user_id,
-                  created_at
-        "#,
-    )
-    .bind(level)
-    .bind(message)
-    .bind(metadata)
-    .bind(log_data.user_id)
-    .fetch_one(pool.as_ref())
-    .await
-    .map_err(|e| format!("Failed to create log: {}", e))?;
+    let log = repo
+        .create(NewLog {
+            level,
+            message,
+            metadata,
+            user_id: log_data.user_id,
+        })
+        .await
+        .map_err(|e| format!("Failed to create log: {}", e))?;
 
     Ok(log)
 }
 
+/// Records a security event, such as an account lockout, as a `warn` entry.
+///
+/// The event name is stored in `metadata.event` so events can be filtered
+/// from ordinary logs. Failures are only traced, never returned, so a broken
+/// log table cannot block the action being audited.
+pub(crate) async fn log_security_event(
+    repo: &dyn LogRepository,
+    event: &str,
+    message: String,
+    user_id: Option<Uuid>,
+    details: serde_json::Value,
+) {
+    let mut metadata = serde_json::json!({ "event": event, "security": true });
+    if let (Some(metadata), serde_json::Value::Object(details)) = (metadata.as_object_mut(), details) {
+        metadata.extend(details);
+    }
+
+    tracing::warn!("Security event {}: {}", event, message);
+    let result = repo
+        .create(NewLog {
+            level: "warn".to_string(),
+            message,
+            metadata,
+            user_id,
+        })
+        .await;
+
+    if let Err(e) = result {
+        tracing::error!("Failed to record security event {}: {}", event, e);
+    }
+}
+
 #[tauri::command]
 pub async fn get_logs(query: LogQuery) -> Result<Vec<AppLog>, String> {
-    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    get_logs_with(&log_repository()?, query).await
+}
 
+pub(crate) async fn get_logs_with(repo: &dyn LogRepository, query: LogQuery) -> Result<Vec<AppLog>, String> {
     let LogQuery {
         level,
         user_id,
@@ -48,44 +82,15 @@ pub async fn get_logs(query: LogQuery) -> Result<Vec<AppLog>, String> {
         offset,
     } = query;
 
-    let limit = limit.unwrap_or(100).clamp(1, 1_000);
-    let offset = offset.unwrap_or(0).max(0);
-
-    let mut builder = QueryBuilder::new(
-        "SELECT id,
-                level,
-                message,
-                metadata,
-                user_id,
-                created_at
-         FROM app_logs",
-    );
-
-    let mut has_condition = false;
-
-    if let Some(level) = level {
-        builder.push(" WHERE level = ");
-        builder.push_bind(level);
-        has_condition = true;
-    }
-
-    if let Some(user_id) = user_id {
-        builder.push(if has_condition {
-            " AND user_id = "
-        } else {
-            " WHERE user_id = "
-        });
-        builder.push_bind(user_id);
-    }
-
-    builder.push(" ORDER BY created_at DESC LIMIT ");
-    builder.push_bind(limit);
-    builder.push(" OFFSET ");
-    builder.push_bind(offset);
+    let filter = LogFilter {
+        level,
+        user_id,
+        limit: limit.unwrap_or(100).clamp(1, 1_000),
+        offset: offset.unwrap_or(0).max(0),
+    };
 
-    let logs = builder
-        .build_query_as::<AppLog>()
-        .fetch_all(pool.as_ref())
+    let logs = repo
+        .query(filter)
         .await
         .map_err(|e| format!("Failed to fetch logs: {}", e))?;
 
@@ -94,22 +99,20 @@ pub async fn get_logs(query: LogQuery) -> Result<Vec<AppLog>, String> {
 
 #[tauri::command]
 pub async fn delete_old_logs(days_old: i32) -> Result<String, String> {
-    let pool = get_pool_ref().map_err(|e| e.to_string())?;
-
-    let result = sqlx::query(
-        r#"
-        DELETE FROM app_logs
-        WHERE created_at < NOW() - ($1::INT * INTERVAL '1 day')
-        "#,
-    )
-    .bind(days_old)
-    .execute(pool.as_ref())
-    .await
-    .map_err(|e| format!("Failed to delete old logs: {}", e))?;
+    delete_old_logs_with(&log_repository()?, days_old).await
+}
+
+pub(crate) async fn delete_old_logs_with(repo: &dyn LogRepository, days_old: i32) -> Result<String, String> {
+    let cutoff = Utc::now() - Duration::days(i64::from(days_old));
+
+    let rows_affected = repo
+        .delete_older_than(cutoff)
+        .await
+        .map_err(|e| format!("Failed to delete old logs: {}", e))?;
 
     Ok(format!(
         "Deleted {} old log entries",
-        result.rows_affected()
+        rows_affected
     ))
 }
 #[cfg(test)]
@@ -118,6 +121,7 @@ mod tests {
     use crate::database::test_utils::{pool, reset_all_tables};
     use crate::handlers::users::create_user;
     use crate::models::{CreateAppLog, CreateUser, LogQuery};
+    use crate::repositories::memory::InMemoryLogRepository;
     use anyhow::Result as AnyResult;
     use serde_json::json;
     use serial_test::serial;
@@ -187,4 +191,110 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn log_logic_runs_against_in_memory_repository() {
+        let repo = InMemoryLogRepository::new();
+        let user_id = Uuid::new_v4();
+
+        for level in ["info", "warn", "info"] {
+            create_log_with(
+                &repo,
+                CreateAppLog {
+                    level: level.to_string(),
+                    message: format!("{} entry", level),
+                    metadata: None,
+                    user_id: Some(user_id),
+                },
+            )
+            .await
+            .expect("log creation should succeed");
+        }
+
+        let invalid = create_log_with(
+            &repo,
+            CreateAppLog {
+                level: "info".to_string(),
+                message: "   ".to_string(),
+                metadata: None,
+                user_id: None,
+            },
+        )
+        .await;
+        assert!(matches!(invalid, Err(message) if message.starts_with("Invalid log message")));
+
+        let info = get_logs_with(
+            &repo,
+            LogQuery {
+                level: Some("info".to_string()),
+                user_id: Some(user_id),
+                limit: None,
+                offset: None,
+            },
+        )
+        .await
+        .expect("fetching logs should succeed");
+        assert_eq!(info.len(), 2);
+        assert_eq!(info[0].metadata, json!({}));
+
+        let clamped = get_logs_with(
+            &repo,
+            LogQuery {
+                level: None,
+                user_id: None,
+                limit: Some(0),
+                offset: Some(-1),
+            },
+        )
+        .await
+        .expect("fetching logs should succeed");
+        assert_eq!(clamped.len(), 1);
+
+        let deleted = delete_old_logs_with(&repo, 0)
+            .await
+            .expect("deleting old logs should succeed");
+        assert_eq!(deleted, "Deleted 3 old log entries");
+    }
+
+    #[cfg(feature = "module-sqlite")]
+    #[tokio::test]
+    #[serial]
+    async fn logs_flow_on_sqlite() -> AnyResult<()> {
+        crate::database::test_utils::sqlite_pool().await?;
+
+        let user = create_user(sample_user())
+            .await
+            .expect("user creation must succeed for log tests");
+        create_log(CreateAppLog {
+            level: "warn".to_string(),
+            message: "SQLite log entry".to_string(),
+            metadata: Some(json!({"backend": "sqlite"})),
+            user_id: Some(user.id),
+        })
+        .await
+        .expect("log creation should succeed");
+
+        let logs = get_logs(LogQuery {
+            level: Some("warn".to_string()),
+            user_id: Some(user.id),
+            limit: None,
+            offset: None,
+        })
+        .await
+        .expect("fetching logs should succeed");
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].metadata["backend"], json!("sqlite"));
+
+        let kept = delete_old_logs(1)
+            .await
+            .expect("deleting old logs should succeed");
+        assert!(kept.starts_with("Deleted 0"));
+
+        let deleted = delete_old_logs(0)
+            .await
+            .expect("deleting old logs should succeed");
+        assert!(deleted.starts_with("Deleted 1"));
+
+        Ok(())
+    }
 }
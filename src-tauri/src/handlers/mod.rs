@@ -5,16 +5,28 @@
 
 pub mod cache;
 pub mod database;
+pub mod email_verification;
 pub mod filesystem;
 pub mod logs;
+pub mod passwords;
 pub mod rate_limited;
+pub mod secrets;
+pub mod session;
+pub mod settings;
 pub mod system;
+pub mod two_factor;
 pub mod users;
 
 pub use cache::*;
 pub use database::*;
+pub use email_verification::*;
 pub use filesystem::*;
 pub use logs::*;
+pub use passwords::*;
 pub use rate_limited::*;
+pub use secrets::*;
+pub use session::*;
+pub use settings::*;
 pub use system::*;
+pub use two_factor::*;
 pub use users::*;
\ No newline at end of file
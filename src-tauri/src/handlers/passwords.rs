@@ -0,0 +1,407 @@
+//! Password change and reset command handlers.
+//!
+//! Changing a password requires the current one. A forgotten password is
+//! reset with a one-time token delivered by the configured
+//! [`ResetTokenSender`]; requesting a token never reveals whether the email
+//! belongs to an account. Either way, every token issued to the user is
+//! revoked and their other windows are signed out.
+
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::handlers::logs::log_security_event;
+use crate::models::User;
+use crate::password::{verify_password, HashAlgorithm};
+use crate::password_reset::{generate_token, hash_token, reset_sender, token_ttl, ResetTokenSender};
+use crate::repositories::{
+    LogRepository, PasswordResetRepository, SqlLogRepository, SqlPasswordResetRepository,
+    SqlUserRepository, UserRepository,
+};
+use crate::session::{CurrentUser, Session};
+use crate::validation::{validate_email, validate_password, PasswordPolicy};
+
+/// Message returned by `request_password_reset` whether or not the account exists.
+pub const RESET_REQUESTED: &str = "If an account exists for that email, a reset token has been sent";
+
+const INVALID_RESET_TOKEN: &str = "Invalid or expired reset token";
+
+fn repositories() -> Result<(SqlUserRepository, SqlPasswordResetRepository, SqlLogRepository), String> {
+    Ok((
+        SqlUserRepository::from_global().map_err(|e| e.to_string())?,
+        SqlPasswordResetRepository::from_global().map_err(|e| e.to_string())?,
+        SqlLogRepository::from_global().map_err(|e| e.to_string())?,
+    ))
+}
+
+/// Changes the signed-in user's password after checking the current one,
+/// keeping only the calling window signed in.
+#[tauri::command]
+pub async fn change_password(
+    current_user: CurrentUser,
+    current_password: String,
+    new_password: String,
+) -> Result<String, String> {
+    let (users, resets, logs) = repositories()?;
+    change_password_with(
+        &users,
+        &resets,
+        &logs,
+        current_user.user_id,
+        &current_password,
+        &new_password,
+    )
+    .await?;
+
+    current_user
+        .sessions()
+        .sign_out_other_windows(current_user.user_id, current_user.label());
+    Ok("Password changed successfully".to_string())
+}
+
+pub(crate) async fn change_password_with(
+    users: &dyn UserRepository,
+    resets: &dyn PasswordResetRepository,
+    logs: &dyn LogRepository,
+    user_id: Uuid,
+    current_password: &str,
+    new_password: &str,
+) -> Result<(), String> {
+    let user = users
+        .find_by_id(user_id)
+        .await
+        .map_err(|e| format!("Failed to fetch user: {}", e))?
+        .ok_or_else(|| "User not found".to_string())?;
+
+    let matches = verify_password(current_password, &user.password_hash)
+        .map_err(|e| format!("Failed to verify password: {}", e))?;
+    if !matches {
+        return Err("Current password is incorrect".to_string());
+    }
+
+    if current_password == new_password {
+        return Err("New password must differ from the current password".to_string());
+    }
+
+    validate_password(new_password, &PasswordPolicy::from_env(), &user.username, &user.email)
+        .map_err(|e| format!("Invalid password: {}", e))?;
+    store_new_password(users, resets, &user, new_password, Utc::now()).await?;
+
+    log_security_event(
+        logs,
+        "password_changed",
+        "Password changed".to_string(),
+        Some(user.id),
+        json!({}),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Sends a reset token to `email` if it belongs to an active account.
+///
+/// The response is the same either way, so it cannot be used to probe for
+/// accounts.
+#[tauri::command]
+pub async fn request_password_reset(email: String) -> Result<String, String> {
+    let (users, resets, _) = repositories()?;
+    request_password_reset_with(&users, &resets, &reset_sender(), &email, Utc::now()).await?;
+
+    Ok(RESET_REQUESTED.to_string())
+}
+
+pub(crate) async fn request_password_reset_with(
+    users: &dyn UserRepository,
+    resets: &dyn PasswordResetRepository,
+    sender: &dyn ResetTokenSender,
+    email: &str,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let email = validate_email(email).map_err(|e| format!("Invalid email: {}", e))?;
+
+    let user = users
+        .find_by_email(&email)
+        .await
+        .map_err(|e| format!("Failed to request password reset: {}", e))?
+        .filter(|user| user.can_sign_in());
+    let Some(user) = user else {
+        return Ok(());
+    };
+
+    // Only the newest token works.
+    resets
+        .invalidate_for_user(user.id, now)
+        .await
+        .map_err(|e| format!("Failed to request password reset: {}", e))?;
+
+    let token = generate_token();
+    let expires_at = now + token_ttl();
+    resets
+        .create(user.id, &hash_token(&token), expires_at)
+        .await
+        .map_err(|e| format!("Failed to request password reset: {}", e))?;
+
+    // A delivery failure is not reported either, as it would reveal the account.
+    if let Err(e) = sender.send(&user.email, &token, expires_at).await {
+        tracing::error!("Failed to send password reset token to user {}: {}", user.id, e);
+    }
+
+    Ok(())
+}
+
+/// Sets a new password using a reset token, then signs the user out of every
+/// window.
+#[tauri::command]
+pub async fn reset_password(
+    session: Session,
+    token: String,
+    new_password: String,
+) -> Result<String, String> {
+    let (users, resets, logs) = repositories()?;
+    let user_id = reset_password_with(&users, &resets, &logs, &token, &new_password, Utc::now()).await?;
+
+    if let Ok(store) = session.store() {
+        store.sign_out_user(user_id);
+    }
+    Ok("Password reset successfully".to_string())
+}
+
+pub(crate) async fn reset_password_with(
+    users: &dyn UserRepository,
+    resets: &dyn PasswordResetRepository,
+    logs: &dyn LogRepository,
+    token: &str,
+    new_password: &str,
+    now: DateTime<Utc>,
+) -> Result<Uuid, String> {
+    let reset = resets
+        .find_by_hash(&hash_token(token))
+        .await
+        .map_err(|e| format!("Failed to reset password: {}", e))?
+        .filter(|reset| reset.is_usable(now))
+        .ok_or_else(|| INVALID_RESET_TOKEN.to_string())?;
+
+    let user = users
+        .find_by_id(reset.user_id)
+        .await
+        .map_err(|e| format!("Failed to reset password: {}", e))?
+        .filter(|user| user.can_sign_in())
+        .ok_or_else(|| INVALID_RESET_TOKEN.to_string())?;
+
+    // Check the policy first so a rejected password does not spend the token.
+    validate_password(new_password, &PasswordPolicy::from_env(), &user.username, &user.email)
+        .map_err(|e| format!("Invalid password: {}", e))?;
+
+    let consumed = resets
+        .consume(reset.id, now)
+        .await
+        .map_err(|e| format!("Failed to reset password: {}", e))?;
+    if !consumed {
+        return Err(INVALID_RESET_TOKEN.to_string());
+    }
+
+    store_new_password(users, resets, &user, new_password, now).await?;
+    users
+        .clear_failed_logins(user.id)
+        .await
+        .map_err(|e| format!("Failed to reset password: {}", e))?;
+
+    log_security_event(
+        logs,
+        "password_reset",
+        "Password reset with a reset token".to_string(),
+        Some(user.id),
+        json!({ "tokenId": reset.id }),
+    )
+    .await;
+
+    Ok(user.id)
+}
+
+/// Hashes and stores `new_password`, spends any outstanding reset tokens and
+/// revokes the user's token sessions.
+async fn store_new_password(
+    users: &dyn UserRepository,
+    resets: &dyn PasswordResetRepository,
+    user: &User,
+    new_password: &str,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let password_hash = HashAlgorithm::from_env()
+        .hash(new_password)
+        .map_err(|e| format!("Failed to hash password: {}", e))?;
+
+    let updated = users
+        .set_password_hash(user.id, &password_hash)
+        .await
+        .map_err(|e| format!("Failed to update password: {}", e))?;
+    if !updated {
+        return Err("User not found".to_string());
+    }
+
+    resets
+        .invalidate_for_user(user.id, now)
+        .await
+        .map_err(|e| format!("Failed to update password: {}", e))?;
+
+    users
+        .revoke_auth_sessions(user.id, now)
+        .await
+        .map_err(|e| format!("Failed to update password: {}", e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::users::{authenticate_user_with, create_user_with};
+    use crate::lockout::LoginThrottle;
+    use crate::models::{CreateUser, LoginRequest};
+    use crate::repositories::memory::{
+        InMemoryLogRepository, InMemoryPasswordResetRepository, InMemoryUserRepository,
+    };
+    use crate::totp::InMemoryTotpSecrets;
+    use chrono::Duration;
+    use std::sync::Mutex;
+
+    /// Fails every delivery.
+    struct FailingSender;
+
+    #[async_trait::async_trait]
+    impl ResetTokenSender for FailingSender {
+        async fn send(&self, _email: &str, _token: &str, _expires_at: DateTime<Utc>) -> anyhow::Result<()> {
+            anyhow::bail!("mail server unreachable")
+        }
+    }
+
+    /// Keeps sent tokens for the test to read.
+    #[derive(Default)]
+    struct RecordingSender {
+        sent: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ResetTokenSender for RecordingSender {
+        async fn send(&self, email: &str, token: &str, _expires_at: DateTime<Utc>) -> anyhow::Result<()> {
+            self.sent.lock().unwrap().push((email.to_string(), token.to_string()));
+            Ok(())
+        }
+    }
+
+    async fn create_account(users: &InMemoryUserRepository) -> Uuid {
+        create_user_with(
+            users,
+            CreateUser {
+                email: "reset@example.com".to_string(),
+                username: "reset_user".to_string(),
+                password: "Sup3r$ecret".to_string(),
+                first_name: None,
+                last_name: None,
+            },
+        )
+        .await
+        .expect("user creation should succeed")
+        .id
+    }
+
+    async fn can_log_in(users: &InMemoryUserRepository, password: &str) -> bool {
+        authenticate_user_with(
+            users,
+            &InMemoryLogRepository::new(),
+            &LoginThrottle::default(),
+            &InMemoryTotpSecrets::default(),
+            false,
+            "test",
+            LoginRequest {
+                email: "reset@example.com".to_string(),
+                password: password.to_string(),
+                totp_code: None,
+            },
+        )
+        .await
+        .expect("authentication should not fail")
+        .is_some()
+    }
+
+    #[tokio::test]
+    async fn change_password_requires_the_current_password() {
+        let users = InMemoryUserRepository::new();
+        let resets = InMemoryPasswordResetRepository::new();
+        let logs = InMemoryLogRepository::new();
+        let user_id = create_account(&users).await;
+
+        let wrong = change_password_with(&users, &resets, &logs, user_id, "wrong", "N3w$ecret!").await;
+        assert_eq!(wrong.unwrap_err(), "Current password is incorrect");
+
+        let weak = change_password_with(&users, &resets, &logs, user_id, "Sup3r$ecret", "short").await;
+        assert!(weak.unwrap_err().starts_with("Invalid password:"));
+
+        assert_eq!(users.sessions_revoked_at(user_id).await, None);
+        change_password_with(&users, &resets, &logs, user_id, "Sup3r$ecret", "N3w$ecret!")
+            .await
+            .expect("password change should succeed");
+        assert!(users.sessions_revoked_at(user_id).await.is_some());
+        assert!(!can_log_in(&users, "Sup3r$ecret").await);
+        assert!(can_log_in(&users, "N3w$ecret!").await);
+    }
+
+    #[tokio::test]
+    async fn reset_tokens_are_single_use_and_expire() {
+        let users = InMemoryUserRepository::new();
+        let resets = InMemoryPasswordResetRepository::new();
+        let logs = InMemoryLogRepository::new();
+        let sender = RecordingSender::default();
+        let user_id = create_account(&users).await;
+        let now = Utc::now();
+
+        request_password_reset_with(&users, &resets, &sender, "nobody@example.com", now)
+            .await
+            .expect("unknown emails should not be reported");
+        request_password_reset_with(&users, &resets, &sender, "reset@example.com", now)
+            .await
+            .unwrap();
+        request_password_reset_with(&users, &resets, &sender, "reset@example.com", now)
+            .await
+            .unwrap();
+
+        let sent = sender.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 2);
+        assert!(sent.iter().all(|(email, _)| email == "reset@example.com"));
+        let (superseded, token) = (sent[0].1.clone(), sent[1].1.clone());
+
+        let reused = reset_password_with(&users, &resets, &logs, &superseded, "N3w$ecret!", now).await;
+        assert_eq!(reused.unwrap_err(), INVALID_RESET_TOKEN);
+
+        let expired = now + token_ttl() + Duration::seconds(1);
+        let late = reset_password_with(&users, &resets, &logs, &token, "N3w$ecret!", expired).await;
+        assert_eq!(late.unwrap_err(), INVALID_RESET_TOKEN);
+
+        let weak = reset_password_with(&users, &resets, &logs, &token, "password", now).await;
+        assert!(weak.unwrap_err().starts_with("Invalid password:"));
+
+        let reset_user = reset_password_with(&users, &resets, &logs, &token, "N3w$ecret!", now)
+            .await
+            .expect("reset should succeed");
+        assert_eq!(reset_user, user_id);
+        assert_eq!(users.sessions_revoked_at(user_id).await, Some(now));
+        assert!(can_log_in(&users, "N3w$ecret!").await);
+
+        let again = reset_password_with(&users, &resets, &logs, &token, "An0ther$ecret", now).await;
+        assert_eq!(again.unwrap_err(), INVALID_RESET_TOKEN);
+    }
+
+    #[tokio::test]
+    async fn failed_deliveries_look_like_unknown_emails() {
+        let users = InMemoryUserRepository::new();
+        let resets = InMemoryPasswordResetRepository::new();
+        create_account(&users).await;
+
+        for email in ["reset@example.com", "nobody@example.com"] {
+            request_password_reset_with(&users, &resets, &FailingSender, email, Utc::now())
+                .await
+                .expect("delivery failures should not be reported");
+        }
+    }
+}
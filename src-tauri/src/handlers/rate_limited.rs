@@ -1,23 +1,46 @@
 //! Rate-limited wrappers for all Tauri command handlers.
+//!
+//! Calls from a signed-in window also count against that user's per-user limit.
+//! Wrappers declared with `requires: Permission::...` reject callers whose
+//! role lacks that permission with an `Unauthorized` or `Forbidden` `AppError`.
 
 use crate::rate_limiter::RateLimiterConfig;
 use crate::handlers::*;
+use crate::permissions::Permission;
+use crate::session::{CurrentUser, Session};
 use crate::logging::handlers::{get_log_config, update_log_config, get_log_entries, clear_old_logs, get_log_stats, create_test_log};
+#[cfg(feature = "module-auth")]
+use crate::modules::auth::{auth_check, auth_list_sessions, auth_login, auth_logout, auth_revoke_session};
 use std::sync::Arc;
 use tauri::State;
 
 /// Helper macro to create rate-limited wrappers for command handlers.
 macro_rules! create_rate_limited_handler {
-    ($func_name:ident, $original_func:ident, $($param:ident: $param_type:ty),* $(,)?) => {
+    ($func_name:ident, $original_func:ident, requires: $permission:expr, $($param:ident: $param_type:ty),* $(,)?) => {
         #[tauri::command]
         pub async fn $func_name(
             rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+            caller: Session,
             $($param: $param_type,)*
         ) -> Result<serde_json::Value, String> {
-            if let Err(e) = rate_limiter.check_rate_limit(None).await {
-                tracing::warn!("Rate limit exceeded: {}", e);
-                return Err(format!("Rate limit exceeded: {}", e));
+            check_rate_limit(&rate_limiter, &caller).await?;
+            caller.authorize($permission).map_err(|e| e.to_string())?;
+
+            let result = $original_func($($param,)*).await;
+            match result {
+                Ok(value) => serde_json::to_value(value).map_err(|e| format!("Serialization error: {}", e)),
+                Err(e) => Err(format!("{}", e)),
             }
+        }
+    };
+    ($func_name:ident, $original_func:ident, $($param:ident: $param_type:ty),* $(,)?) => {
+        #[tauri::command]
+        pub async fn $func_name(
+            rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+            caller: Session,
+            $($param: $param_type,)*
+        ) -> Result<serde_json::Value, String> {
+            check_rate_limit(&rate_limiter, &caller).await?;
 
             let result = $original_func($($param,)*).await;
             match result {
@@ -28,6 +51,16 @@ macro_rules! create_rate_limited_handler {
     };
 }
 
+/// Applies the global limit and, for signed-in callers, the per-user limit.
+async fn check_rate_limit(rate_limiter: &RateLimiterConfig, caller: &Session) -> Result<(), String> {
+    if let Err(e) = rate_limiter.check_rate_limit(caller.rate_limit_key().as_deref()).await {
+        tracing::warn!("Rate limit exceeded: {}", e);
+        return Err(format!("Rate limit exceeded: {}", e));
+    }
+
+    Ok(())
+}
+
 // Create rate-limited wrappers for database commands
 create_rate_limited_handler!(
     rl_check_database_connection,
@@ -37,22 +70,61 @@ create_rate_limited_handler!(
 create_rate_limited_handler!(
     rl_initialize_database,
     initialize_database,
+    requires: Permission::SystemAdmin,
 );
 
 create_rate_limited_handler!(
     rl_run_migrations,
     run_migrations,
+    requires: Permission::SystemAdmin,
+);
+
+create_rate_limited_handler!(
+    rl_rollback_migrations,
+    rollback_migrations,
+    requires: Permission::SystemAdmin,
+    target_version: i64
+);
+
+create_rate_limited_handler!(
+    rl_get_applied_migrations,
+    get_applied_migrations,
+    requires: Permission::SystemAdmin,
+);
+
+create_rate_limited_handler!(
+    rl_set_database_credentials,
+    set_database_credentials,
+    requires: Permission::SystemAdmin,
+    database_url: String
+);
+
+create_rate_limited_handler!(
+    rl_rotate_database_password,
+    rotate_database_password,
+    requires: Permission::SystemAdmin,
+    password: String
 );
 
 // Create rate-limited wrappers for user commands
 create_rate_limited_handler!(
     rl_get_all_users,
     get_all_users,
+    requires: Permission::UsersRead,
+    include_deleted: Option<bool>
+);
+
+create_rate_limited_handler!(
+    rl_list_users,
+    list_users,
+    requires: Permission::UsersRead,
+    query: crate::models::UserQuery
 );
 
 create_rate_limited_handler!(
     rl_get_user_by_id,
     get_user_by_id,
+    current_user: CurrentUser,
     user_id: String
 );
 
@@ -65,6 +137,7 @@ create_rate_limited_handler!(
 create_rate_limited_handler!(
     rl_update_user,
     update_user,
+    current_user: CurrentUser,
     user_id: String,
     user: crate::models::UpdateUser
 );
@@ -72,15 +145,177 @@ create_rate_limited_handler!(
 create_rate_limited_handler!(
     rl_delete_user,
     delete_user,
+    current_user: CurrentUser,
+    user_id: String
+);
+
+create_rate_limited_handler!(
+    rl_restore_user,
+    restore_user,
+    current_user: CurrentUser,
     user_id: String
 );
 
+create_rate_limited_handler!(
+    rl_purge_deleted_users,
+    purge_deleted_users,
+    current_user: CurrentUser
+);
+
+create_rate_limited_handler!(
+    rl_deactivate_user,
+    deactivate_user,
+    current_user: CurrentUser,
+    user_id: String
+);
+
+create_rate_limited_handler!(
+    rl_reactivate_user,
+    reactivate_user,
+    current_user: CurrentUser,
+    user_id: String
+);
+
+create_rate_limited_handler!(
+    rl_set_user_role,
+    set_user_role,
+    current_user: CurrentUser,
+    user_id: String,
+    role: String
+);
+
+create_rate_limited_handler!(
+    rl_get_user_settings,
+    get_user_settings,
+    current_user: CurrentUser,
+    user_id: String
+);
+
+create_rate_limited_handler!(
+    rl_upsert_user_settings,
+    upsert_user_settings,
+    current_user: CurrentUser,
+    user_id: String,
+    settings: crate::models::UpdateUserSettings
+);
+
+create_rate_limited_handler!(
+    rl_reset_user_settings,
+    reset_user_settings,
+    current_user: CurrentUser,
+    user_id: String
+);
+
+create_rate_limited_handler!(
+    rl_get_settings_schemas,
+    get_settings_schemas,
+);
+
+create_rate_limited_handler!(
+    rl_export_settings,
+    export_settings,
+    current_user: CurrentUser,
+    user_id: String,
+    path: String
+);
+
+create_rate_limited_handler!(
+    rl_import_settings,
+    import_settings,
+    current_user: CurrentUser,
+    user_id: String,
+    path: String
+);
+
+create_rate_limited_handler!(
+    rl_change_password,
+    change_password,
+    current_user: CurrentUser,
+    current_password: String,
+    new_password: String
+);
+
+create_rate_limited_handler!(
+    rl_request_password_reset,
+    request_password_reset,
+    email: String
+);
+
+create_rate_limited_handler!(
+    rl_reset_password,
+    reset_password,
+    session: Session,
+    token: String,
+    new_password: String
+);
+
+create_rate_limited_handler!(
+    rl_begin_totp_enrollment,
+    begin_totp_enrollment,
+    current_user: CurrentUser
+);
+
+create_rate_limited_handler!(
+    rl_confirm_totp_enrollment,
+    confirm_totp_enrollment,
+    current_user: CurrentUser,
+    code: String
+);
+
+create_rate_limited_handler!(
+    rl_disable_totp,
+    disable_totp,
+    current_user: CurrentUser,
+    password: String,
+    code: String
+);
+
+create_rate_limited_handler!(
+    rl_regenerate_recovery_codes,
+    regenerate_recovery_codes,
+    current_user: CurrentUser,
+    code: String
+);
+
+create_rate_limited_handler!(
+    rl_request_email_verification,
+    request_email_verification,
+    email: String
+);
+
+create_rate_limited_handler!(
+    rl_verify_email,
+    verify_email,
+    token: String
+);
+
 create_rate_limited_handler!(
     rl_authenticate_user,
     authenticate_user,
+    session: Session,
     credentials: crate::models::LoginRequest
 );
 
+// Create rate-limited wrappers for session commands
+create_rate_limited_handler!(
+    rl_sign_in,
+    sign_in,
+    session: Session,
+    login_data: crate::models::LoginRequest
+);
+
+create_rate_limited_handler!(
+    rl_sign_out,
+    sign_out,
+    session: Session
+);
+
+create_rate_limited_handler!(
+    rl_get_current_user,
+    get_current_user,
+    session: Session
+);
+
 // Create rate-limited wrappers for log commands
 create_rate_limited_handler!(
     rl_create_log,
@@ -91,12 +326,14 @@ create_rate_limited_handler!(
 create_rate_limited_handler!(
     rl_get_logs,
     get_logs,
+    requires: Permission::LogsRead,
     query: crate::models::logs::LogQuery
 );
 
 create_rate_limited_handler!(
     rl_delete_old_logs,
     delete_old_logs,
+    requires: Permission::LogsPurge,
     days: i32
 );
 
@@ -242,11 +479,9 @@ create_rate_limited_handler!(
 #[tauri::command]
 pub async fn rl_get_log_config(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+    caller: Session,
 ) -> Result<crate::logging::config::AppLogConfig, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
+    check_rate_limit(&rate_limiter, &caller).await?;
 
     get_log_config().await
 }
@@ -254,12 +489,11 @@ pub async fn rl_get_log_config(
 #[tauri::command]
 pub async fn rl_update_log_config(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+    caller: Session,
     config: crate::logging::config::AppLogConfig,
 ) -> Result<String, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
+    check_rate_limit(&rate_limiter, &caller).await?;
+    caller.authorize(Permission::SystemAdmin).map_err(|e| e.to_string())?;
 
     update_log_config(config).await
 }
@@ -267,12 +501,11 @@ pub async fn rl_update_log_config(
 #[tauri::command]
 pub async fn rl_get_log_entries(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+    caller: Session,
     params: crate::logging::handlers::LogQueryParams,
 ) -> Result<crate::logging::handlers::LogResponse, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
+    check_rate_limit(&rate_limiter, &caller).await?;
+    caller.authorize(Permission::LogsRead).map_err(|e| e.to_string())?;
 
     get_log_entries(params).await
 }
@@ -280,12 +513,11 @@ pub async fn rl_get_log_entries(
 #[tauri::command]
 pub async fn rl_clear_old_logs(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+    caller: Session,
     days_to_keep: u32,
 ) -> Result<String, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
+    check_rate_limit(&rate_limiter, &caller).await?;
+    caller.authorize(Permission::LogsPurge).map_err(|e| e.to_string())?;
 
     clear_old_logs(days_to_keep).await
 }
@@ -293,11 +525,10 @@ pub async fn rl_clear_old_logs(
 #[tauri::command]
 pub async fn rl_get_log_stats(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+    caller: Session,
 ) -> Result<std::collections::HashMap<String, serde_json::Value>, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
+    check_rate_limit(&rate_limiter, &caller).await?;
+    caller.authorize(Permission::LogsRead).map_err(|e| e.to_string())?;
 
     get_log_stats().await
 }
@@ -305,13 +536,11 @@ pub async fn rl_get_log_stats(
 #[tauri::command]
 pub async fn rl_create_test_log(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+    caller: Session,
     level: String,
     message: String,
 ) -> Result<String, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
+    check_rate_limit(&rate_limiter, &caller).await?;
 
     create_test_log(level, message).await
 }
@@ -348,16 +577,85 @@ create_rate_limited_handler!(
     is_cache_available,
 );
 
+// Create rate-limited wrappers for secret storage commands
+create_rate_limited_handler!(
+    rl_put_secret,
+    put_secret,
+    requires: Permission::SystemAdmin,
+    client: String,
+    key: String,
+    value: String
+);
+
+create_rate_limited_handler!(
+    rl_get_secret,
+    get_secret,
+    requires: Permission::SystemAdmin,
+    client: String,
+    key: String
+);
+
+create_rate_limited_handler!(
+    rl_delete_secret,
+    delete_secret,
+    requires: Permission::SystemAdmin,
+    client: String,
+    key: String
+);
+
+create_rate_limited_handler!(
+    rl_list_secret_keys,
+    list_secret_keys,
+    requires: Permission::SystemAdmin,
+    client: String
+);
+
+// Token-based commands of the auth module, present only when it is enabled.
+#[cfg(feature = "module-auth")]
+create_rate_limited_handler!(
+    rl_auth_login,
+    auth_login,
+    session: Session,
+    request: crate::models::LoginRequest
+);
+
+#[cfg(feature = "module-auth")]
+create_rate_limited_handler!(
+    rl_auth_logout,
+    auth_logout,
+    token: String
+);
+
+#[cfg(feature = "module-auth")]
+create_rate_limited_handler!(
+    rl_auth_check,
+    auth_check,
+    token: String
+);
+
+#[cfg(feature = "module-auth")]
+create_rate_limited_handler!(
+    rl_auth_list_sessions,
+    auth_list_sessions,
+    token: String
+);
+
+#[cfg(feature = "module-auth")]
+create_rate_limited_handler!(
+    rl_auth_revoke_session,
+    auth_revoke_session,
+    token: String,
+    session_id: String
+);
+
 // Special handler for greet function
 #[tauri::command]
 pub async fn rl_greet(
     rate_limiter: State<'_, Arc<RateLimiterConfig>>,
+    caller: Session,
     name: String,
 ) -> Result<String, String> {
-    if let Err(e) = rate_limiter.check_rate_limit(None).await {
-        tracing::warn!("Rate limit exceeded for greet: {}", e);
-        return Err(format!("Rate limit exceeded: {}", e));
-    }
+    check_rate_limit(&rate_limiter, &caller).await?;
 
     Ok(format!("Hello, {}! You've been greeted from Rust!", name))
 }
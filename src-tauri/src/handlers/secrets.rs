@@ -0,0 +1,145 @@
+//! Secret storage command handlers backed by the Stronghold vault.
+//!
+//! Values are UTF-8 strings such as API tokens. Clients used internally, like
+//! the database credentials, cannot be read or changed through these commands.
+
+use crate::database::credentials;
+use crate::errors::{AppError, AppResult, ErrorCode, IntoAppError};
+use crate::stronghold::{self, Error as StrongholdError};
+use crate::totp;
+
+/// Stronghold clients reserved for backend use; `auth` holds the token signing
+/// key and `totp` the two-factor secrets.
+const RESERVED_CLIENTS: &[&str] = &[credentials::CLIENT, "auth", totp::SECRET_CLIENT];
+
+fn check_client(client: &str) -> AppResult<()> {
+    if RESERVED_CLIENTS.contains(&client) {
+        return Err(AppError::forbidden(format!(
+            "Secret client '{}' is reserved",
+            client
+        )));
+    }
+    Ok(())
+}
+
+fn vault_error(error: StrongholdError) -> AppError {
+    match error {
+        StrongholdError::InvalidName(_) => AppError::new(ErrorCode::InvalidInput, error.to_string()),
+        StrongholdError::NotInitialized => {
+            AppError::new(ErrorCode::ConfigurationError, error.to_string())
+        }
+        _ => AppError::internal_error(error.to_string()),
+    }
+}
+
+/// Stores a secret and saves the vault.
+#[tauri::command]
+pub async fn put_secret(client: String, key: String, value: String) -> AppResult<()> {
+    check_client(&client)?;
+
+    let vault = stronghold::vault().map_err(vault_error)?;
+    let manager = vault.lock().await;
+    manager
+        .put_secret(&client, &key, value.into_bytes())
+        .map_err(vault_error)?;
+    manager.save().map_err(vault_error)
+}
+
+/// Returns a secret, or `None` if the key is not set.
+#[tauri::command]
+pub async fn get_secret(client: String, key: String) -> AppResult<Option<String>> {
+    check_client(&client)?;
+
+    let vault = stronghold::vault().map_err(vault_error)?;
+    let value = vault
+        .lock()
+        .await
+        .get_secret(&client, &key)
+        .map_err(vault_error)?;
+
+    value
+        .map(String::from_utf8)
+        .transpose()
+        .into_app_error(ErrorCode::InvalidFormat)
+}
+
+/// Deletes a secret and saves the vault, returning whether it existed.
+#[tauri::command]
+pub async fn delete_secret(client: String, key: String) -> AppResult<bool> {
+    check_client(&client)?;
+
+    let vault = stronghold::vault().map_err(vault_error)?;
+    let manager = vault.lock().await;
+    let deleted = manager.delete_secret(&client, &key).map_err(vault_error)?;
+    manager.save().map_err(vault_error)?;
+
+    Ok(deleted)
+}
+
+/// Lists the keys stored for a client.
+#[tauri::command]
+pub async fn list_secret_keys(client: String) -> AppResult<Vec<String>> {
+    check_client(&client)?;
+
+    let vault = stronghold::vault().map_err(vault_error)?;
+    let keys = vault.lock().await.list_keys(&client).map_err(vault_error)?;
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stronghold::{KdfParams, StrongholdManager, VAULT_FILE};
+    use serial_test::serial;
+
+    fn install_test_vault(dir: &std::path::Path) {
+        let params = KdfParams {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let manager = StrongholdManager::open_with_params(dir.join(VAULT_FILE), "password", params)
+            .expect("test vault should open");
+        stronghold::install_vault(manager);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn secret_commands_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        install_test_vault(dir.path());
+
+        put_secret("integrations".into(), "slack".into(), "xoxb-token".into())
+            .await
+            .expect("storing a secret should succeed");
+        assert_eq!(
+            get_secret("integrations".into(), "slack".into()).await.unwrap(),
+            Some("xoxb-token".to_string())
+        );
+        assert_eq!(
+            list_secret_keys("integrations".into()).await.unwrap(),
+            vec!["slack".to_string()]
+        );
+        assert!(delete_secret("integrations".into(), "slack".into()).await.unwrap());
+        assert_eq!(get_secret("integrations".into(), "slack".into()).await.unwrap(), None);
+
+        stronghold::reset_vault_for_tests();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn reserved_and_invalid_names_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        install_test_vault(dir.path());
+
+        let reserved = get_secret(credentials::CLIENT.into(), "database_url".into()).await;
+        assert!(matches!(reserved, Err(AppError { code: ErrorCode::Forbidden, .. })));
+
+        let invalid = put_secret("integrations".into(), String::new(), "value".into()).await;
+        assert!(matches!(invalid, Err(AppError { code: ErrorCode::InvalidInput, .. })));
+
+        stronghold::reset_vault_for_tests();
+        let missing = list_secret_keys("integrations".into()).await;
+        assert!(matches!(missing, Err(AppError { code: ErrorCode::ConfigurationError, .. })));
+    }
+}
@@ -0,0 +1,150 @@
+//! Sign-in command handlers for the per-window session store.
+
+use crate::email_verification::verification_required;
+use crate::handlers::users::authenticate_user_with;
+use crate::lockout::{login_throttle, LoginThrottle};
+use crate::models::{LoginRequest, PublicUser};
+use crate::repositories::{LogRepository, SqlLogRepository, SqlUserRepository, UserRepository};
+use crate::session::{Session, SessionUser};
+use crate::totp::{TotpSecretStore, VaultTotpSecrets};
+
+/// Verifies credentials and signs the user in to the calling window with the
+/// permissions of their role.
+#[tauri::command]
+pub async fn sign_in(session: Session, login_data: LoginRequest) -> Result<PublicUser, String> {
+    let repo = SqlUserRepository::from_global().map_err(|e| e.to_string())?;
+    let logs = SqlLogRepository::from_global().map_err(|e| e.to_string())?;
+    sign_in_with(
+        &repo,
+        &logs,
+        login_throttle(),
+        &VaultTotpSecrets,
+        verification_required(),
+        &session,
+        login_data,
+    )
+    .await
+}
+
+pub(crate) async fn sign_in_with(
+    repo: &dyn UserRepository,
+    logs: &dyn LogRepository,
+    throttle: &LoginThrottle,
+    totp: &dyn TotpSecretStore,
+    require_verified_email: bool,
+    session: &Session,
+    login_data: LoginRequest,
+) -> Result<PublicUser, String> {
+    let store = session.store()?;
+
+    let user = authenticate_user_with(
+        repo,
+        logs,
+        throttle,
+        totp,
+        require_verified_email,
+        &session.label,
+        login_data,
+    )
+    .await?
+        .ok_or_else(|| "Invalid email or password".to_string())?;
+    let permissions = repo
+        .permissions_for_role(&user.role)
+        .await
+        .map_err(|e| format!("Failed to load permissions: {}", e))?;
+
+    store.sign_in(&session.label, &user, permissions);
+    Ok(user)
+}
+
+/// Signs the calling window out, returning whether it had a session.
+#[tauri::command]
+pub async fn sign_out(session: Session) -> Result<bool, String> {
+    let signed_out = session.store()?.sign_out(&session.label);
+    Ok(signed_out.is_some())
+}
+
+/// Returns the user signed in to the calling window, if any.
+#[tauri::command]
+pub async fn get_current_user(session: Session) -> Result<Option<SessionUser>, String> {
+    Ok(session.user)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::users::create_user_with;
+    use crate::models::CreateUser;
+    use crate::repositories::memory::{InMemoryLogRepository, InMemoryUserRepository};
+    use crate::session::SessionStore;
+    use crate::totp::InMemoryTotpSecrets;
+    use std::sync::Arc;
+
+    fn session_for(store: &Arc<SessionStore>, label: &str) -> Session {
+        Session::for_tests(label, store.clone())
+    }
+
+    #[tokio::test]
+    async fn sign_in_binds_the_user_to_the_calling_window() {
+        let repo = InMemoryUserRepository::new();
+        let logs = InMemoryLogRepository::new();
+        let throttle = LoginThrottle::default();
+        let store = Arc::new(SessionStore::new());
+        let created = create_user_with(
+            &repo,
+            CreateUser {
+                email: "window@example.com".to_string(),
+                username: "window_user".to_string(),
+                password: "Sup3r$ecret".to_string(),
+                first_name: None,
+                last_name: None,
+            },
+        )
+        .await
+        .expect("user creation should succeed");
+
+        let wrong = sign_in_with(
+            &repo,
+            &logs,
+            &throttle,
+            &InMemoryTotpSecrets::default(),
+            false,
+            &session_for(&store, "main"),
+            LoginRequest {
+                email: "window@example.com".to_string(),
+                password: "wrong".to_string(),
+                totp_code: None,
+            },
+        )
+        .await;
+        assert!(wrong.is_err());
+        assert_eq!(store.get("main"), None);
+
+        sign_in_with(
+            &repo,
+            &logs,
+            &throttle,
+            &InMemoryTotpSecrets::default(),
+            false,
+            &session_for(&store, "main"),
+            LoginRequest {
+                email: "window@example.com".to_string(),
+                password: "Sup3r$ecret".to_string(),
+                totp_code: None,
+            },
+        )
+        .await
+        .expect("sign-in should succeed");
+
+        let current = get_current_user(session_for(&store, "main"))
+            .await
+            .unwrap()
+            .expect("window should have a session");
+        assert_eq!(current.user_id, created.id);
+        assert!(current.has_permission(crate::permissions::Permission::UsersDelete));
+        assert_eq!(get_current_user(session_for(&store, "other")).await.unwrap(), None);
+
+        assert!(sign_out(session_for(&store, "main")).await.unwrap());
+        assert!(!sign_out(session_for(&store, "main")).await.unwrap());
+    }
+}
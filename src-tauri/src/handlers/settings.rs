@@ -0,0 +1,753 @@
+//! User settings command handlers.
+//!
+//! Users read and change their own settings; other users' settings need
+//! `users:read` or `users:write`. Users who never saved settings get the
+//! defaults from [`crate::settings`], and values in registered namespaces are
+//! checked against their schemas. Writes that change anything are published
+//! to [`settings_events`]. Settings can also be exported to and imported from
+//! a [`SettingsBundle`] file inside the sandboxed filesystem root.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::handlers::filesystem::{read_text_file, write_text_file};
+use crate::logging::apply_log_config;
+use crate::logging::config::{save_config_to_file, AppLogConfig};
+use crate::logging::handlers::get_log_config;
+use crate::models::{UpdateUserSettings, UserSettings};
+use crate::permissions::Permission;
+use crate::repositories::{SettingsRepository, SqlSettingsRepository};
+use crate::session::CurrentUser;
+use crate::settings::bundle::SettingsBundle;
+use crate::settings::events::{settings_events, SettingsChanged, SettingsEvents};
+use crate::settings::schema::{settings_registry, SettingsRegistry, SettingsSchema};
+use crate::settings::{merge_patch, unsaved, validate_app_settings, with_defaults};
+use crate::validation::{validate_language, validate_theme};
+
+/// Bundle part holding the logging configuration.
+const LOG_CONFIG_PART: &str = "logConfig";
+
+/// What an import applied.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedSettings {
+    pub settings: UserSettings,
+    /// Bundle parts that were not applied, such as `logConfig` for callers
+    /// without `system:admin`.
+    pub skipped: Vec<String>,
+}
+
+fn settings_repository() -> Result<SqlSettingsRepository, String> {
+    SqlSettingsRepository::from_global().map_err(|e| e.to_string())
+}
+
+/// Returns a user's settings, or the defaults if they have not saved any.
+#[tauri::command]
+pub async fn get_user_settings(current_user: CurrentUser, user_id: String) -> Result<UserSettings, String> {
+    let uuid = current_user
+        .ensure_self_or(&user_id, Permission::UsersRead)
+        .map_err(|e| e.to_string())?;
+
+    get_user_settings_with(&settings_repository()?, settings_registry(), uuid).await
+}
+
+pub(crate) async fn get_user_settings_with(
+    repo: &dyn SettingsRepository,
+    registry: &SettingsRegistry,
+    user_id: Uuid,
+) -> Result<UserSettings, String> {
+    let stored = repo
+        .find_by_user(user_id)
+        .await
+        .map_err(|e| format!("Failed to fetch settings: {}", e))?;
+
+    Ok(with_defaults(stored.unwrap_or_else(|| unsaved(user_id, Utc::now())), registry))
+}
+
+/// Creates or updates a user's settings.
+///
+/// Fields left out are kept. `settingsData` is a JSON merge patch: nested
+/// objects are merged and `null` removes a key, restoring its default. The
+/// patch is applied to the stored row under a lock, so concurrent writes to
+/// different keys do not undo each other.
+/// App settings and registered namespaces the patch touches must have valid
+/// values; the error lists every field that does not.
+#[tauri::command]
+pub async fn upsert_user_settings(
+    current_user: CurrentUser,
+    user_id: String,
+    settings: UpdateUserSettings,
+) -> Result<UserSettings, String> {
+    let uuid = current_user
+        .ensure_self_or(&user_id, Permission::UsersWrite)
+        .map_err(|e| e.to_string())?;
+
+    upsert_user_settings_with(&settings_repository()?, settings_registry(), settings_events(), uuid, settings).await
+}
+
+pub(crate) async fn upsert_user_settings_with(
+    repo: &dyn SettingsRepository,
+    registry: &SettingsRegistry,
+    events: &SettingsEvents,
+    user_id: Uuid,
+    changes: UpdateUserSettings,
+) -> Result<UserSettings, String> {
+    let UpdateUserSettings {
+        theme,
+        language,
+        notifications_enabled,
+        settings_data,
+    } = changes;
+
+    let theme = match theme.as_deref() {
+        Some(theme) => Some(validate_theme(theme).map_err(|e| format!("Invalid theme: {}", e))?),
+        None => None,
+    };
+    let language = match language.as_deref() {
+        Some(language) => Some(validate_language(language).map_err(|e| format!("Invalid language: {}", e))?),
+        None => None,
+    };
+    if settings_data.as_ref().is_some_and(|patch| !patch.is_object()) {
+        return Err("Invalid settings data: must be a JSON object".to_string());
+    }
+
+    // The patch is merged into the stored row while the row is locked, so
+    // concurrent patches to different keys all apply.
+    let change = Box::new(|stored: Option<&UserSettings>| {
+        let mut settings = stored.cloned().unwrap_or_else(|| {
+            let mut settings = unsaved(user_id, Utc::now());
+            settings.id = Uuid::new_v4();
+            settings
+        });
+
+        if let Some(theme) = theme {
+            settings.theme = theme;
+        }
+        if let Some(language) = language {
+            settings.language = language;
+        }
+        if let Some(notifications_enabled) = notifications_enabled {
+            settings.notifications_enabled = notifications_enabled;
+        }
+        if let Some(patch) = &settings_data {
+            merge_patch(&mut settings.settings_data, patch);
+            let mut errors = validate_app_settings(&settings.settings_data, patch).err().unwrap_or_default();
+            errors.extend(registry.validate(&settings.settings_data, patch).err().unwrap_or_default());
+            if !errors.is_empty() {
+                let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                return Err(format!("Invalid settings data: {}", errors.join("; ")));
+            }
+        }
+
+        Ok(settings)
+    });
+
+    let (previous, saved) = repo
+        .update(user_id, change)
+        .await
+        .map_err(|e| format!("Failed to save settings: {}", e))??;
+
+    let before = with_defaults(previous.unwrap_or_else(|| unsaved(user_id, Utc::now())), registry);
+    let saved = with_defaults(saved, registry);
+    if let Some(event) = SettingsChanged::between(&before, &saved) {
+        events.publish(&event);
+    }
+
+    Ok(saved)
+}
+
+/// Deletes a user's stored settings and returns the defaults.
+#[tauri::command]
+pub async fn reset_user_settings(current_user: CurrentUser, user_id: String) -> Result<UserSettings, String> {
+    let uuid = current_user
+        .ensure_self_or(&user_id, Permission::UsersWrite)
+        .map_err(|e| e.to_string())?;
+
+    reset_user_settings_with(&settings_repository()?, settings_registry(), settings_events(), uuid).await
+}
+
+pub(crate) async fn reset_user_settings_with(
+    repo: &dyn SettingsRepository,
+    registry: &SettingsRegistry,
+    events: &SettingsEvents,
+    user_id: Uuid,
+) -> Result<UserSettings, String> {
+    let stored = repo
+        .find_by_user(user_id)
+        .await
+        .map_err(|e| format!("Failed to reset settings: {}", e))?;
+    repo.delete_by_user(user_id)
+        .await
+        .map_err(|e| format!("Failed to reset settings: {}", e))?;
+
+    let defaults = with_defaults(unsaved(user_id, Utc::now()), registry);
+    if let Some(event) = stored.and_then(|stored| SettingsChanged::between(&with_defaults(stored, registry), &defaults)) {
+        events.publish(&event);
+    }
+
+    Ok(defaults)
+}
+
+/// Writes a user's settings, app settings and the logging configuration to a
+/// bundle at `path`, relative to the filesystem root.
+#[tauri::command]
+pub async fn export_settings(current_user: CurrentUser, user_id: String, path: String) -> Result<String, String> {
+    let uuid = current_user
+        .ensure_self_or(&user_id, Permission::UsersRead)
+        .map_err(|e| e.to_string())?;
+
+    let bundle = export_settings_with(&settings_repository()?, uuid, get_log_config().await?, Utc::now()).await?;
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| format!("Failed to export settings: {}", e))?;
+
+    write_text_file(path, json).await
+}
+
+pub(crate) async fn export_settings_with(
+    repo: &dyn SettingsRepository,
+    user_id: Uuid,
+    log_config: AppLogConfig,
+    now: DateTime<Utc>,
+) -> Result<SettingsBundle, String> {
+    let stored = repo
+        .find_by_user(user_id)
+        .await
+        .map_err(|e| format!("Failed to export settings: {}", e))?
+        .unwrap_or_else(|| unsaved(user_id, now));
+
+    SettingsBundle::new(&stored, log_config, now).map_err(|e| format!("Failed to export settings: {}", e))
+}
+
+/// Applies a bundle at `path`, relative to the filesystem root, to a user.
+///
+/// The whole bundle is validated first. The logging configuration is global,
+/// so it is only applied for callers with `system:admin` and reported as
+/// skipped for everyone else. For admins either the settings and the logging
+/// configuration are both saved or neither is, and the new log level applies
+/// to the running logger straight away.
+#[tauri::command]
+pub async fn import_settings(current_user: CurrentUser, user_id: String, path: String) -> Result<ImportedSettings, String> {
+    let uuid = current_user
+        .ensure_self_or(&user_id, Permission::UsersWrite)
+        .map_err(|e| e.to_string())?;
+
+    let log_config_path = current_user
+        .has_permission(Permission::SystemAdmin)
+        .then(crate::logging::default_log_config_path);
+
+    let json = read_text_file(path).await?;
+    import_settings_with(
+        &settings_repository()?,
+        settings_registry(),
+        settings_events(),
+        uuid,
+        &json,
+        log_config_path.as_deref(),
+    )
+    .await
+}
+
+/// Imports the bundle `json` for `user_id`. Its logging configuration is
+/// written to `log_config_path` and applied, or skipped when that is `None`.
+pub(crate) async fn import_settings_with(
+    repo: &dyn SettingsRepository,
+    registry: &SettingsRegistry,
+    events: &SettingsEvents,
+    user_id: Uuid,
+    json: &str,
+    log_config_path: Option<&Path>,
+) -> Result<ImportedSettings, String> {
+    let bundle = SettingsBundle::parse(json, registry).map_err(|e| e.to_string())?;
+
+    let previous = repo
+        .find_by_user(user_id)
+        .await
+        .map_err(|e| format!("Failed to import settings: {}", e))?;
+    let mut settings = previous.clone().unwrap_or_else(|| {
+        let mut settings = unsaved(user_id, Utc::now());
+        settings.id = Uuid::new_v4();
+        settings
+    });
+    let before = with_defaults(settings.clone(), registry);
+    settings.settings_data = bundle.settings_data();
+    settings.theme = bundle.user_settings.theme;
+    settings.language = bundle.user_settings.language;
+    settings.notifications_enabled = bundle.user_settings.notifications_enabled;
+
+    // Stage the log configuration next to the real file, save the settings,
+    // then move the configuration into place, undoing the save if that fails.
+    let staged = log_config_path.map(|path| (path, path.with_extension("json.import")));
+    if let Some((path, staged)) = &staged {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        save_config_to_file(&bundle.log_config, staged)
+            .map_err(|e| format!("Failed to import log configuration: {}", e))?;
+    }
+
+    let saved = match repo.save(settings).await {
+        Ok(saved) => saved,
+        Err(e) => {
+            if let Some((_, staged)) = &staged {
+                let _ = fs::remove_file(staged);
+            }
+            return Err(format!("Failed to import settings: {}", e));
+        }
+    };
+
+    let moved = match &staged {
+        Some((path, staged)) => fs::rename(staged, path).map_err(|e| {
+            let _ = fs::remove_file(staged);
+            e
+        }),
+        None => Ok(()),
+    };
+    if let Err(e) = moved {
+        let restored = match previous {
+            Some(previous) => repo.save(previous).await.map(|_| ()),
+            None => repo.delete_by_user(user_id).await.map(|_| ()),
+        };
+        if let Err(restore_error) = restored {
+            tracing::error!("Failed to restore settings of user {} after a failed import: {}", user_id, restore_error);
+        }
+        return Err(format!("Failed to import log configuration: {}", e));
+    }
+
+    let mut skipped = Vec::new();
+    if staged.is_some() {
+        // The configuration is saved by now, so a failure here only delays
+        // the new level until the next launch.
+        if let Err(e) = apply_log_config(&bundle.log_config) {
+            tracing::warn!("Failed to apply imported log configuration: {}", e);
+        }
+    } else {
+        skipped.push(LOG_CONFIG_PART.to_string());
+    }
+
+    let saved = with_defaults(saved, registry);
+    if let Some(event) = SettingsChanged::between(&before, &saved) {
+        events.publish(&event);
+    }
+
+    Ok(ImportedSettings { settings: saved, skipped })
+}
+
+/// Returns the registered settings namespaces and their schemas.
+#[tauri::command]
+pub async fn get_settings_schemas() -> Result<BTreeMap<String, SettingsSchema>, String> {
+    Ok(settings_registry().schemas())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_utils::{pool, reset_all_tables};
+    use crate::handlers::users::create_user;
+    use crate::models::CreateUser;
+    use crate::permissions::{default_permissions, ROLE_USER};
+    use crate::repositories::memory::InMemorySettingsRepository;
+    use crate::session::{SessionStore, SessionUser};
+    use anyhow::Result as AnyResult;
+    use serde_json::json;
+    use serial_test::serial;
+    use std::sync::{Arc, Mutex};
+
+    fn signed_in_as(user_id: Uuid) -> CurrentUser {
+        CurrentUser::for_tests(
+            SessionUser {
+                user_id,
+                email: "settings@example.com".to_string(),
+                username: "settings_user".to_string(),
+                role: ROLE_USER.to_string(),
+                permissions: default_permissions(ROLE_USER)
+                    .iter()
+                    .map(|permission| permission.as_str().to_string())
+                    .collect(),
+                signed_in_at: Utc::now(),
+            },
+            Arc::new(SessionStore::new()),
+        )
+    }
+
+    #[tokio::test]
+    async fn settings_are_patched_over_the_defaults() {
+        let repo = InMemorySettingsRepository::new();
+        let registry = SettingsRegistry::new();
+        let events = SettingsEvents::new();
+        let user_id = Uuid::new_v4();
+
+        let defaults = get_user_settings_with(&repo, &registry, user_id).await.unwrap();
+        assert!(defaults.id.is_nil());
+        assert_eq!(defaults.theme, "light");
+        assert_eq!(defaults.settings_data["autoSave"], json!(true));
+
+        let saved = upsert_user_settings_with(
+            &repo,
+            &registry,
+            &events,
+            user_id,
+            UpdateUserSettings {
+                theme: Some("Dark".to_string()),
+                settings_data: Some(json!({ "autoSave": false, "editor": { "tabSize": 4, "wrap": true } })),
+                ..UpdateUserSettings::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(!saved.id.is_nil());
+        assert_eq!(saved.theme, "dark");
+
+        let patched = upsert_user_settings_with(
+            &repo,
+            &registry,
+            &events,
+            user_id,
+            UpdateUserSettings {
+                language: Some("pt-BR".to_string()),
+                settings_data: Some(json!({ "autoSave": null, "editor": { "wrap": false } })),
+                ..UpdateUserSettings::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(patched.id, saved.id);
+        assert_eq!((patched.theme.as_str(), patched.language.as_str()), ("dark", "pt-BR"));
+        assert_eq!(
+            patched.settings_data,
+            json!({
+                "sidebarCollapsed": false,
+                "autoSave": true,
+                "notifications": true,
+                "editor": { "tabSize": 4, "wrap": false }
+            })
+        );
+        // Only what the user set is stored.
+        let stored = repo.find_by_user(user_id).await.unwrap().unwrap();
+        assert_eq!(stored.settings_data, json!({ "editor": { "tabSize": 4, "wrap": false } }));
+
+        let reset = reset_user_settings_with(&repo, &registry, &events, user_id).await.unwrap();
+        assert_eq!(reset.theme, "light");
+        assert!(repo.find_by_user(user_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn changes_are_published_with_their_keys() {
+        let repo = InMemorySettingsRepository::new();
+        let registry = SettingsRegistry::new();
+        let events = SettingsEvents::new();
+        let published = Arc::new(Mutex::new(Vec::new()));
+        let recorded = published.clone();
+        events.subscribe(move |event: &SettingsChanged| recorded.lock().unwrap().push(event.clone()));
+        let user_id = Uuid::new_v4();
+
+        let dark = || UpdateUserSettings {
+            theme: Some("dark".to_string()),
+            notifications_enabled: Some(false),
+            settings_data: Some(json!({ "autoSave": true })),
+            ..UpdateUserSettings::default()
+        };
+        let saved = upsert_user_settings_with(&repo, &registry, &events, user_id, dark()).await.unwrap();
+        // Saving the same values again is not a change.
+        upsert_user_settings_with(&repo, &registry, &events, user_id, dark()).await.unwrap();
+        reset_user_settings_with(&repo, &registry, &events, user_id).await.unwrap();
+        reset_user_settings_with(&repo, &registry, &events, user_id).await.unwrap();
+
+        let published = published.lock().unwrap();
+        let changed: Vec<Vec<String>> = published.iter().map(|event| event.changed.clone()).collect();
+        assert_eq!(
+            changed,
+            vec![vec!["theme", "notificationsEnabled"], vec!["theme", "notificationsEnabled"]]
+        );
+        assert_eq!(published[0].user_id, user_id);
+        assert_eq!(published[0].settings, saved);
+        assert_eq!(published[1].settings.theme, "light");
+    }
+
+    #[tokio::test]
+    async fn invalid_settings_are_rejected() {
+        let repo = InMemorySettingsRepository::new();
+        let registry = SettingsRegistry::new();
+        let events = SettingsEvents::new();
+        let user_id = Uuid::new_v4();
+
+        for (changes, expected) in [
+            (
+                UpdateUserSettings {
+                    theme: Some("neon".to_string()),
+                    ..UpdateUserSettings::default()
+                },
+                "Invalid theme: Theme must be one of: light, dark, system",
+            ),
+            (
+                UpdateUserSettings {
+                    language: Some("klingon".to_string()),
+                    ..UpdateUserSettings::default()
+                },
+                "Invalid language: Language must be a tag such as 'en' or 'pt-BR'",
+            ),
+            (
+                UpdateUserSettings {
+                    settings_data: Some(json!(["not", "an", "object"])),
+                    ..UpdateUserSettings::default()
+                },
+                "Invalid settings data: must be a JSON object",
+            ),
+            (
+                UpdateUserSettings {
+                    settings_data: Some(json!({ "autoSave": "yes" })),
+                    ..UpdateUserSettings::default()
+                },
+                "Invalid settings data: autoSave must be a boolean",
+            ),
+        ] {
+            let response = upsert_user_settings_with(&repo, &registry, &events, user_id, changes).await;
+            assert_eq!(response.unwrap_err(), expected);
+        }
+        assert!(repo.find_by_user(user_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn exported_settings_import_on_another_account() {
+        let repo = InMemorySettingsRepository::new();
+        let registry = SettingsRegistry::new();
+        let events = SettingsEvents::new();
+        let temp = tempfile::TempDir::new().unwrap();
+        let log_config_path = temp.path().join("config").join("logging.json");
+        let (source, target) = (Uuid::new_v4(), Uuid::new_v4());
+
+        upsert_user_settings_with(
+            &repo,
+            &registry,
+            &events,
+            source,
+            UpdateUserSettings {
+                theme: Some("system".to_string()),
+                notifications_enabled: Some(false),
+                settings_data: Some(json!({ "sidebarCollapsed": true, "editor": { "tabSize": 2 } })),
+                ..UpdateUserSettings::default()
+            },
+        )
+        .await
+        .unwrap();
+        let mut log_config = AppLogConfig::default();
+        log_config.file.max_files = 7;
+
+        let bundle = export_settings_with(&repo, source, log_config, Utc::now()).await.unwrap();
+        assert_eq!(bundle.app_settings.sidebar_collapsed, Some(true));
+        let json = serde_json::to_string_pretty(&bundle).unwrap();
+
+        let imported = import_settings_with(&repo, &registry, &events, target, &json, Some(&log_config_path))
+            .await
+            .unwrap();
+        assert!(imported.skipped.is_empty());
+        let imported = imported.settings;
+        assert_eq!(imported.user_id, target);
+        assert_eq!((imported.theme.as_str(), imported.notifications_enabled), ("system", false));
+        let stored = repo.find_by_user(target).await.unwrap().unwrap();
+        assert_eq!(stored.settings_data, json!({ "sidebarCollapsed": true, "editor": { "tabSize": 2 } }));
+
+        let written = crate::logging::config::load_config_from_file(&log_config_path).unwrap();
+        assert_eq!(written.file.max_files, 7);
+        assert!(!log_config_path.with_extension("json.import").exists());
+    }
+
+    #[tokio::test]
+    async fn imports_without_a_log_config_path_skip_it() {
+        let repo = InMemorySettingsRepository::new();
+        let registry = SettingsRegistry::new();
+        let events = SettingsEvents::new();
+        let mut bundle = export_settings_with(&repo, Uuid::new_v4(), AppLogConfig::default(), Utc::now())
+            .await
+            .unwrap();
+        bundle.user_settings.theme = "dark".to_string();
+        let json = serde_json::to_string(&bundle).unwrap();
+        let user_id = Uuid::new_v4();
+
+        let imported = import_settings_with(&repo, &registry, &events, user_id, &json, None)
+            .await
+            .unwrap();
+        assert_eq!(imported.settings.theme, "dark");
+        assert_eq!(imported.skipped, vec![LOG_CONFIG_PART.to_string()]);
+        assert_eq!(repo.find_by_user(user_id).await.unwrap().unwrap().theme, "dark");
+    }
+
+    #[tokio::test]
+    async fn failed_imports_change_nothing() {
+        let repo = InMemorySettingsRepository::new();
+        let registry = SettingsRegistry::new();
+        let events = SettingsEvents::new();
+        let temp = tempfile::TempDir::new().unwrap();
+        let user_id = Uuid::new_v4();
+        let bundle = export_settings_with(&repo, Uuid::new_v4(), AppLogConfig::default(), Utc::now())
+            .await
+            .unwrap();
+        let json = serde_json::to_string(&bundle).unwrap();
+
+        let invalid = json.replace("\"theme\":\"light\"", "\"theme\":\"neon\"");
+        let log_config_path = temp.path().join("logging.json");
+        let error = import_settings_with(&repo, &registry, &events, user_id, &invalid, Some(&log_config_path))
+            .await
+            .unwrap_err();
+        assert!(error.starts_with("Invalid theme"));
+        assert!(!log_config_path.exists());
+
+        // The log configuration cannot be moved onto a directory, so the
+        // settings saved before that step are rolled back.
+        let blocked_path = temp.path().join("blocked");
+        fs::create_dir(&blocked_path).unwrap();
+        let error = import_settings_with(&repo, &registry, &events, user_id, &json, Some(&blocked_path))
+            .await
+            .unwrap_err();
+        assert!(error.starts_with("Failed to import log configuration"));
+        assert!(repo.find_by_user(user_id).await.unwrap().is_none());
+        assert!(!blocked_path.with_extension("json.import").exists());
+    }
+
+    #[tokio::test]
+    async fn registered_namespaces_are_validated_and_defaulted() {
+        let repo = InMemorySettingsRepository::new();
+        let registry = SettingsRegistry::new();
+        registry
+            .register_json(
+                "sync",
+                r#"{
+                    "enabled": { "field_type": "boolean", "default": false },
+                    "interval": { "field_type": "integer", "default": 15, "min": 5 }
+                }"#,
+            )
+            .unwrap();
+        let events = SettingsEvents::new();
+        let user_id = Uuid::new_v4();
+
+        let defaults = get_user_settings_with(&repo, &registry, user_id).await.unwrap();
+        assert_eq!(defaults.settings_data["sync"], json!({ "enabled": false, "interval": 15 }));
+
+        let rejected = upsert_user_settings_with(
+            &repo,
+            &registry,
+            &events,
+            user_id,
+            UpdateUserSettings {
+                settings_data: Some(json!({ "sync": { "enabled": "yes", "interval": 1, "peer": "x" } })),
+                ..UpdateUserSettings::default()
+            },
+        )
+        .await;
+        assert_eq!(
+            rejected.unwrap_err(),
+            "Invalid settings data: sync.peer is not a known setting; sync.enabled must be a boolean; \
+             sync.interval must be at least 5"
+        );
+        assert!(repo.find_by_user(user_id).await.unwrap().is_none());
+
+        let saved = upsert_user_settings_with(
+            &repo,
+            &registry,
+            &events,
+            user_id,
+            UpdateUserSettings {
+                settings_data: Some(json!({ "sync": { "enabled": true } })),
+                ..UpdateUserSettings::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(saved.settings_data["sync"], json!({ "enabled": true, "interval": 15 }));
+        let stored = repo.find_by_user(user_id).await.unwrap().unwrap();
+        assert_eq!(stored.settings_data, json!({ "sync": { "enabled": true } }));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn settings_commands_store_one_row_per_user() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        let user = create_user(CreateUser {
+            email: "settings@example.com".to_string(),
+            username: "settings_user".to_string(),
+            password: "Sup3r$ecret".to_string(),
+            first_name: None,
+            last_name: None,
+        })
+        .await
+        .expect("user creation should succeed");
+        let user_id = user.id.to_string();
+
+        let other = get_user_settings(signed_in_as(Uuid::new_v4()), user_id.clone()).await;
+        assert!(matches!(other, Err(message) if message.starts_with("[FORBIDDEN]")));
+
+        for tab_size in [2, 4] {
+            upsert_user_settings(
+                signed_in_as(user.id),
+                user_id.clone(),
+                UpdateUserSettings {
+                    settings_data: Some(json!({ "editor": { "tabSize": tab_size } })),
+                    ..UpdateUserSettings::default()
+                },
+            )
+            .await
+            .expect("saving settings should succeed");
+        }
+
+        let settings = get_user_settings(signed_in_as(user.id), user_id.clone())
+            .await
+            .expect("fetching settings should succeed");
+        assert_eq!(settings.settings_data["editor"], json!({ "tabSize": 4 }));
+        assert_eq!(settings.settings_data["notifications"], json!(true));
+
+        let reset = reset_user_settings(signed_in_as(user.id), user_id).await.unwrap();
+        assert!(reset.id.is_nil());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn concurrent_patches_to_different_keys_all_apply() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        let user = create_user(CreateUser {
+            email: "concurrent@example.com".to_string(),
+            username: "concurrent_user".to_string(),
+            password: "Sup3r$ecret".to_string(),
+            first_name: None,
+            last_name: None,
+        })
+        .await
+        .expect("user creation should succeed");
+        let repo = settings_repository().map_err(anyhow::Error::msg)?;
+        let (registry, events) = (SettingsRegistry::new(), SettingsEvents::new());
+
+        let patch = |data: serde_json::Value| {
+            upsert_user_settings_with(
+                &repo,
+                &registry,
+                &events,
+                user.id,
+                UpdateUserSettings {
+                    settings_data: Some(data),
+                    ..UpdateUserSettings::default()
+                },
+            )
+        };
+        let (first, second, third) = tokio::join!(
+            patch(json!({ "autoSave": false })),
+            patch(json!({ "sidebarCollapsed": true })),
+            patch(json!({ "editor": { "tabSize": 2 } })),
+        );
+        assert!(first.is_ok() && second.is_ok() && third.is_ok());
+
+        let stored = repo.find_by_user(user.id).await?.unwrap();
+        assert_eq!(
+            stored.settings_data,
+            json!({ "autoSave": false, "sidebarCollapsed": true, "editor": { "tabSize": 2 } })
+        );
+
+        Ok(())
+    }
+}
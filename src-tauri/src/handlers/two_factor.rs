@@ -0,0 +1,460 @@
+//! Two-factor (TOTP) enrollment command handlers.
+//!
+//! Enrollment is two steps: `begin_totp_enrollment` keeps a new secret as
+//! pending and returns its provisioning URI, and `confirm_totp_enrollment`
+//! activates it once the user proves their authenticator produces valid
+//! codes. Confirming returns the recovery codes; they are shown only once.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::handlers::logs::log_security_event;
+use crate::models::User;
+use crate::password::verify_password;
+use crate::repositories::{LogRepository, SqlLogRepository, SqlUserRepository, UserRepository};
+use crate::session::CurrentUser;
+use crate::totp::{
+    self, base32_encode, generate_recovery_codes, generate_secret, hash_recovery_code,
+    pending_secret_key, provisioning_uri, secret_key, TotpSecretStore, VaultTotpSecrets,
+};
+
+/// Error returned when a TOTP or recovery code is wrong or already used.
+pub const INVALID_TWO_FACTOR_CODE: &str = "Invalid two-factor code";
+
+/// A pending secret for the user to add to their authenticator app.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpEnrollment {
+    /// The secret in base32, for entering by hand.
+    pub secret: String,
+    /// `otpauth://` URI, usually rendered as a QR code.
+    pub provisioning_uri: String,
+}
+
+fn repositories() -> Result<(SqlUserRepository, SqlLogRepository), String> {
+    Ok((
+        SqlUserRepository::from_global().map_err(|e| e.to_string())?,
+        SqlLogRepository::from_global().map_err(|e| e.to_string())?,
+    ))
+}
+
+async fn find_user(users: &dyn UserRepository, user_id: Uuid) -> Result<User, String> {
+    users
+        .find_by_id(user_id)
+        .await
+        .map_err(|e| format!("Failed to fetch user: {}", e))?
+        .ok_or_else(|| "User not found".to_string())
+}
+
+/// Checks a TOTP or recovery code for `user`, spending it if it is valid.
+///
+/// TOTP codes are accepted once per time step; recovery codes once ever.
+pub(crate) async fn verify_second_factor(
+    users: &dyn UserRepository,
+    secrets: &dyn TotpSecretStore,
+    user: &User,
+    code: &str,
+    now: DateTime<Utc>,
+) -> Result<bool, String> {
+    if !totp::looks_like_totp_code(code) {
+        return users
+            .consume_recovery_code(user.id, &hash_recovery_code(code), now)
+            .await
+            .map_err(|e| format!("Failed to verify recovery code: {}", e));
+    }
+
+    let secret = secrets
+        .load(&secret_key(user.id))
+        .await
+        .map_err(|e| format!("Failed to load two-factor secret: {}", e))?
+        .ok_or_else(|| "Two-factor secret is missing".to_string())?;
+
+    let Some(step) = totp::verify_code(&secret, code, now) else {
+        return Ok(false);
+    };
+
+    users
+        .record_totp_step(user.id, step)
+        .await
+        .map_err(|e| format!("Failed to verify two-factor code: {}", e))
+}
+
+/// Starts two-factor enrollment for the signed-in user.
+#[tauri::command]
+pub async fn begin_totp_enrollment(current_user: CurrentUser) -> Result<TotpEnrollment, String> {
+    let (users, _) = repositories()?;
+    begin_totp_enrollment_with(&users, &VaultTotpSecrets, current_user.user_id).await
+}
+
+pub(crate) async fn begin_totp_enrollment_with(
+    users: &dyn UserRepository,
+    secrets: &dyn TotpSecretStore,
+    user_id: Uuid,
+) -> Result<TotpEnrollment, String> {
+    let user = find_user(users, user_id).await?;
+    if user.totp_enabled {
+        return Err("Two-factor authentication is already enabled".to_string());
+    }
+
+    let secret = generate_secret();
+    secrets
+        .store(&pending_secret_key(user.id), secret.clone())
+        .await
+        .map_err(|e| format!("Failed to store two-factor secret: {}", e))?;
+
+    Ok(TotpEnrollment {
+        secret: base32_encode(&secret),
+        provisioning_uri: provisioning_uri(&secret, &user.email),
+    })
+}
+
+/// Activates the pending secret once `code` proves the authenticator works,
+/// returning the new recovery codes.
+#[tauri::command]
+pub async fn confirm_totp_enrollment(current_user: CurrentUser, code: String) -> Result<Vec<String>, String> {
+    let (users, logs) = repositories()?;
+    confirm_totp_enrollment_with(&users, &VaultTotpSecrets, &logs, current_user.user_id, &code, Utc::now()).await
+}
+
+pub(crate) async fn confirm_totp_enrollment_with(
+    users: &dyn UserRepository,
+    secrets: &dyn TotpSecretStore,
+    logs: &dyn LogRepository,
+    user_id: Uuid,
+    code: &str,
+    now: DateTime<Utc>,
+) -> Result<Vec<String>, String> {
+    let user = find_user(users, user_id).await?;
+    if user.totp_enabled {
+        return Err("Two-factor authentication is already enabled".to_string());
+    }
+
+    let pending_key = pending_secret_key(user.id);
+    let secret = secrets
+        .load(&pending_key)
+        .await
+        .map_err(|e| format!("Failed to load two-factor secret: {}", e))?
+        .ok_or_else(|| "No two-factor enrollment in progress".to_string())?;
+
+    let step = totp::verify_code(&secret, code, now).ok_or_else(|| INVALID_TWO_FACTOR_CODE.to_string())?;
+
+    secrets
+        .store(&secret_key(user.id), secret)
+        .await
+        .map_err(|e| format!("Failed to store two-factor secret: {}", e))?;
+    secrets
+        .remove(&pending_key)
+        .await
+        .map_err(|e| format!("Failed to store two-factor secret: {}", e))?;
+
+    let recovery_codes = generate_recovery_codes();
+    let hashes: Vec<String> = recovery_codes.iter().map(|code| hash_recovery_code(code)).collect();
+    users
+        .enable_totp(user.id, &hashes)
+        .await
+        .map_err(|e| format!("Failed to enable two-factor authentication: {}", e))?;
+    users
+        .record_totp_step(user.id, step)
+        .await
+        .map_err(|e| format!("Failed to enable two-factor authentication: {}", e))?;
+
+    log_security_event(
+        logs,
+        "totp_enabled",
+        "Two-factor authentication enabled".to_string(),
+        Some(user.id),
+        json!({}),
+    )
+    .await;
+
+    Ok(recovery_codes)
+}
+
+/// Turns off two-factor logins after checking the password and a current
+/// TOTP or recovery code.
+#[tauri::command]
+pub async fn disable_totp(current_user: CurrentUser, password: String, code: String) -> Result<String, String> {
+    let (users, logs) = repositories()?;
+    disable_totp_with(&users, &VaultTotpSecrets, &logs, current_user.user_id, &password, &code, Utc::now()).await?;
+
+    Ok("Two-factor authentication disabled".to_string())
+}
+
+pub(crate) async fn disable_totp_with(
+    users: &dyn UserRepository,
+    secrets: &dyn TotpSecretStore,
+    logs: &dyn LogRepository,
+    user_id: Uuid,
+    password: &str,
+    code: &str,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let user = find_user(users, user_id).await?;
+    if !user.totp_enabled {
+        return Err("Two-factor authentication is not enabled".to_string());
+    }
+
+    let matches = verify_password(password, &user.password_hash)
+        .map_err(|e| format!("Failed to verify password: {}", e))?;
+    if !matches {
+        return Err("Current password is incorrect".to_string());
+    }
+
+    if !verify_second_factor(users, secrets, &user, code, now).await? {
+        return Err(INVALID_TWO_FACTOR_CODE.to_string());
+    }
+
+    users
+        .disable_totp(user.id)
+        .await
+        .map_err(|e| format!("Failed to disable two-factor authentication: {}", e))?;
+    secrets
+        .remove(&secret_key(user.id))
+        .await
+        .map_err(|e| format!("Failed to remove two-factor secret: {}", e))?;
+
+    log_security_event(
+        logs,
+        "totp_disabled",
+        "Two-factor authentication disabled".to_string(),
+        Some(user.id),
+        json!({}),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Replaces the recovery codes after checking a current TOTP or recovery code.
+#[tauri::command]
+pub async fn regenerate_recovery_codes(current_user: CurrentUser, code: String) -> Result<Vec<String>, String> {
+    let (users, logs) = repositories()?;
+    regenerate_recovery_codes_with(&users, &VaultTotpSecrets, &logs, current_user.user_id, &code, Utc::now()).await
+}
+
+pub(crate) async fn regenerate_recovery_codes_with(
+    users: &dyn UserRepository,
+    secrets: &dyn TotpSecretStore,
+    logs: &dyn LogRepository,
+    user_id: Uuid,
+    code: &str,
+    now: DateTime<Utc>,
+) -> Result<Vec<String>, String> {
+    let user = find_user(users, user_id).await?;
+    if !user.totp_enabled {
+        return Err("Two-factor authentication is not enabled".to_string());
+    }
+
+    if !verify_second_factor(users, secrets, &user, code, now).await? {
+        return Err(INVALID_TWO_FACTOR_CODE.to_string());
+    }
+
+    let recovery_codes = generate_recovery_codes();
+    let hashes: Vec<String> = recovery_codes.iter().map(|code| hash_recovery_code(code)).collect();
+    users
+        .replace_recovery_codes(user.id, &hashes)
+        .await
+        .map_err(|e| format!("Failed to replace recovery codes: {}", e))?;
+
+    log_security_event(
+        logs,
+        "recovery_codes_regenerated",
+        "Recovery codes regenerated".to_string(),
+        Some(user.id),
+        json!({}),
+    )
+    .await;
+
+    Ok(recovery_codes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::users::{authenticate_user_with, create_user_with, TWO_FACTOR_REQUIRED};
+    use crate::lockout::LoginThrottle;
+    use crate::models::{CreateUser, LoginRequest, PublicUser};
+    use crate::repositories::memory::{InMemoryLogRepository, InMemoryUserRepository};
+    use crate::totp::{code_at, time_step, InMemoryTotpSecrets};
+    use chrono::Duration;
+
+    const PASSWORD: &str = "Sup3r$ecret";
+
+    struct Fixture {
+        users: InMemoryUserRepository,
+        secrets: InMemoryTotpSecrets,
+        logs: InMemoryLogRepository,
+        throttle: LoginThrottle,
+        user_id: Uuid,
+    }
+
+    impl Fixture {
+        async fn new() -> Self {
+            let users = InMemoryUserRepository::new();
+            let user_id = create_user_with(
+                &users,
+                CreateUser {
+                    email: "totp@example.com".to_string(),
+                    username: "totp_user".to_string(),
+                    password: PASSWORD.to_string(),
+                    first_name: None,
+                    last_name: None,
+                },
+            )
+            .await
+            .expect("user creation should succeed")
+            .id;
+
+            Self {
+                users,
+                secrets: InMemoryTotpSecrets::default(),
+                logs: InMemoryLogRepository::new(),
+                throttle: LoginThrottle::default(),
+                user_id,
+            }
+        }
+
+        /// Enrolls the user, returning the secret and the recovery codes.
+        async fn enroll(&self, now: DateTime<Utc>) -> (Vec<u8>, Vec<String>) {
+            begin_totp_enrollment_with(&self.users, &self.secrets, self.user_id)
+                .await
+                .expect("enrollment should start");
+            let secret = self.secrets.load(&pending_secret_key(self.user_id)).await.unwrap().unwrap();
+            let codes = confirm_totp_enrollment_with(
+                &self.users,
+                &self.secrets,
+                &self.logs,
+                self.user_id,
+                &code_at(&secret, time_step(now)),
+                now,
+            )
+            .await
+            .expect("enrollment should be confirmed");
+            (secret, codes)
+        }
+
+        async fn login(&self, code: Option<&str>) -> Result<Option<PublicUser>, String> {
+            authenticate_user_with(
+                &self.users,
+                &self.logs,
+                &self.throttle,
+                &self.secrets,
+                false,
+                "test",
+                LoginRequest {
+                    email: "totp@example.com".to_string(),
+                    password: PASSWORD.to_string(),
+                    totp_code: code.map(str::to_string),
+                },
+            )
+            .await
+        }
+    }
+
+    #[tokio::test]
+    async fn enrollment_needs_a_valid_code_before_it_takes_effect() {
+        let fixture = Fixture::new().await;
+        let now = Utc::now();
+
+        let enrollment = begin_totp_enrollment_with(&fixture.users, &fixture.secrets, fixture.user_id)
+            .await
+            .unwrap();
+        assert!(enrollment.provisioning_uri.contains(&format!("secret={}", enrollment.secret)));
+
+        let wrong = confirm_totp_enrollment_with(
+            &fixture.users,
+            &fixture.secrets,
+            &fixture.logs,
+            fixture.user_id,
+            "000000",
+            now - Duration::hours(1),
+        )
+        .await;
+        assert_eq!(wrong.unwrap_err(), INVALID_TWO_FACTOR_CODE);
+        assert!(fixture.login(None).await.unwrap().is_some());
+
+        let (secret, codes) = fixture.enroll(now).await;
+        assert_eq!(codes.len(), totp::RECOVERY_CODE_COUNT);
+        assert_eq!(fixture.secrets.load(&secret_key(fixture.user_id)).await.unwrap(), Some(secret));
+        assert_eq!(fixture.secrets.load(&pending_secret_key(fixture.user_id)).await.unwrap(), None);
+
+        let again = begin_totp_enrollment_with(&fixture.users, &fixture.secrets, fixture.user_id).await;
+        assert!(again.is_err());
+    }
+
+    #[tokio::test]
+    async fn login_requires_a_fresh_code_once_enabled() {
+        let fixture = Fixture::new().await;
+        let enrolled_at = Utc::now() - Duration::minutes(5);
+        let (secret, _) = fixture.enroll(enrolled_at).await;
+
+        assert_eq!(fixture.login(None).await.unwrap_err(), TWO_FACTOR_REQUIRED);
+        assert_eq!(fixture.login(Some("not-a-code")).await.unwrap_err(), INVALID_TWO_FACTOR_CODE);
+
+        let code = code_at(&secret, time_step(Utc::now()));
+        let user = fixture.login(Some(&code)).await.unwrap().expect("code should be accepted");
+        assert!(user.totp_enabled);
+
+        // The same code cannot be replayed within its time step.
+        assert_eq!(fixture.login(Some(&code)).await.unwrap_err(), INVALID_TWO_FACTOR_CODE);
+    }
+
+    #[tokio::test]
+    async fn recovery_codes_work_once() {
+        let fixture = Fixture::new().await;
+        let (_, codes) = fixture.enroll(Utc::now()).await;
+
+        let code = codes[0].to_uppercase();
+        assert!(fixture.login(Some(&code)).await.unwrap().is_some());
+        assert_eq!(fixture.login(Some(&code)).await.unwrap_err(), INVALID_TWO_FACTOR_CODE);
+
+        let replaced = regenerate_recovery_codes_with(
+            &fixture.users,
+            &fixture.secrets,
+            &fixture.logs,
+            fixture.user_id,
+            &codes[1],
+            Utc::now(),
+        )
+        .await
+        .expect("regenerating should succeed");
+        assert_eq!(fixture.login(Some(&codes[2])).await.unwrap_err(), INVALID_TWO_FACTOR_CODE);
+        assert!(fixture.login(Some(&replaced[0])).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn disabling_requires_the_password_and_a_code() {
+        let fixture = Fixture::new().await;
+        let (_, codes) = fixture.enroll(Utc::now()).await;
+        let disable = |password: &'static str, code: String| {
+            let fixture = &fixture;
+            async move {
+                disable_totp_with(
+                    &fixture.users,
+                    &fixture.secrets,
+                    &fixture.logs,
+                    fixture.user_id,
+                    password,
+                    &code,
+                    Utc::now(),
+                )
+                .await
+            }
+        };
+
+        assert_eq!(
+            disable("wrong", codes[0].clone()).await.unwrap_err(),
+            "Current password is incorrect"
+        );
+        assert_eq!(
+            disable(PASSWORD, "abcde-abcde".to_string()).await.unwrap_err(),
+            INVALID_TWO_FACTOR_CODE
+        );
+
+        disable(PASSWORD, codes[0].clone()).await.expect("disabling should succeed");
+        assert_eq!(fixture.secrets.load(&secret_key(fixture.user_id)).await.unwrap(), None);
+        assert!(!fixture.login(None).await.unwrap().unwrap().totp_enabled);
+    }
+}
@@ -1,71 +1,171 @@
 //! User management command handlers.
+//!
+//! Each command resolves the SQL repository over the global pool and delegates
+//! to a `*_with` function that holds the validation and hashing logic, so the
+//! logic can be exercised against any `UserRepository`. Commands that change
+//! an account take a [`CurrentUser`] and act on the signed-in user, or on
+//! others when the user's role grants the matching permission.
+//!
+//! Deleting a user only marks the account deleted: it is hidden, cannot sign
+//! in and can be restored until the retention period from
+//! [`crate::retention`] has passed and it is purged.
 
-use crate::database::get_pool_ref;
-use crate::models::{CreateUser, LoginRequest, PublicUser, UpdateUser, User};
-use crate::validation::{validate_email, validate_username, validate_optional_name};
-use bcrypt::{hash, verify, DEFAULT_COST};
+use crate::email_verification::verification_required;
+use crate::handlers::email_verification::send_verification;
+use crate::handlers::logs::log_security_event;
+use crate::handlers::two_factor::{verify_second_factor, INVALID_TWO_FACTOR_CODE};
+use crate::lockout::{locked_message, login_throttle, LoginThrottle};
+use crate::models::{CreateUser, LoginRequest, PublicUser, UpdateUser, User, UserPage, UserQuery};
+use crate::password::{verify_dummy_password, verify_password, HashAlgorithm};
+use crate::permissions::{is_known_role, Permission, ROLE_ADMIN, ROLE_USER};
+use crate::repositories::{
+    LogRepository, NewUser, SqlLogRepository, SqlUserRepository, UserChanges, UserFilter, UserRepository,
+};
+use crate::retention::purge_cutoff;
+use crate::session::{CurrentUser, Session, SessionUser};
+use crate::totp::{TotpSecretStore, VaultTotpSecrets};
+use crate::validation::{
+    validate_email, validate_optional_name, validate_password, validate_username, PasswordPolicy,
+};
+use chrono::{DateTime, Utc};
+use serde_json::json;
 use uuid::Uuid;
 
+/// Error returned when a correct password needs a two-factor code as well.
+pub const TWO_FACTOR_REQUIRED: &str = "Two-factor code required";
+
+/// Error returned when correct credentials belong to an unverified email address.
+pub const EMAIL_NOT_VERIFIED: &str = "Email address has not been verified";
+
+fn user_repository() -> Result<SqlUserRepository, String> {
+    SqlUserRepository::from_global().map_err(|e| e.to_string())
+}
+
 /// Retrieves all users from the database (excluding password hashes).
+///
+/// Soft-deleted users are only included with `include_deleted`. Prefer
+/// [`list_users`] for large tables.
 #[tauri::command]
-pub async fn get_all_users() -> Result<Vec<PublicUser>, String> {
-    let pool = get_pool_ref().map_err(|e| e.to_string())?;
-
-    let users: Vec<User> = sqlx::query_as::<_, User>(
-        r#"
-        SELECT id,
-               email,
-               username,
-               password_hash,
-               first_name,
-               last_name,
-               is_active,
-               created_at,
-               updated_at
-        FROM users
-        ORDER BY created_at DESC
-        "#,
-    )
-    .fetch_all(pool.as_ref())
-    .await
-    .map_err(|e| format!("Failed to fetch users: {}", e))?;
+pub async fn get_all_users(include_deleted: Option<bool>) -> Result<Vec<PublicUser>, String> {
+    get_all_users_with(&user_repository()?, include_deleted.unwrap_or(false)).await
+}
+
+pub(crate) async fn get_all_users_with(
+    repo: &dyn UserRepository,
+    include_deleted: bool,
+) -> Result<Vec<PublicUser>, String> {
+    let users = repo
+        .list(include_deleted)
+        .await
+        .map_err(|e| format!("Failed to fetch users: {}", e))?;
 
     Ok(users.into_iter().map(PublicUser::from).collect())
 }
 
-/// Retrieves a specific user by their UUID.
+/// Lists users a page at a time, with optional search, filters and sorting.
+///
+/// Results default to 50 per page, newest first, without soft-deleted users.
 #[tauri::command]
-pub async fn get_user_by_id(user_id: String) -> Result<Option<PublicUser>, String> {
-    let pool = get_pool_ref().map_err(|e| e.to_string())?;
-    let uuid = Uuid::parse_str(&user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
-
-    let user = sqlx::query_as::<_, User>(
-        r#"
-        SELECT id,
-               email,
-               username,
-               password_hash,
-               first_name,
-               last_name,
-               is_active,
-               created_at,
-               updated_at
-        FROM users
-        WHERE id = $1
-        "#,
-    )
-    .bind(uuid)
-    .fetch_optional(pool.as_ref())
-    .await
-    .map_err(|e| format!("Failed to fetch user: {}", e))?;
+pub async fn list_users(query: UserQuery) -> Result<UserPage, String> {
+    list_users_with(&user_repository()?, query).await
+}
+
+pub(crate) async fn list_users_with(repo: &dyn UserRepository, query: UserQuery) -> Result<UserPage, String> {
+    let UserQuery {
+        search,
+        is_active,
+        created_after,
+        created_before,
+        include_deleted,
+        sort_by,
+        sort_order,
+        limit,
+        offset,
+    } = query;
+
+    if let (Some(after), Some(before)) = (created_after, created_before) {
+        if after > before {
+            return Err("Invalid date range: createdAfter is later than createdBefore".to_string());
+        }
+    }
+
+    let filter = UserFilter {
+        search: search
+            .map(|search| search.trim().to_lowercase())
+            .filter(|search| !search.is_empty()),
+        is_active,
+        created_after,
+        created_before,
+        include_deleted: include_deleted.unwrap_or(false),
+        sort_by: sort_by.unwrap_or_default(),
+        sort_order: sort_order.unwrap_or_default(),
+        limit: limit.unwrap_or(50).clamp(1, 500),
+        offset: offset.unwrap_or(0).max(0),
+    };
+
+    let users = repo
+        .query(&filter)
+        .await
+        .map_err(|e| format!("Failed to fetch users: {}", e))?;
+    let total = repo
+        .count_matching(&filter)
+        .await
+        .map_err(|e| format!("Failed to fetch users: {}", e))?;
+
+    Ok(UserPage {
+        users: users.into_iter().map(PublicUser::from).collect(),
+        total,
+        limit: filter.limit,
+        offset: filter.offset,
+    })
+}
+
+/// Retrieves a specific user by their UUID. Soft-deleted users are not returned.
+///
+/// Reading another user's account requires `users:read`.
+#[tauri::command]
+pub async fn get_user_by_id(current_user: CurrentUser, user_id: String) -> Result<Option<PublicUser>, String> {
+    current_user
+        .ensure_self_or(&user_id, Permission::UsersRead)
+        .map_err(|e| e.to_string())?;
+    get_user_by_id_with(&user_repository()?, &user_id).await
+}
+
+pub(crate) async fn get_user_by_id_with(
+    repo: &dyn UserRepository,
+    user_id: &str,
+) -> Result<Option<PublicUser>, String> {
+    let uuid = Uuid::parse_str(user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let user = repo
+        .find_by_id(uuid)
+        .await
+        .map_err(|e| format!("Failed to fetch user: {}", e))?
+        .filter(|user| user.deleted_at.is_none());
 
     Ok(user.map(PublicUser::from))
 }
 
 /// Creates a new user account with validation and password hashing.
+///
+/// Passwords must satisfy the `AUTH_PASSWORD_*` policy and are hashed with the
+/// algorithm configured by `AUTH_HASH_ALGORITHM`.
+///
+/// The first account becomes an admin so a fresh install can be managed;
+/// later accounts get the `user` role. A verification token is emailed to the
+/// new address.
 #[tauri::command]
 pub async fn create_user(user_data: CreateUser) -> Result<PublicUser, String> {
-    let pool = get_pool_ref().map_err(|e| e.to_string())?;
+    let user = create_user_with(&user_repository()?, user_data).await?;
+    send_verification(user.id, &user.email).await;
+
+    Ok(user)
+}
+
+pub(crate) async fn create_user_with(
+    repo: &dyn UserRepository,
+    user_data: CreateUser,
+) -> Result<PublicUser, String> {
     let CreateUser {
         email,
         username,
@@ -78,47 +178,64 @@ pub async fn create_user(user_data: CreateUser) -> Result<PublicUser, String> {
     let username = validate_username(&username).map_err(|e| format!("Invalid username: {}", e))?;
     let first_name = validate_optional_name(first_name.as_deref()).map_err(|e| format!("Invalid first name: {}", e))?;
     let last_name = validate_optional_name(last_name.as_deref()).map_err(|e| format!("Invalid last name: {}", e))?;
+    validate_password(&password, &PasswordPolicy::from_env(), &username, &email)
+        .map_err(|e| format!("Invalid password: {}", e))?;
 
-    let password_hash = hash(password.as_str(), DEFAULT_COST)
+    let password_hash = HashAlgorithm::from_env()
+        .hash(&password)
         .map_err(|e| format!("Failed to hash password: {}", e))?;
 
-    let user = sqlx::query_as::<_, User>(
-        r#"
-        INSERT INTO users (email, username, password_hash, first_name, last_name)
-        VALUES ($1, $2, $3, $4, $5)
-        RETURNING id,
-                  email,
-                  username,
-                  password_hash,
-                  first_name,
-                  last_name,
-                  is_active,
-                  created_at,
-                  updated_at
-        "#,
-    )
-    .bind(email)
-    .bind(username)
-    .bind(password_hash)
-    .bind(first_name)
-    .bind(last_name)
-    .fetch_one(pool.as_ref())
-    .await
-    .map_err(|e| format!("Failed to create user: {}", e))?;
+    // The first account administers the application.
+    let user = repo
+        .create(
+            NewUser {
+                email,
+                username,
+                password_hash,
+                first_name,
+                last_name,
+                role: ROLE_USER.to_string(),
+            },
+            ROLE_ADMIN,
+        )
+        .await
+        .map_err(|e| format!("Failed to create user: {}", e))?;
 
     Ok(PublicUser::from(user))
 }
 
+/// Updates an account. A changed email address must be verified again, so a
+/// new verification token is emailed to it.
 #[tauri::command]
-pub async fn update_user(user_id: String, user_data: UpdateUser) -> Result<PublicUser, String> {
-    let pool = get_pool_ref().map_err(|e| e.to_string())?;
-    let uuid = Uuid::parse_str(&user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+pub async fn update_user(
+    current_user: CurrentUser,
+    user_id: String,
+    user_data: UpdateUser,
+) -> Result<PublicUser, String> {
+    current_user
+        .ensure_self_or(&user_id, Permission::UsersWrite)
+        .map_err(|e| e.to_string())?;
+
+    let email_changed = user_data.email.is_some();
+    let user = update_user_with(&user_repository()?, &user_id, user_data).await?;
+    if email_changed && !user.email_verified {
+        send_verification(user.id, &user.email).await;
+    }
+
+    Ok(user)
+}
+
+pub(crate) async fn update_user_with(
+    repo: &dyn UserRepository,
+    user_id: &str,
+    user_data: UpdateUser,
+) -> Result<PublicUser, String> {
+    let uuid = Uuid::parse_str(user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
     let UpdateUser {
         email,
         username,
         first_name,
         last_name,
-        is_active,
     } = user_data;
 
     // Validate and sanitize inputs
@@ -133,107 +250,424 @@ pub async fn update_user(user_id: String, user_data: UpdateUser) -> Result<Publi
     let first_name = validate_optional_name(first_name.as_deref()).map_err(|e| format!("Invalid first name: {}", e))?;
     let last_name = validate_optional_name(last_name.as_deref()).map_err(|e| format!("Invalid last name: {}", e))?;
 
-    let user = sqlx::query_as::<_, User>(
-        r#"
-        UPDATE users
-        SET email = COALESCE($2, email),
-            username = COALESCE($3, username),
-            first_name = COALESCE($4, first_name),
-            last_name = COALESCE($5, last_name),
-            is_active = COALESCE($6, is_active),
-            updated_at = CURRENT_TIMESTAMP
-        WHERE id = $1
-        RETURNING id,
-                  email,
-                  username,
-                  password_hash,
-                  first_name,
-                  last_name,
-                  is_active,
-                  created_at,
-                  updated_at
-        "#,
-    )
-    .bind(uuid)
-    .bind(email)
-    .bind(username)
-    .bind(first_name)
-    .bind(last_name)
-    .bind(is_active)
-    .fetch_one(pool.as_ref())
-    .await
-    .map_err(|e| format!("Failed to update user: {}", e))?;
+    let user = repo
+        .update(
+            uuid,
+            UserChanges {
+                email,
+                username,
+                first_name,
+                last_name,
+            },
+        )
+        .await
+        .map_err(|e| format!("Failed to update user: {}", e))?
+        .ok_or_else(|| "User not found".to_string())?;
 
     Ok(PublicUser::from(user))
 }
 
+/// Soft-deletes an account, revokes its tokens and signs it out of every
+/// window. It can be restored with [`restore_user`] until the retention period
+/// has passed.
+///
+/// Users may delete their own account; deleting others needs `users:delete`.
 #[tauri::command]
-pub async fn delete_user(user_id: String) -> Result<String, String> {
-    let pool = get_pool_ref().map_err(|e| e.to_string())?;
-    let uuid = Uuid::parse_str(&user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+pub async fn delete_user(current_user: CurrentUser, user_id: String) -> Result<String, String> {
+    let uuid = current_user
+        .ensure_self_or(&user_id, Permission::UsersDelete)
+        .map_err(|e| e.to_string())?;
+    let message = delete_user_with(&user_repository()?, &user_id).await?;
 
-    let result = sqlx::query("DELETE FROM users WHERE id = $1")
-        .bind(uuid)
-        .execute(pool.as_ref())
+    current_user.sessions().sign_out_user(uuid);
+    Ok(message)
+}
+
+pub(crate) async fn delete_user_with(repo: &dyn UserRepository, user_id: &str) -> Result<String, String> {
+    let uuid = Uuid::parse_str(user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let deleted = repo
+        .soft_delete(uuid, Utc::now())
         .await
         .map_err(|e| format!("Failed to delete user: {}", e))?;
 
-    if result.rows_affected() > 0 {
+    if deleted {
         Ok("User deleted successfully".to_string())
     } else {
         Err("User not found".to_string())
     }
 }
 
+/// Restores a soft-deleted account that has not been purged yet. Requires
+/// `users:delete`.
 #[tauri::command]
-pub async fn authenticate_user(login_data: LoginRequest) -> Result<Option<PublicUser>, String> {
-    let pool = get_pool_ref().map_err(|e| e.to_string())?;
-    let LoginRequest { email, password } = login_data;
+pub async fn restore_user(current_user: CurrentUser, user_id: String) -> Result<PublicUser, String> {
+    current_user
+        .authorize(Permission::UsersDelete)
+        .map_err(|e| e.to_string())?;
+    restore_user_with(&user_repository()?, &user_id).await
+}
 
-    // Validate email input
-    let email = validate_email(&email).map_err(|e| format!("Invalid email: {}", e))?;
+pub(crate) async fn restore_user_with(repo: &dyn UserRepository, user_id: &str) -> Result<PublicUser, String> {
+    let uuid = Uuid::parse_str(user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    let user = repo
+        .restore(uuid)
+        .await
+        .map_err(|e| format!("Failed to restore user: {}", e))?
+        .ok_or_else(|| "Deleted user not found".to_string())?;
+
+    Ok(PublicUser::from(user))
+}
+
+/// Permanently removes accounts deleted longer ago than the retention period.
+/// Requires `users:delete`.
+#[tauri::command]
+pub async fn purge_deleted_users(current_user: CurrentUser) -> Result<String, String> {
+    current_user
+        .authorize(Permission::UsersDelete)
+        .map_err(|e| e.to_string())?;
+    let purged = purge_deleted_users_with(&user_repository()?, Utc::now()).await?;
+
+    Ok(format!("Purged {} deleted user(s)", purged))
+}
+
+pub(crate) async fn purge_deleted_users_with(
+    repo: &dyn UserRepository,
+    now: DateTime<Utc>,
+) -> Result<u64, String> {
+    repo.purge_deleted(purge_cutoff(now))
+        .await
+        .map_err(|e| format!("Failed to purge deleted users: {}", e))
+}
+
+/// Purges expired deleted accounts in the background. Errors, including a
+/// database that is not connected yet, are only logged.
+pub(crate) async fn purge_expired_users() {
+    let repo = match user_repository() {
+        Ok(repo) => repo,
+        Err(e) => {
+            tracing::debug!("Skipping purge of deleted users: {}", e);
+            return;
+        }
+    };
+
+    match purge_deleted_users_with(&repo, Utc::now()).await {
+        Ok(0) => {}
+        Ok(purged) => tracing::info!("Purged {} deleted user(s)", purged),
+        Err(e) => tracing::warn!("{}", e),
+    }
+}
+
+/// Deactivates another user's account, revokes their tokens and signs them
+/// out. Inactive accounts cannot sign in until they are reactivated.
+#[tauri::command]
+pub async fn deactivate_user(current_user: CurrentUser, user_id: String) -> Result<PublicUser, String> {
+    let user = set_user_active_with(&user_repository()?, &current_user, &user_id, false).await?;
+
+    current_user.sessions().sign_out_user(user.id);
+    Ok(user)
+}
+
+/// Reactivates a deactivated account.
+#[tauri::command]
+pub async fn reactivate_user(current_user: CurrentUser, user_id: String) -> Result<PublicUser, String> {
+    set_user_active_with(&user_repository()?, &current_user, &user_id, true).await
+}
+
+pub(crate) async fn set_user_active_with(
+    repo: &dyn UserRepository,
+    current_user: &SessionUser,
+    user_id: &str,
+    active: bool,
+) -> Result<PublicUser, String> {
+    current_user
+        .authorize(Permission::UsersWrite)
+        .map_err(|e| e.to_string())?;
+    let uuid = Uuid::parse_str(user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    if uuid == current_user.user_id {
+        return Err("You cannot deactivate or reactivate your own account".to_string());
+    }
+
+    let user = repo
+        .set_active(uuid, active, Utc::now())
+        .await
+        .map_err(|e| format!("Failed to update user: {}", e))?
+        .ok_or_else(|| "User not found".to_string())?;
+
+    Ok(PublicUser::from(user))
+}
+
+/// Assigns a role to another user and signs them out so it takes effect.
+#[tauri::command]
+pub async fn set_user_role(
+    current_user: CurrentUser,
+    user_id: String,
+    role: String,
+) -> Result<PublicUser, String> {
+    let user = set_user_role_with(&user_repository()?, &current_user, &user_id, &role).await?;
+
+    current_user.sessions().sign_out_user(user.id);
+    Ok(user)
+}
+
+pub(crate) async fn set_user_role_with(
+    repo: &dyn UserRepository,
+    current_user: &SessionUser,
+    user_id: &str,
+    role: &str,
+) -> Result<PublicUser, String> {
+    current_user
+        .authorize(Permission::UsersWrite)
+        .map_err(|e| e.to_string())?;
+    let uuid = Uuid::parse_str(user_id).map_err(|e| format!("Invalid UUID: {}", e))?;
+
+    if uuid == current_user.user_id {
+        return Err("You cannot change your own role".to_string());
+    }
+    if !is_known_role(role) {
+        return Err(format!("Unknown role '{}'", role));
+    }
+
+    let user = repo
+        .set_role(uuid, role)
+        .await
+        .map_err(|e| format!("Failed to update role: {}", e))?
+        .ok_or_else(|| "User not found".to_string())?;
 
-    let user = sqlx::query_as::<_, User>(
-        r#"
-        SELECT id,
-               email,
-               username,
-               password_hash,
-               first_name,
-               last_name,
-               is_active,
-               created_at,
-               updated_at
-        FROM users
-        WHERE email = $1
-          AND is_active = TRUE
-        LIMIT 1
-        "#,
+    Ok(PublicUser::from(user))
+}
+
+/// Checks credentials, counting failures against the account, the email and the calling window.
+#[tauri::command]
+pub async fn authenticate_user(
+    session: Session,
+    login_data: LoginRequest,
+) -> Result<Option<PublicUser>, String> {
+    let logs = SqlLogRepository::from_global().map_err(|e| e.to_string())?;
+    authenticate_user_with(
+        &user_repository()?,
+        &logs,
+        login_throttle(),
+        &VaultTotpSecrets,
+        verification_required(),
+        &session.label,
+        login_data,
     )
-    .bind(&email)
-    .fetch_optional(pool.as_ref())
     .await
-    .map_err(|e| format!("Failed to authenticate user: {}", e))?;
+}
+
+/// Verifies a login from the webview `source`.
+///
+/// Locked accounts, emails and sources are refused with an error before the
+/// password is checked. Wrong credentials return `Ok(None)` and count towards
+/// every lock; lockouts are recorded as security events. A correct password stored
+/// with another algorithm than the configured one is rehashed.
+///
+/// Accounts with two-factor enabled also need a TOTP or recovery code: without
+/// one the error is [`TWO_FACTOR_REQUIRED`] and nothing is counted, and a wrong
+/// one counts as a failed login like a wrong password. With
+/// `require_verified_email`, correct credentials for an unverified address are
+/// refused with [`EMAIL_NOT_VERIFIED`].
+pub(crate) async fn authenticate_user_with(
+    repo: &dyn UserRepository,
+    logs: &dyn LogRepository,
+    throttle: &LoginThrottle,
+    totp: &dyn TotpSecretStore,
+    require_verified_email: bool,
+    source: &str,
+    login_data: LoginRequest,
+) -> Result<Option<PublicUser>, String> {
+    let LoginRequest { email, password, totp_code } = login_data;
+
+    // Validate email input
+    let email = validate_email(&email).map_err(|e| format!("Invalid email: {}", e))?;
+    let now = Utc::now();
+
+    if let Some(until) = throttle.locked_until(&email, source, now) {
+        return Err(locked_message(until, now));
+    }
 
-    if let Some(user) = user {
-        match verify(password.as_str(), &user.password_hash) {
-            Ok(true) => Ok(Some(PublicUser::from(user))),
-            Ok(false) => Ok(None),
-            Err(e) => Err(format!("Failed to verify password: {}", e)),
+    let user = repo
+        .find_by_email(&email)
+        .await
+        .map_err(|e| format!("Failed to authenticate user: {}", e))?
+        .filter(|user| user.can_sign_in());
+
+    let Some(user) = user else {
+        // Hash anyway, so the response time does not reveal which emails are registered.
+        verify_dummy_password(&password);
+        record_throttled_failure(logs, throttle, &email, source, now).await;
+        return Ok(None);
+    };
+
+    if let Some(until) = user.locked_until.filter(|until| *until > now) {
+        return Err(locked_message(until, now));
+    }
+
+    let matches = verify_password(&password, &user.password_hash)
+        .map_err(|e| format!("Failed to verify password: {}", e))?;
+
+    if !matches {
+        record_failed_login(repo, logs, throttle, &email, source, &user, now).await?;
+        return Ok(None);
+    }
+
+    if user.totp_enabled {
+        let Some(code) = totp_code.filter(|code| !code.trim().is_empty()) else {
+            return Err(TWO_FACTOR_REQUIRED.to_string());
+        };
+        if !verify_second_factor(repo, totp, &user, &code, now).await? {
+            record_failed_login(repo, logs, throttle, &email, source, &user, now).await?;
+            return Err(INVALID_TWO_FACTOR_CODE.to_string());
         }
-    } else {
-        Ok(None)
+    }
+
+    if require_verified_email && !user.email_verified {
+        return Err(EMAIL_NOT_VERIFIED.to_string());
+    }
+
+    if user.failed_login_attempts > 0 || user.locked_until.is_some() {
+        repo.clear_failed_logins(user.id)
+            .await
+            .map_err(|e| format!("Failed to authenticate user: {}", e))?;
+    }
+    throttle.record_success(&email);
+    rehash_if_outdated(repo, &user, &password).await;
+
+    Ok(Some(PublicUser::from(user)))
+}
+
+/// Counts a failed login from `source` against `email` and `user`, locking
+/// the account once the policy says so.
+async fn record_failed_login(
+    repo: &dyn UserRepository,
+    logs: &dyn LogRepository,
+    throttle: &LoginThrottle,
+    email: &str,
+    source: &str,
+    user: &User,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    record_throttled_failure(logs, throttle, email, source, now).await;
+
+    let failures = repo
+        .record_failed_login(user.id)
+        .await
+        .map_err(|e| format!("Failed to authenticate user: {}", e))?;
+
+    if let Some(duration) = throttle.policy().lock_duration(failures.max(0) as u32) {
+        let until = now + duration;
+        repo.lock_account(user.id, until)
+            .await
+            .map_err(|e| format!("Failed to lock account: {}", e))?;
+
+        log_security_event(
+            logs,
+            "account_locked",
+            format!("Account locked after {} failed login attempts", failures),
+            Some(user.id),
+            json!({ "source": source, "failedAttempts": failures, "lockedUntil": until }),
+        )
+        .await;
+    }
+
+    Ok(())
+}
+
+/// Replaces a hash made with another algorithm than the configured one.
+///
+/// Failures are only logged: the login has already succeeded, and the next
+/// one will try again.
+async fn rehash_if_outdated(repo: &dyn UserRepository, user: &User, password: &str) {
+    let algorithm = HashAlgorithm::from_env();
+    if !algorithm.needs_rehash(&user.password_hash) {
+        return;
+    }
+
+    let result = match algorithm.hash(password) {
+        Ok(password_hash) => repo
+            .set_password_hash(user.id, &password_hash)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    match result {
+        Ok(()) => tracing::info!("Rehashed password of user {} with {}", user.id, algorithm),
+        Err(e) => tracing::warn!("Failed to rehash password of user {}: {}", user.id, e),
     }
 }
+
+async fn record_throttled_failure(
+    logs: &dyn LogRepository,
+    throttle: &LoginThrottle,
+    email: &str,
+    source: &str,
+    now: DateTime<Utc>,
+) {
+    let locks = throttle.record_failure(email, source, now);
+    if let Some(until) = locks.email {
+        log_security_event(
+            logs,
+            "login_email_locked",
+            format!("Logins for '{}' locked after repeated failures", email),
+            None,
+            json!({ "email": email, "source": source, "lockedUntil": until }),
+        )
+        .await;
+    }
+    if let Some(until) = locks.source {
+        log_security_event(
+            logs,
+            "login_source_locked",
+            format!("Logins from '{}' locked after repeated failures", source),
+            None,
+            json!({ "email": email, "source": source, "lockedUntil": until }),
+        )
+        .await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::database::test_utils::{pool, reset_all_tables};
     use crate::models::{CreateUser, LoginRequest, UpdateUser};
+    use crate::lockout::LockoutPolicy;
+    use crate::permissions::{default_permissions, ROLE_OPERATOR};
+    use crate::repositories::memory::{InMemoryLogRepository, InMemoryUserRepository};
+    use crate::session::SessionStore;
+    use crate::totp::InMemoryTotpSecrets;
     use anyhow::Result as AnyResult;
     use serial_test::serial;
+    use std::sync::Arc;
     use uuid::Uuid;
 
+    fn session_user(user_id: Uuid, role: &str) -> SessionUser {
+        SessionUser {
+            user_id,
+            email: "session@example.com".to_string(),
+            username: "session_user".to_string(),
+            role: role.to_string(),
+            permissions: default_permissions(role)
+                .iter()
+                .map(|permission| permission.as_str().to_string())
+                .collect(),
+            signed_in_at: chrono::Utc::now(),
+        }
+    }
+
+    fn signed_in_as(user_id: Uuid) -> CurrentUser {
+        CurrentUser::for_tests(session_user(user_id, ROLE_USER), Arc::new(SessionStore::new()))
+    }
+
+    fn test_session() -> Session {
+        Session::for_tests(&Uuid::new_v4().to_string(), Arc::new(SessionStore::new()))
+    }
+
     fn sample_user_payload() -> CreateUser {
         let unique_suffix = Uuid::new_v4();
         CreateUser {
@@ -245,6 +679,149 @@ mod tests {
         }
     }
 
+    /// Creates users `ann_smith`, `bob_jones` (deactivated) and `carol`.
+    async fn create_listing_fixture(repo: &dyn UserRepository) -> Vec<PublicUser> {
+        let mut created = Vec::new();
+        for (email, username, first_name, last_name) in [
+            ("ann@example.com", "ann_smith", Some("Ann"), Some("Smith")),
+            ("bob@example.com", "bob_jones", Some("Bob"), Some("Jones")),
+            ("carol@example.org", "carol", Some("Carol"), None),
+        ] {
+            let user = create_user_with(
+                repo,
+                CreateUser {
+                    email: email.to_string(),
+                    username: username.to_string(),
+                    password: "Sup3r$ecret".to_string(),
+                    first_name: first_name.map(str::to_string),
+                    last_name: last_name.map(str::to_string),
+                },
+            )
+            .await
+            .expect("user creation should succeed");
+            created.push(user);
+        }
+
+        repo.set_active(created[1].id, false, Utc::now()).await.unwrap();
+        created
+    }
+
+    fn usernames(page: &UserPage) -> Vec<&str> {
+        page.users.iter().map(|user| user.username.as_str()).collect()
+    }
+
+    async fn assert_user_listing(repo: &dyn UserRepository) {
+        let by_username = |search: Option<&str>| UserQuery {
+            search: search.map(str::to_string),
+            sort_by: Some(crate::models::UserSortBy::Username),
+            sort_order: Some(crate::models::SortOrder::Asc),
+            ..UserQuery::default()
+        };
+
+        let first = list_users_with(repo, UserQuery { limit: Some(2), ..by_username(None) })
+            .await
+            .unwrap();
+        assert_eq!(usernames(&first), vec!["ann_smith", "bob_jones"]);
+        assert_eq!((first.total, first.limit, first.offset), (3, 2, 0));
+        let second = list_users_with(repo, UserQuery { limit: Some(2), offset: Some(2), ..by_username(None) })
+            .await
+            .unwrap();
+        assert_eq!(usernames(&second), vec!["carol"]);
+
+        for (search, expected) in [
+            ("SMITH", vec!["ann_smith"]),
+            ("bob jones", vec!["bob_jones"]),
+            ("example.org", vec!["carol"]),
+            // Wildcards match literally.
+            ("_", vec!["ann_smith", "bob_jones"]),
+            ("%", vec![]),
+        ] {
+            let page = list_users_with(repo, by_username(Some(search))).await.unwrap();
+            assert_eq!(usernames(&page), expected, "search {:?}", search);
+            assert_eq!(page.total, expected.len() as i64);
+        }
+
+        let active = list_users_with(repo, UserQuery { is_active: Some(true), ..by_username(None) })
+            .await
+            .unwrap();
+        assert_eq!(usernames(&active), vec!["ann_smith", "carol"]);
+
+        let yesterday = Utc::now() - chrono::Duration::days(1);
+        let old = list_users_with(repo, UserQuery { created_before: Some(yesterday), ..by_username(None) })
+            .await
+            .unwrap();
+        assert_eq!(old.total, 0);
+        let recent = list_users_with(repo, UserQuery { created_after: Some(yesterday), ..by_username(None) })
+            .await
+            .unwrap();
+        assert_eq!(recent.total, 3);
+
+        let reversed = list_users_with(
+            repo,
+            UserQuery {
+                created_after: Some(Utc::now()),
+                created_before: Some(yesterday),
+                ..UserQuery::default()
+            },
+        )
+        .await;
+        assert!(matches!(reversed, Err(message) if message.starts_with("Invalid date range")));
+    }
+
+    #[tokio::test]
+    async fn users_are_listed_a_page_at_a_time() {
+        let repo = InMemoryUserRepository::new();
+        let created = create_listing_fixture(&repo).await;
+        assert_user_listing(&repo).await;
+
+        // Soft-deleted users only show up when asked for.
+        delete_user_with(&repo, &created[2].id.to_string()).await.unwrap();
+        let page = list_users_with(&repo, UserQuery::default()).await.unwrap();
+        assert_eq!(page.total, 2);
+        let page = list_users_with(
+            &repo,
+            UserQuery {
+                include_deleted: Some(true),
+                limit: Some(0),
+                ..UserQuery::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!((page.users.len(), page.total, page.limit), (1, 3, 1));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn users_are_listed_a_page_at_a_time_in_postgres() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        let repo = SqlUserRepository::from_global()?;
+        create_listing_fixture(&repo).await;
+        assert_user_listing(&repo).await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn concurrent_first_registrations_make_one_admin() -> AnyResult<()> {
+        let pool = pool().await?;
+        reset_all_tables(pool.as_ref()).await?;
+
+        let repo = SqlUserRepository::from_global()?;
+        let (first, second) = tokio::join!(
+            create_user_with(&repo, sample_user_payload()),
+            create_user_with(&repo, sample_user_payload()),
+        );
+        let mut roles = vec![first.unwrap().role, second.unwrap().role];
+        roles.sort();
+        assert_eq!(roles, vec![ROLE_ADMIN, ROLE_USER]);
+
+        Ok(())
+    }
+
     #[tokio::test]
     #[serial]
     async fn full_user_lifecycle_and_authentication() -> AnyResult<()> {
@@ -261,24 +838,37 @@ mod tests {
         assert_eq!(created.email, email);
         assert_eq!(created.first_name.as_deref(), Some("Test"));
 
-        let listed = get_all_users().await.expect("listing users should succeed");
+        let listed = get_all_users(None).await.expect("listing users should succeed");
         assert_eq!(listed.len(), 1);
         assert_eq!(listed[0].email, email);
 
-        let fetched = get_user_by_id(created.id.to_string())
+        let fetched = get_user_by_id(signed_in_as(created.id), created.id.to_string())
             .await
             .expect("fetching user should succeed")
             .expect("user should exist");
         assert_eq!(fetched.username, listed[0].username);
 
+        let other_user = update_user(
+            signed_in_as(Uuid::new_v4()),
+            created.id.to_string(),
+            UpdateUser {
+                email: None,
+                username: None,
+                first_name: Some("Intruder".to_string()),
+                last_name: None,
+            },
+        )
+        .await;
+        assert!(matches!(other_user, Err(message) if message.starts_with("[FORBIDDEN]")));
+
         let updated = update_user(
+            signed_in_as(created.id),
             created.id.to_string(),
             UpdateUser {
                 email: None,
                 username: Some("updated_user".to_string()),
                 first_name: Some("Updated".to_string()),
                 last_name: None,
-                is_active: Some(true),
             },
         )
         .await
@@ -287,46 +877,491 @@ mod tests {
         assert_eq!(updated.first_name.as_deref(), Some("Updated"));
         assert_eq!(updated.username, "updated_user");
 
-        let authenticated = authenticate_user(LoginRequest {
-            email: email.clone(),
-            password,
-        })
+        let authenticated = authenticate_user(
+            test_session(),
+            LoginRequest {
+                email: email.clone(),
+                password,
+                totp_code: None,
+            },
+        )
         .await
         .expect("authentication should succeed")
         .expect("credentials should match");
         assert_eq!(authenticated.id, created.id);
 
-        let wrong_password = authenticate_user(LoginRequest {
-            email: email.clone(),
-            password: "badpassword".to_string(),
-        })
+        let wrong_password = authenticate_user(
+            test_session(),
+            LoginRequest {
+                email: email.clone(),
+                password: "badpassword".to_string(),
+                totp_code: None,
+            },
+        )
         .await
         .expect("authentication should return Ok")
         .is_none();
         assert!(wrong_password);
 
-        let deletion = delete_user(created.id.to_string())
+        let deletion = delete_user(signed_in_as(created.id), created.id.to_string())
             .await
             .expect("deleting user should succeed");
         assert_eq!(deletion, "User deleted successfully");
 
-        let missing = get_user_by_id(created.id.to_string())
+        let missing = get_user_by_id(signed_in_as(created.id), created.id.to_string())
             .await
             .expect("fetch should succeed")
             .is_none();
         assert!(missing);
+        assert!(get_all_users(None).await.unwrap().is_empty());
+
+        let deleted = get_all_users(Some(true)).await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert!(deleted[0].deleted_at.is_some());
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn user_logic_runs_against_in_memory_repository() {
+        let repo = InMemoryUserRepository::new();
+        let payload = sample_user_payload();
+        let email = payload.email.clone();
+        let password = payload.password.clone();
+
+        let created = create_user_with(&repo, payload)
+            .await
+            .expect("user creation should succeed");
+        assert_eq!(get_all_users_with(&repo, false).await.unwrap().len(), 1);
+
+        let duplicate = CreateUser {
+            email: email.clone(),
+            ..sample_user_payload()
+        };
+        assert!(create_user_with(&repo, duplicate).await.is_err());
+
+        repo.set_active(created.id, false, Utc::now())
+            .await
+            .expect("deactivating user should succeed");
+
+        let inactive_login = authenticate_user_with(
+            &repo,
+            &InMemoryLogRepository::new(),
+            &LoginThrottle::default(),
+            &InMemoryTotpSecrets::default(),
+            false,
+            "test",
+            LoginRequest { email, password, totp_code: None },
+        )
+        .await
+        .expect("authentication should return Ok");
+        assert!(inactive_login.is_none());
+
+        assert_eq!(
+            delete_user_with(&repo, &created.id.to_string()).await,
+            Ok("User deleted successfully".to_string())
+        );
+        assert!(get_user_by_id_with(&repo, &created.id.to_string())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn deleted_users_are_hidden_until_restored_or_purged() {
+        let repo = InMemoryUserRepository::new();
+        let kept = create_user_with(&repo, sample_user_payload()).await.unwrap();
+        let payload = sample_user_payload();
+        let email = payload.email.clone();
+        let password = payload.password.clone();
+        let deleted = create_user_with(&repo, payload).await.unwrap();
+        let deleted_id = deleted.id.to_string();
+
+        delete_user_with(&repo, &deleted_id).await.unwrap();
+        assert!(repo.sessions_revoked_at(deleted.id).await.is_some());
+        assert_eq!(delete_user_with(&repo, &deleted_id).await.unwrap_err(), "User not found");
+
+        let listed = get_all_users_with(&repo, false).await.unwrap();
+        assert_eq!(listed.iter().map(|user| user.id).collect::<Vec<_>>(), vec![kept.id]);
+        assert_eq!(get_all_users_with(&repo, true).await.unwrap().len(), 2);
+        assert!(get_user_by_id_with(&repo, &deleted_id).await.unwrap().is_none());
+
+        let login = authenticate_user_with(
+            &repo,
+            &InMemoryLogRepository::new(),
+            &LoginThrottle::default(),
+            &InMemoryTotpSecrets::default(),
+            false,
+            "test",
+            LoginRequest { email, password, totp_code: None },
+        )
+        .await;
+        assert_eq!(login.map(|user| user.is_none()), Ok(true));
+
+        let restored = restore_user_with(&repo, &deleted_id).await.unwrap();
+        assert!(restored.deleted_at.is_none());
+        assert_eq!(
+            restore_user_with(&repo, &deleted_id).await.unwrap_err(),
+            "Deleted user not found"
+        );
+
+        // Only accounts deleted before the retention period are purged.
+        delete_user_with(&repo, &deleted_id).await.unwrap();
+        let now = Utc::now();
+        assert_eq!(purge_deleted_users_with(&repo, now).await, Ok(0));
+        let later = now + crate::retention::deleted_user_retention() + chrono::Duration::days(1);
+        assert_eq!(purge_deleted_users_with(&repo, later).await, Ok(1));
+        assert!(repo.find_by_id(deleted.id).await.unwrap().is_none());
+        assert!(repo.find_by_id(kept.id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn accounts_are_deactivated_by_user_admins_only() {
+        let repo = InMemoryUserRepository::new();
+        let admin_account = create_user_with(&repo, sample_user_payload()).await.unwrap();
+        let target = create_user_with(&repo, sample_user_payload()).await.unwrap();
+        let admin = session_user(admin_account.id, ROLE_ADMIN);
+        let operator = session_user(Uuid::new_v4(), ROLE_OPERATOR);
+        let target_id = target.id.to_string();
+
+        let denied = set_user_active_with(&repo, &operator, &target_id, false).await;
+        assert!(matches!(denied, Err(message) if message.starts_with("[FORBIDDEN]")));
+        assert!(set_user_active_with(&repo, &admin, &admin_account.id.to_string(), false)
+            .await
+            .is_err());
+
+        let deactivated = set_user_active_with(&repo, &admin, &target_id, false).await.unwrap();
+        assert!(!deactivated.is_active);
+        assert!(repo.sessions_revoked_at(deactivated.id).await.is_some());
+        let reactivated = set_user_active_with(&repo, &admin, &target_id, true).await.unwrap();
+        assert!(reactivated.is_active);
+
+        delete_user_with(&repo, &target_id).await.unwrap();
+        let deleted = set_user_active_with(&repo, &admin, &target_id, true).await;
+        assert_eq!(deleted.unwrap_err(), "User not found");
+    }
+
+    #[tokio::test]
+    async fn roles_are_assigned_by_admins_only() {
+        let repo = InMemoryUserRepository::new();
+        let first = create_user_with(&repo, sample_user_payload()).await.unwrap();
+        let second = create_user_with(&repo, sample_user_payload()).await.unwrap();
+        assert_eq!(first.role, ROLE_ADMIN);
+        assert_eq!(second.role, ROLE_USER);
+
+        let admin = session_user(first.id, ROLE_ADMIN);
+        let operator = session_user(Uuid::new_v4(), ROLE_OPERATOR);
+        let second_id = second.id.to_string();
+
+        let denied = set_user_role_with(&repo, &operator, &second_id, ROLE_ADMIN).await;
+        assert!(matches!(denied, Err(message) if message.starts_with("[FORBIDDEN]")));
+        assert!(set_user_role_with(&repo, &admin, &second_id, "root").await.is_err());
+        assert!(set_user_role_with(&repo, &admin, &first.id.to_string(), ROLE_USER)
+            .await
+            .is_err());
+
+        let promoted = set_user_role_with(&repo, &admin, &second_id, ROLE_OPERATOR)
+            .await
+            .expect("admins can assign roles");
+        assert_eq!(promoted.role, ROLE_OPERATOR);
+        assert_eq!(
+            repo.permissions_for_role(ROLE_OPERATOR).await.unwrap(),
+            vec!["logs:read".to_string(), "users:read".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn repeated_failures_lock_the_account_and_are_logged() {
+        let repo = InMemoryUserRepository::new();
+        let logs = InMemoryLogRepository::new();
+        let throttle = LoginThrottle::new(LockoutPolicy {
+            max_attempts: 2,
+            ..LockoutPolicy::default()
+        });
+        let totp = InMemoryTotpSecrets::default();
+        let payload = sample_user_payload();
+        let email = payload.email.clone();
+        let password = payload.password.clone();
+        let created = create_user_with(&repo, payload).await.unwrap();
+
+        let attempt = |source: &'static str, password: &str| {
+            authenticate_user_with(
+                &repo,
+                &logs,
+                &throttle,
+                &totp,
+                false,
+                source,
+                LoginRequest {
+                    email: email.clone(),
+                    password: password.to_string(),
+                    totp_code: None,
+                },
+            )
+        };
+
+        assert_eq!(attempt("main", "wrong").await.map(|user| user.is_none()), Ok(true));
+        assert_eq!(attempt("kiosk", "wrong").await.map(|user| user.is_none()), Ok(true));
+
+        let locked = attempt("other", &password).await;
+        assert!(matches!(locked, Err(message) if message.starts_with("Too many failed login attempts")));
+
+        let stored = repo.find_by_id(created.id).await.unwrap().unwrap();
+        assert_eq!(stored.failed_login_attempts, 2);
+        assert!(stored.locked_until.is_some());
+
+        let events = logs
+            .query(crate::repositories::LogFilter {
+                level: Some("warn".to_string()),
+                user_id: Some(created.id),
+                limit: 10,
+                offset: 0,
+            })
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].metadata["event"], json!("account_locked"));
+        assert_eq!(events[0].metadata["failedAttempts"], json!(2));
+
+        // The email stays locked in every window after the account is unlocked.
+        repo.clear_failed_logins(created.id).await.unwrap();
+        let still_locked = attempt("new-window", &password).await;
+        assert!(matches!(still_locked, Err(message) if message.starts_with("Too many failed login attempts")));
+    }
+
+    #[tokio::test]
+    async fn sources_trying_many_emails_are_locked() {
+        let repo = InMemoryUserRepository::new();
+        let logs = InMemoryLogRepository::new();
+        let throttle = LoginThrottle::new(LockoutPolicy {
+            max_attempts: 2,
+            ..LockoutPolicy::default()
+        });
+        let totp = InMemoryTotpSecrets::default();
+        let payload = sample_user_payload();
+        let (email, password) = (payload.email.clone(), payload.password.clone());
+        create_user_with(&repo, payload).await.unwrap();
+
+        let attempt = |source: &'static str, email: &str, password: &str| {
+            authenticate_user_with(
+                &repo,
+                &logs,
+                &throttle,
+                &totp,
+                false,
+                source,
+                LoginRequest {
+                    email: email.to_string(),
+                    password: password.to_string(),
+                    totp_code: None,
+                },
+            )
+        };
+
+        assert_eq!(attempt("main", "a@example.com", "wrong").await.map(|user| user.is_none()), Ok(true));
+        assert_eq!(attempt("main", "b@example.com", "wrong").await.map(|user| user.is_none()), Ok(true));
+
+        let locked = attempt("main", &email, &password).await;
+        assert!(matches!(locked, Err(message) if message.starts_with("Too many failed login attempts")));
+        assert!(attempt("other", &email, &password).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn successful_logins_forget_earlier_email_failures() {
+        let repo = InMemoryUserRepository::new();
+        let logs = InMemoryLogRepository::new();
+        let throttle = LoginThrottle::new(LockoutPolicy {
+            max_attempts: 2,
+            ..LockoutPolicy::default()
+        });
+        let totp = InMemoryTotpSecrets::default();
+        let payload = sample_user_payload();
+        let (email, password) = (payload.email.clone(), payload.password.clone());
+        create_user_with(&repo, payload).await.unwrap();
+
+        let attempt = |source: &'static str, password: &str| {
+            authenticate_user_with(
+                &repo,
+                &logs,
+                &throttle,
+                &totp,
+                false,
+                source,
+                LoginRequest {
+                    email: email.clone(),
+                    password: password.to_string(),
+                    totp_code: None,
+                },
+            )
+        };
+
+        assert_eq!(attempt("main", "wrong").await.map(|user| user.is_none()), Ok(true));
+        assert!(attempt("other", &password).await.unwrap().is_some());
+        // Without the reset this would be the second failure and lock the email.
+        assert_eq!(attempt("kiosk", "wrong").await.map(|user| user.is_none()), Ok(true));
+        assert!(attempt("new-window", &password).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn legacy_bcrypt_hashes_are_rehashed_on_login() {
+        let repo = InMemoryUserRepository::new();
+        let created = repo
+            .create(
+                NewUser {
+                    email: "legacy@example.com".to_string(),
+                    username: "legacy_user".to_string(),
+                    password_hash: bcrypt::hash("Sup3r$ecret", 4).unwrap(),
+                    first_name: None,
+                    last_name: None,
+                    role: ROLE_USER.to_string(),
+                },
+                ROLE_USER,
+            )
+            .await
+            .unwrap();
+        let logs = InMemoryLogRepository::new();
+        let throttle = LoginThrottle::default();
+        let totp = InMemoryTotpSecrets::default();
+
+        let login = |password: &str| {
+            authenticate_user_with(
+                &repo,
+                &logs,
+                &throttle,
+                &totp,
+                false,
+                "test",
+                LoginRequest {
+                    email: "legacy@example.com".to_string(),
+                    password: password.to_string(),
+                    totp_code: None,
+                },
+            )
+        };
+
+        assert!(login("wrong").await.unwrap().is_none());
+        let stored = repo.find_by_id(created.id).await.unwrap().unwrap();
+        assert!(stored.password_hash.starts_with("$2b$"));
+
+        assert!(login("Sup3r$ecret").await.unwrap().is_some());
+        let stored = repo.find_by_id(created.id).await.unwrap().unwrap();
+        assert!(stored.password_hash.starts_with("$argon2id$"));
+
+        assert!(login("Sup3r$ecret").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn create_user_enforces_the_password_policy() {
+        let repo = InMemoryUserRepository::new();
+
+        for (password, expected) in [
+            ("Sh0rt", "Invalid password: Password must be at least 8 characters"),
+            ("Password123", "Invalid password: Password is too common"),
+        ] {
+            let response = create_user_with(
+                &repo,
+                CreateUser {
+                    password: password.to_string(),
+                    ..sample_user_payload()
+                },
+            )
+            .await;
+            assert_eq!(response.unwrap_err(), expected);
+        }
+
+        let payload = sample_user_payload();
+        let response = create_user_with(
+            &repo,
+            CreateUser {
+                password: format!("{}!X9", payload.username),
+                ..payload
+            },
+        )
+        .await;
+        assert_eq!(
+            response.unwrap_err(),
+            "Invalid password: Password must not contain the username or email"
+        );
+        assert!(repo.list(true).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn update_user_reports_when_missing() {
+        let repo = InMemoryUserRepository::new();
+
+        let response = update_user_with(
+            &repo,
+            &Uuid::new_v4().to_string(),
+            UpdateUser {
+                email: None,
+                username: None,
+                first_name: Some("Ghost".to_string()),
+                last_name: None,
+            },
+        )
+        .await;
+        assert!(matches!(response, Err(message) if message == "User not found"));
+
+        let invalid = get_user_by_id_with(&repo, "not-a-uuid").await;
+        assert!(matches!(invalid, Err(message) if message.starts_with("Invalid UUID")));
+    }
+
     #[tokio::test]
     #[serial]
     async fn delete_user_reports_when_missing() -> AnyResult<()> {
         let pool = pool().await?;
         reset_all_tables(pool.as_ref()).await?;
 
-        let response = delete_user(Uuid::new_v4().to_string()).await;
+        let user_id = Uuid::new_v4();
+        let response = delete_user(signed_in_as(user_id), user_id.to_string()).await;
         assert!(matches!(response, Err(message) if message == "User not found"));
         Ok(())
     }
+
+    #[cfg(feature = "module-sqlite")]
+    #[tokio::test]
+    #[serial]
+    async fn user_lifecycle_on_sqlite() -> AnyResult<()> {
+        crate::database::test_utils::sqlite_pool().await?;
+
+        let payload = sample_user_payload();
+        let email = payload.email.clone();
+        let password = payload.password.clone();
+
+        let created = create_user(payload)
+            .await
+            .expect("user creation should succeed");
+        let listed = get_all_users(None).await.expect("listing users should succeed");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, created.id);
+
+        let updated = update_user(
+            signed_in_as(created.id),
+            created.id.to_string(),
+            UpdateUser {
+                email: None,
+                username: None,
+                first_name: Some("Lite".to_string()),
+                last_name: None,
+            },
+        )
+        .await
+        .expect("updating user should succeed");
+        assert_eq!(updated.first_name.as_deref(), Some("Lite"));
+
+        let authenticated = authenticate_user(test_session(), LoginRequest { email, password, totp_code: None })
+            .await
+            .expect("authentication should succeed");
+        assert_eq!(authenticated.map(|user| user.id), Some(created.id));
+
+        delete_user(signed_in_as(created.id), created.id.to_string())
+            .await
+            .expect("deleting user should succeed");
+        assert!(get_user_by_id(signed_in_as(created.id), created.id.to_string())
+            .await
+            .expect("fetch should succeed")
+            .is_none());
+
+        Ok(())
+    }
 }
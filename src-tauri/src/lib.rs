@@ -5,21 +5,33 @@ pub mod stronghold;
 mod cache;
 mod config;
 mod database;
+mod email_verification;
 mod errors;
 mod handlers;
+mod lockout;
 mod logging;
+mod mail;
 mod models;
+mod password;
+mod password_reset;
+mod permissions;
 mod rate_limiter;
 #[cfg(test)]
 mod rate_limiter_test;
+mod repositories;
+mod retention;
+mod session;
+mod settings;
+mod totp;
 mod validation;
 
 mod modules;
 use config::AppConfig;
 use handlers::*;
 use rate_limiter::RateLimiterConfig;
+use session::SessionStore;
 use std::sync::Arc;
-use tauri::Manager;
+use tauri::{Emitter, EventTarget, Manager};
 
 /// Basic greeting command for testing Tauri functionality.
 #[tauri::command]
@@ -27,94 +39,49 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-/// Initializes and runs the Tauri application with all configured plugins and handlers.
-///
-/// Sets up the application with:
-/// - File system, dialog, notification, and shell plugins
-/// - Database connection and migrations
-/// - Rate limiting for all commands
-/// - Comprehensive error handling and logging
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_fs::init())
-        .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_notification::init())
-        .plugin(tauri_plugin_window_state::Builder::default().build())
-        .plugin(tauri_plugin_os::init())
-        .plugin(tauri_plugin_shell::init())
-        .plugin(tauri_plugin_stronghold::Builder::new(|password| {
-            use argon2::{Algorithm, Argon2, Params, Version};
-            let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default());
-            let salt = &[0; 32];
-            let mut output = [0u8; 32];
-            argon2.hash_password_into(password.as_bytes(), salt, &mut output)
-                .expect("failed to hash password");
-            output.to_vec()
-        }).build())
-        .setup(|app| {
-            let config = AppConfig::from_env();
-            tracing::info!("App environment: {:?}", config.environment);
-
-            let rate_limiter = Arc::new(RateLimiterConfig::new());
-            app.manage(rate_limiter.clone());
-            tracing::info!("Rate limiter initialized successfully");
-
-
-            if let Err(e) = logging::init_logging_from_env() {
-                eprintln!("Failed to initialize logging: {}", e);
-            } else {
-                tracing::info!("Logging system initialized successfully");
-            }
-
-            if let Err(e) = cache::initialize_redis() {
-                tracing::warn!("Failed to initialize Redis: {}. Continuing without caching.", e);
-            }
-
-            tauri::async_runtime::spawn(async move {
-                match database::create_pool().await {
-                    Ok(pool) => {
-                        database::connection::initialize_pool(pool).await;
-                        tracing::info!("Database initialized successfully");
-
-                        if let Ok(pool) = database::get_pool_ref() {
-                            if let Err(e) = database::migrations::run_migrations(pool.as_ref()).await {
-                                tracing::error!("Failed to run migrations: {}", e);
-                            } else {
-                                tracing::info!("Migrations completed successfully");
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to initialize database: {}", e);
-                    }
-                }
-            });
-
-            let rate_limiter_cleanup = rate_limiter.clone();
-            tauri::async_runtime::spawn(async move {
-                let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
-                loop {
-                    interval.tick().await;
-                    rate_limiter_cleanup.cleanup_old_limiters();
-                    tracing::debug!("Cleaned up old rate limiters");
-                }
-            });
-
-            Ok(())
-        })
-        .invoke_handler(tauri::generate_handler![
+/// Expands to the invoke handler for every core command, followed by
+/// `$extra` commands from enabled modules.
+macro_rules! app_commands {
+    ($($extra:ident),* $(,)?) => {
+        tauri::generate_handler![
             rl_greet,
             rl_check_database_connection,
             rl_initialize_database,
             rl_run_migrations,
+            rl_rollback_migrations,
+            rl_get_applied_migrations,
+            rl_set_database_credentials,
+            rl_rotate_database_password,
             rl_get_all_users,
+            rl_list_users,
             rl_get_user_by_id,
             rl_create_user,
             rl_update_user,
             rl_delete_user,
+            rl_restore_user,
+            rl_purge_deleted_users,
+            rl_deactivate_user,
+            rl_reactivate_user,
+            rl_set_user_role,
+            rl_get_user_settings,
+            rl_upsert_user_settings,
+            rl_reset_user_settings,
+            rl_get_settings_schemas,
+            rl_export_settings,
+            rl_import_settings,
+            rl_change_password,
+            rl_request_password_reset,
+            rl_reset_password,
+            rl_begin_totp_enrollment,
+            rl_confirm_totp_enrollment,
+            rl_disable_totp,
+            rl_regenerate_recovery_codes,
+            rl_request_email_verification,
+            rl_verify_email,
             rl_authenticate_user,
+            rl_sign_in,
+            rl_sign_out,
+            rl_get_current_user,
             rl_create_log,
             rl_get_logs,
             rl_delete_old_logs,
@@ -150,8 +117,158 @@ pub fn run() {
             rl_delete_cache_value,
             rl_cache_key_exists,
             rl_is_cache_available,
+            rl_put_secret,
+            rl_get_secret,
+            rl_delete_secret,
+            rl_list_secret_keys,
             get_rate_limiter_status
-        ])
+            $(, $extra)*
+        ]
+    };
+}
+
+/// Registers the commands, including those of enabled modules.
+#[cfg(feature = "module-auth")]
+fn register_commands(builder: tauri::Builder<tauri::Wry>) -> tauri::Builder<tauri::Wry> {
+    builder.invoke_handler(app_commands![
+        rl_auth_login,
+        rl_auth_logout,
+        rl_auth_check,
+        rl_auth_list_sessions,
+        rl_auth_revoke_session,
+    ])
+}
+
+/// Registers the commands, including those of enabled modules.
+#[cfg(not(feature = "module-auth"))]
+fn register_commands(builder: tauri::Builder<tauri::Wry>) -> tauri::Builder<tauri::Wry> {
+    builder.invoke_handler(app_commands![])
+}
+
+/// Initializes and runs the Tauri application with all configured plugins and handlers.
+///
+/// Sets up the application with:
+/// - File system, dialog, notification, and shell plugins
+/// - Database connection, migrations, and automatic reconnection
+/// - Rate limiting for all commands
+/// - Per-window sessions for signed-in users
+/// - Comprehensive error handling and logging
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    if let Err(e) = logging::init_logging_from_env() {
+        eprintln!("Failed to initialize logging: {}", e);
+    } else {
+        tracing::info!("Logging system initialized successfully");
+    }
+
+    let mut builder = tauri::Builder::default()
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_window_state::Builder::default().build())
+        .plugin(tauri_plugin_os::init())
+        .plugin(tauri_plugin_shell::init());
+
+    // Without its saved salt the plugin could not open existing vaults, so it
+    // is left out rather than given a key that would not survive a restart.
+    let data_dir = config::app_data_dir();
+    match stronghold::PluginKeys::load(
+        &data_dir,
+        stronghold::KdfParams::from_env(),
+        &stronghold::plugin_snapshot_dirs(&data_dir),
+    ) {
+        Ok(plugin_keys) => {
+            builder = builder.plugin(tauri_plugin_stronghold::Builder::new(move |password| {
+                plugin_keys.derive_key(password).unwrap_or_else(|e| {
+                    // An empty key makes the plugin report an error instead of opening the vault.
+                    tracing::error!("Failed to derive Stronghold key: {}", e);
+                    Vec::new()
+                })
+            }).build());
+        }
+        Err(e) => tracing::error!("Failed to load Stronghold key derivation settings, the Stronghold plugin is disabled: {}", e),
+    }
+
+    let builder = builder
+        .setup(|app| {
+            let config = AppConfig::from_env();
+            tracing::info!("App environment: {:?}", config.environment);
+
+            let rate_limiter = Arc::new(RateLimiterConfig::new());
+            app.manage(rate_limiter.clone());
+            tracing::info!("Rate limiter initialized successfully");
+
+            app.manage(Arc::new(SessionStore::new()));
+
+            settings::schema::settings_registry().register_modules(settings::schema::MODULE_SETTINGS);
+
+            if let Err(e) = mail::install_mail_transport(config.mail.transport()) {
+                tracing::warn!("Failed to install mail transport: {}", e);
+            }
+
+            if let Err(e) = cache::initialize_redis() {
+                tracing::warn!("Failed to initialize Redis: {}. Continuing without caching.", e);
+            }
+
+            if let Err(e) = stronghold::initialize_vault(&config::app_data_dir()) {
+                tracing::warn!("Failed to open Stronghold vault: {}. Database credentials will be read from the environment.", e);
+            }
+
+            let app_handle = app.handle().clone();
+            database::supervisor::spawn_supervisor(
+                database::supervisor::SupervisorConfig::default(),
+                move |status| {
+                    if let Err(e) = app_handle.emit(database::supervisor::DATABASE_STATUS_EVENT, status) {
+                        tracing::warn!("Failed to emit database status event: {}", e);
+                    }
+                },
+            );
+
+            let app_handle = app.handle().clone();
+            settings::events::settings_events().subscribe(move |change| {
+                // Only webviews signed in as the user may see their settings.
+                let sessions = app_handle.state::<Arc<SessionStore>>();
+                let signed_in_as_user = |target: &EventTarget| match target {
+                    EventTarget::Webview { label } | EventTarget::WebviewWindow { label } => sessions
+                        .get(label)
+                        .is_some_and(|session| session.user_id == change.user_id),
+                    _ => false,
+                };
+                if let Err(e) = app_handle.emit_filter(settings::events::SETTINGS_CHANGED_EVENT, change, signed_in_as_user) {
+                    tracing::warn!("Failed to emit settings changed event: {}", e);
+                }
+            });
+
+            let rate_limiter_cleanup = rate_limiter.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+                loop {
+                    interval.tick().await;
+                    rate_limiter_cleanup.cleanup_old_limiters();
+                    tracing::debug!("Cleaned up old rate limiters");
+                }
+            });
+
+            tauri::async_runtime::spawn(async {
+                let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+                loop {
+                    interval.tick().await;
+                    handlers::users::purge_expired_users().await;
+                }
+            });
+
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Destroyed = event {
+                if let Some(sessions) = window.try_state::<Arc<SessionStore>>() {
+                    sessions.sign_out(window.label());
+                }
+            }
+        });
+
+    register_commands(builder)
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
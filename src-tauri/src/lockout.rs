@@ -0,0 +1,249 @@
+//! Brute-force protection for password logins.
+//!
+//! Failed logins are counted per account, in `users.failed_login_attempts`,
+//! and in memory per email address and per source. The email count also
+//! covers addresses without an account, so they lock like registered ones,
+//! and it is shared by every window, so opening a new one does not reset it.
+//! The source count catches one window trying many different emails. Once
+//! any count reaches the policy's limit, further attempts are refused until
+//! `locked_until`; each failure beyond the limit doubles the lock, up to
+//! `max_delay`.
+
+use chrono::{DateTime, Duration, Utc};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Limits applied to failed logins.
+#[derive(Debug, Clone)]
+pub struct LockoutPolicy {
+    /// Failures allowed before the first lock.
+    pub max_attempts: u32,
+    /// Length of the first lock.
+    pub base_delay: Duration,
+    /// Longest a lock can grow to.
+    pub max_delay: Duration,
+    /// Quiet period after which an email's failures are forgotten.
+    pub reset_after: Duration,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::minutes(1),
+            max_delay: Duration::hours(1),
+            reset_after: Duration::minutes(15),
+        }
+    }
+}
+
+impl LockoutPolicy {
+    /// Lock to apply after `failures` consecutive failures, if any.
+    pub fn lock_duration(&self, failures: u32) -> Option<Duration> {
+        if failures < self.max_attempts {
+            return None;
+        }
+
+        let exponent = (failures - self.max_attempts).min(16);
+        let delay = self.base_delay * (1i32 << exponent);
+        Some(delay.min(self.max_delay))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FailureState {
+    failures: u32,
+    last_failure: DateTime<Utc>,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+/// In-memory failure counts per key, such as an email address.
+#[derive(Debug, Default)]
+struct FailureCounts {
+    states: Mutex<HashMap<String, FailureState>>,
+}
+
+impl FailureCounts {
+    fn locked_until(&self, key: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.states()
+            .get(key)
+            .and_then(|state| state.locked_until)
+            .filter(|until| *until > now)
+    }
+
+    /// Counts a failure for `key`, returning the new lock if one started.
+    /// Keys that are neither locked nor failed within `reset_after` are
+    /// forgotten, so the map only holds recent failures.
+    fn record_failure(&self, policy: &LockoutPolicy, key: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut states = self.states();
+        states.retain(|_, state| {
+            now - state.last_failure <= policy.reset_after || state.locked_until.is_some_and(|until| until > now)
+        });
+
+        let state = states.entry(key.to_string()).or_insert(FailureState {
+            failures: 0,
+            last_failure: now,
+            locked_until: None,
+        });
+        if now - state.last_failure > policy.reset_after {
+            state.failures = 0;
+        }
+        state.failures += 1;
+        state.last_failure = now;
+
+        let until = now + policy.lock_duration(state.failures)?;
+        state.locked_until = Some(until);
+        Some(until)
+    }
+
+    fn clear(&self, key: &str) {
+        self.states().remove(key);
+    }
+
+    fn states(&self) -> std::sync::MutexGuard<'_, HashMap<String, FailureState>> {
+        self.states
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Locks started by one failed login.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NewLocks {
+    pub email: Option<DateTime<Utc>>,
+    pub source: Option<DateTime<Utc>>,
+}
+
+/// In-memory failure counts per login email and per source.
+#[derive(Debug)]
+pub struct LoginThrottle {
+    policy: LockoutPolicy,
+    emails: FailureCounts,
+    sources: FailureCounts,
+}
+
+impl Default for LoginThrottle {
+    fn default() -> Self {
+        Self::new(LockoutPolicy::default())
+    }
+}
+
+impl LoginThrottle {
+    pub fn new(policy: LockoutPolicy) -> Self {
+        Self {
+            policy,
+            emails: FailureCounts::default(),
+            sources: FailureCounts::default(),
+        }
+    }
+
+    pub fn policy(&self) -> &LockoutPolicy {
+        &self.policy
+    }
+
+    /// Returns when `source` may try `email` again, if either is currently
+    /// locked.
+    pub fn locked_until(&self, email: &str, source: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.emails
+            .locked_until(email, now)
+            .max(self.sources.locked_until(source, now))
+    }
+
+    /// Counts a failed login from `source` for `email`.
+    pub fn record_failure(&self, email: &str, source: &str, now: DateTime<Utc>) -> NewLocks {
+        NewLocks {
+            email: self.emails.record_failure(&self.policy, email, now),
+            source: self.sources.record_failure(&self.policy, source, now),
+        }
+    }
+
+    /// Forgets the failures of `email` after a successful login.
+    pub fn record_success(&self, email: &str) {
+        self.emails.clear(email);
+    }
+}
+
+static LOGIN_THROTTLE: Lazy<LoginThrottle> = Lazy::new(LoginThrottle::default);
+
+/// The process-wide throttle used by login commands.
+pub fn login_throttle() -> &'static LoginThrottle {
+    &LOGIN_THROTTLE
+}
+
+/// Message returned while an account or email is locked.
+pub fn locked_message(until: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = (until - now).num_seconds().max(1);
+    format!(
+        "Too many failed login attempts. Try again in {} second(s)",
+        seconds
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_grows_after_the_limit_until_capped() {
+        let policy = LockoutPolicy::default();
+
+        assert_eq!(policy.lock_duration(4), None);
+        assert_eq!(policy.lock_duration(5), Some(Duration::minutes(1)));
+        assert_eq!(policy.lock_duration(6), Some(Duration::minutes(2)));
+        assert_eq!(policy.lock_duration(8), Some(Duration::minutes(8)));
+        assert_eq!(policy.lock_duration(20), Some(Duration::hours(1)));
+        assert_eq!(policy.lock_duration(u32::MAX), Some(Duration::hours(1)));
+    }
+
+    #[test]
+    fn emails_lock_independently_and_recover() {
+        let throttle = LoginThrottle::new(LockoutPolicy {
+            max_attempts: 2,
+            ..LockoutPolicy::default()
+        });
+        let now = Utc::now();
+
+        assert_eq!(throttle.record_failure("user@example.com", "main", now), NewLocks::default());
+        let until = throttle
+            .record_failure("user@example.com", "other", now)
+            .email
+            .expect("second failure should lock");
+        assert_eq!(until, now + Duration::minutes(1));
+        assert_eq!(throttle.locked_until("user@example.com", "new-window", now), Some(until));
+        assert_eq!(throttle.locked_until("other@example.com", "new-window", now), None);
+        assert_eq!(throttle.locked_until("user@example.com", "new-window", until), None);
+
+        let later = now + Duration::hours(1);
+        assert_eq!(throttle.record_failure("user@example.com", "main", later), NewLocks::default());
+        throttle.record_success("user@example.com");
+        assert_eq!(throttle.record_failure("user@example.com", "other", later).email, None);
+    }
+
+    #[test]
+    fn sources_trying_many_emails_lock() {
+        let throttle = LoginThrottle::new(LockoutPolicy {
+            max_attempts: 2,
+            ..LockoutPolicy::default()
+        });
+        let now = Utc::now();
+
+        throttle.record_failure("a@example.com", "main", now);
+        let locks = throttle.record_failure("b@example.com", "main", now);
+        assert_eq!(locks.email, None);
+        let until = locks.source.expect("second failure from one source should lock");
+        assert_eq!(throttle.locked_until("c@example.com", "main", now), Some(until));
+        assert_eq!(throttle.locked_until("c@example.com", "other", now), None);
+    }
+
+    #[test]
+    fn stale_failures_are_pruned() {
+        let throttle = LoginThrottle::default();
+        let now = Utc::now();
+
+        throttle.record_failure("old@example.com", "main", now);
+        throttle.record_failure("new@example.com", "other", now + Duration::hours(1));
+        assert_eq!(throttle.emails.states().len(), 1);
+        assert_eq!(throttle.sources.states().len(), 1);
+    }
+}
@@ -47,7 +47,8 @@ pub async fn get_log_config() -> Result<AppLogConfig, String> {
     Ok(config)
 }
 
-/// Updates and saves the logging configuration to file.
+/// Saves the logging configuration to file and applies its level to the
+/// running logger.
 #[tauri::command]
 pub async fn update_log_config(config: AppLogConfig) -> Result<String, String> {
     info!("Updating log configuration: {:?}", config);
@@ -65,9 +66,14 @@ pub async fn update_log_config(config: AppLogConfig) -> Result<String, String> {
         return Err(format!("Failed to save configuration: {}", e));
     }
 
+    if let Err(e) = crate::logging::apply_log_config(&config) {
+        error!("Failed to apply log configuration: {}", e);
+        return Err(format!("Failed to apply configuration: {}", e));
+    }
+
     info!("Log configuration updated successfully");
     Ok(
-        "Configuration updated successfully. Restart the application for changes to take effect."
+        "Configuration updated successfully. The log level applies now; restart the application for other changes to take effect."
             .to_string(),
     )
 }
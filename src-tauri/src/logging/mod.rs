@@ -3,7 +3,7 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use directories::ProjectDirs;
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
@@ -14,8 +14,9 @@ use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::{
     fmt::{self, format::FmtSpan},
     layer::SubscriberExt,
+    reload,
     util::SubscriberInitExt,
-    EnvFilter, Layer,
+    EnvFilter, Layer, Registry,
 };
 
 pub mod config;
@@ -24,6 +25,9 @@ pub mod handlers;
 /// Ensures logging system is initialized only once.
 static LOG_INITIALIZED: Lazy<std::sync::Mutex<bool>> = Lazy::new(|| std::sync::Mutex::new(false));
 
+/// Swaps the level filter of the running logger.
+static LEVEL_FILTER: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+
 /// Log levels supported by the application.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -135,6 +139,7 @@ pub fn init_logging(config: LogConfig) -> Result<()> {
     let env_filter = EnvFilter::try_from_default_env()
         .or_else(|_| EnvFilter::try_new(config.level.to_string()))
         .unwrap_or_else(|_| EnvFilter::new("info"));
+    let (env_filter, level_filter) = reload::Layer::new(env_filter);
 
     let mut layers = Vec::new();
 
@@ -181,6 +186,7 @@ pub fn init_logging(config: LogConfig) -> Result<()> {
         .with(layers)
         .init();
 
+    let _ = LEVEL_FILTER.set(level_filter);
     *guard = true;
 
     info!(
@@ -195,6 +201,30 @@ pub fn init_logging(config: LogConfig) -> Result<()> {
     Ok(())
 }
 
+/// Applies the level of `config` to the running logger.
+///
+/// Outputs and formats are set up when logging starts and change on the next
+/// launch. As at startup, a valid `RUST_LOG` takes precedence, in which case
+/// the level is left alone. Does nothing before logging is initialized.
+pub fn apply_log_config(config: &config::AppLogConfig) -> Result<()> {
+    let Some(level_filter) = LEVEL_FILTER.get() else {
+        return Ok(());
+    };
+    if EnvFilter::try_from_default_env().is_ok() {
+        return Ok(());
+    }
+
+    let level = if config.enabled {
+        config.level.to_string()
+    } else {
+        "off".to_string()
+    };
+    level_filter.reload(EnvFilter::try_new(&level)?)?;
+    info!("Log level set to {}", level);
+
+    Ok(())
+}
+
 /// Returns the default log directory for the application.
 pub(crate) fn default_log_dir() -> PathBuf {
     ProjectDirs::from("com", "tavuc", "eztauri")
@@ -0,0 +1,344 @@
+//! Outbound email for account workflows.
+//!
+//! Messages are plain text and go through a [`MailTransport`]. At startup the
+//! application installs the transport its [`MailConfig`] describes:
+//!
+//! - `AUTH_MAIL_SMTP` (`host:port`) delivers over unauthenticated, unencrypted
+//!   SMTP, meant for local sinks such as MailHog or Mailpit.
+//! - `AUTH_MAIL_OUTBOX` appends messages to a file, one JSON object per line.
+//! - Without either, messages are written to the application log.
+//!
+//! None of these is meant for production mail; applications with a mail
+//! service install their own with [`install_mail_transport`] instead.
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Sender address when `AUTH_MAIL_FROM` is unset.
+const DEFAULT_FROM: &str = "no-reply@localhost";
+
+/// Mail settings, read from `AUTH_MAIL_*` environment variables.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MailConfig {
+    /// `host:port` of an SMTP sink.
+    pub smtp_address: Option<String>,
+    /// File that messages are appended to when no SMTP sink is set.
+    pub outbox: Option<PathBuf>,
+    /// Sender address for SMTP.
+    pub from: String,
+}
+
+impl MailConfig {
+    /// Reads the settings from `lookup`, ignoring blank values.
+    pub fn from_lookup<F>(lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let value = |key: &str| {
+            lookup(key)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        Self {
+            smtp_address: value("AUTH_MAIL_SMTP"),
+            outbox: value("AUTH_MAIL_OUTBOX").map(PathBuf::from),
+            from: value("AUTH_MAIL_FROM").unwrap_or_else(|| DEFAULT_FROM.to_string()),
+        }
+    }
+
+    /// Reads the settings from the environment.
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// The transport these settings describe.
+    pub fn transport(&self) -> Arc<dyn MailTransport> {
+        if let Some(address) = &self.smtp_address {
+            Arc::new(SmtpSinkTransport::new(address.clone(), self.from.clone()))
+        } else if let Some(path) = &self.outbox {
+            Arc::new(FileMailTransport::new(path))
+        } else {
+            Arc::new(LogMailTransport)
+        }
+    }
+}
+
+/// A plain-text email.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers outbound email.
+#[async_trait]
+pub trait MailTransport: Send + Sync {
+    async fn send(&self, message: &MailMessage) -> Result<()>;
+}
+
+/// Writes messages to the application log. Only suitable for development.
+#[derive(Debug, Default)]
+pub struct LogMailTransport;
+
+#[async_trait]
+impl MailTransport for LogMailTransport {
+    async fn send(&self, message: &MailMessage) -> Result<()> {
+        tracing::info!(
+            "Mail to {}: {}\n{}",
+            message.to,
+            message.subject,
+            message.body
+        );
+        Ok(())
+    }
+}
+
+/// Appends messages to a file, one JSON object per line.
+#[derive(Debug, Clone)]
+pub struct FileMailTransport {
+    path: PathBuf,
+}
+
+impl FileMailTransport {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl MailTransport for FileMailTransport {
+    async fn send(&self, message: &MailMessage) -> Result<()> {
+        let mut line = serde_json::to_string(&serde_json::json!({
+            "to": message.to,
+            "subject": message.subject,
+            "body": message.body,
+            "sentAt": Utc::now(),
+        }))?;
+        line.push('\n');
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+
+        Ok(())
+    }
+}
+
+/// Delivers messages to an SMTP sink without authentication or TLS.
+#[derive(Debug, Clone)]
+pub struct SmtpSinkTransport {
+    address: String,
+    from: String,
+}
+
+impl SmtpSinkTransport {
+    pub fn new(address: impl Into<String>, from: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            from: from.into(),
+        }
+    }
+}
+
+/// Reads one (possibly multi-line) SMTP reply and checks its status code.
+async fn expect_reply<R>(reader: &mut R, expected: u16) -> Result<()>
+where
+    R: AsyncBufReadExt + Unpin,
+{
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            bail!("SMTP server closed the connection");
+        }
+
+        let code: u16 = line
+            .get(..3)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| anyhow!("Malformed SMTP reply: {}", line.trim_end()))?;
+        if code != expected {
+            bail!("Unexpected SMTP reply: {}", line.trim_end());
+        }
+        // "250-" continues a multi-line reply; "250 " ends it.
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok(());
+        }
+    }
+}
+
+#[async_trait]
+impl MailTransport for SmtpSinkTransport {
+    async fn send(&self, message: &MailMessage) -> Result<()> {
+        let stream = TcpStream::connect(&self.address)
+            .await
+            .with_context(|| format!("Failed to connect to SMTP server at {}", self.address))?;
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        expect_reply(&mut reader, 220).await?;
+        for (command, expected) in [
+            ("EHLO localhost".to_string(), 250),
+            (format!("MAIL FROM:<{}>", self.from), 250),
+            (format!("RCPT TO:<{}>", message.to), 250),
+            ("DATA".to_string(), 354),
+        ] {
+            writer.write_all(format!("{}\r\n", command).as_bytes()).await?;
+            expect_reply(&mut reader, expected).await?;
+        }
+
+        let mut data = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n",
+            self.from,
+            message.to,
+            message.subject,
+            Utc::now().to_rfc2822()
+        );
+        for line in message.body.lines() {
+            // Dot-stuffing, so a line with a lone "." does not end the message.
+            if line.starts_with('.') {
+                data.push('.');
+            }
+            data.push_str(line);
+            data.push_str("\r\n");
+        }
+        data.push_str(".\r\n");
+        writer.write_all(data.as_bytes()).await?;
+        expect_reply(&mut reader, 250).await?;
+
+        writer.write_all(b"QUIT\r\n").await?;
+        expect_reply(&mut reader, 221).await?;
+
+        Ok(())
+    }
+}
+
+static MAIL_TRANSPORT: OnceCell<Arc<dyn MailTransport>> = OnceCell::new();
+
+/// Installs the transport used for outbound email. Fails if one is already in use.
+pub fn install_mail_transport(transport: Arc<dyn MailTransport>) -> Result<()> {
+    MAIL_TRANSPORT
+        .set(transport)
+        .map_err(|_| anyhow!("A mail transport is already installed"))
+}
+
+/// The installed transport, or the one [`MailConfig::from_env`] describes if
+/// none has been installed yet.
+pub fn mail_transport() -> Arc<dyn MailTransport> {
+    MAIL_TRANSPORT
+        .get_or_init(|| MailConfig::from_env().transport())
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn message() -> MailMessage {
+        MailMessage {
+            to: "jane@example.com".to_string(),
+            subject: "Hello".to_string(),
+            body: "First line\n.leading dot".to_string(),
+        }
+    }
+
+    #[test]
+    fn config_ignores_blank_values() {
+        let config = MailConfig::from_lookup(|key| match key {
+            "AUTH_MAIL_SMTP" => Some(" ".to_string()),
+            "AUTH_MAIL_OUTBOX" => Some(" outbox.jsonl ".to_string()),
+            _ => None,
+        });
+
+        assert_eq!(
+            config,
+            MailConfig {
+                smtp_address: None,
+                outbox: Some(PathBuf::from("outbox.jsonl")),
+                from: DEFAULT_FROM.to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn file_transport_appends_one_line_per_message() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("outbox.jsonl");
+        let transport = FileMailTransport::new(&path);
+
+        transport.send(&message()).await?;
+        transport.send(&message()).await?;
+
+        let contents = std::fs::read_to_string(&path)?;
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["to"], "jane@example.com");
+        assert_eq!(lines[0]["body"], "First line\n.leading dot");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn smtp_transport_speaks_to_a_sink() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?.to_string();
+
+        // A minimal sink that accepts one message and returns its DATA section.
+        let sink = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let mut data = Vec::new();
+
+            writer.write_all(b"220 sink ready\r\n").await?;
+            while let Some(line) = lines.next_line().await? {
+                let reply: &[u8] = match line.as_str() {
+                    "EHLO localhost" => b"250-sink\r\n250 8BITMIME\r\n",
+                    "DATA" => b"354 go ahead\r\n",
+                    "QUIT" => b"221 bye\r\n",
+                    _ if line.starts_with("MAIL FROM:") || line.starts_with("RCPT TO:") => b"250 ok\r\n",
+                    _ => {
+                        if line == "." {
+                            writer.write_all(b"250 queued\r\n").await?;
+                        } else {
+                            data.push(line);
+                        }
+                        continue;
+                    }
+                };
+                writer.write_all(reply).await?;
+                if line == "QUIT" {
+                    break;
+                }
+            }
+
+            Ok::<_, std::io::Error>(data)
+        });
+
+        SmtpSinkTransport::new(address, "app@example.com")
+            .send(&message())
+            .await?;
+
+        let data = sink.await??;
+        assert!(data.contains(&"To: jane@example.com".to_string()));
+        assert!(data.contains(&"Subject: Hello".to_string()));
+        assert!(data.contains(&"..leading dot".to_string()));
+
+        Ok(())
+    }
+}
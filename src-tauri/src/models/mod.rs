@@ -8,6 +8,5 @@ pub mod settings;
 pub mod user;
 
 pub use logs::*;
-#[allow(unused_imports)]
 pub use settings::*;
 pub use user::*;
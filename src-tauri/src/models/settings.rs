@@ -6,8 +6,8 @@ use sqlx::FromRow;
 use uuid::Uuid;
 
 /// User-specific settings stored in the database.
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
 pub struct UserSettings {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -31,18 +31,21 @@ pub struct CreateUserSettings {
 }
 
 /// Request payload for updating existing user settings.
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct UpdateUserSettings {
     pub theme: Option<String>,
     pub language: Option<String>,
     pub notifications_enabled: Option<bool>,
+    /// JSON merge patch applied to the stored `settings_data`.
     pub settings_data: Option<serde_json::Value>,
 }
 
 /// General application settings with common UI preferences.
+///
+/// These are the defaults under every user's `settings_data`.
 #[derive(Debug, Serialize, Deserialize)]
-#[allow(dead_code)]
+#[serde(rename_all = "camelCase")]
 pub struct AppSettings {
     pub sidebar_collapsed: Option<bool>,
     pub auto_save: Option<bool>,
@@ -18,6 +18,27 @@ pub struct User {
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// One of [`crate::permissions::ROLES`].
+    pub role: String,
+    /// Consecutive failed logins since the last successful one.
+    pub failed_login_attempts: i32,
+    /// Logins are refused until this time.
+    pub locked_until: Option<DateTime<Utc>>,
+    /// Logins also need a TOTP or recovery code.
+    pub totp_enabled: bool,
+    /// Time step of the last TOTP code accepted, to refuse replays.
+    pub totp_last_step: Option<i64>,
+    /// Whether the user has proven they own `email`.
+    pub email_verified: bool,
+    /// When the account was soft-deleted; it is purged after the retention period.
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl User {
+    /// Whether the account may sign in: active and not deleted.
+    pub fn can_sign_in(&self) -> bool {
+        self.is_active && self.deleted_at.is_none()
+    }
 }
 
 /// User model safe for public API responses (excludes password hash).
@@ -31,6 +52,10 @@ pub struct PublicUser {
     pub last_name: Option<String>,
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
+    pub role: String,
+    pub totp_enabled: bool,
+    pub email_verified: bool,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 /// Request payload for creating a new user account.
@@ -45,6 +70,9 @@ pub struct CreateUser {
 }
 
 /// Request payload for updating existing user information.
+///
+/// Accounts are activated and deactivated with `reactivate_user` and
+/// `deactivate_user`, not through updates.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUser {
@@ -52,7 +80,53 @@ pub struct UpdateUser {
     pub username: Option<String>,
     pub first_name: Option<String>,
     pub last_name: Option<String>,
+}
+
+/// Column `list_users` sorts by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UserSortBy {
+    #[default]
+    CreatedAt,
+    Email,
+    Username,
+}
+
+/// Direction of a sort.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Query parameters for listing users a page at a time.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserQuery {
+    /// Case-insensitive text matched against email, username and name.
+    pub search: Option<String>,
     pub is_active: Option<bool>,
+    /// Only users created at or after this time.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only users created before this time.
+    pub created_before: Option<DateTime<Utc>>,
+    pub include_deleted: Option<bool>,
+    pub sort_by: Option<UserSortBy>,
+    pub sort_order: Option<SortOrder>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// One page of users, with the number of users matching the query.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPage {
+    pub users: Vec<PublicUser>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
 }
 
 /// Request payload for user authentication.
@@ -61,6 +135,47 @@ pub struct UpdateUser {
 pub struct LoginRequest {
     pub email: String,
     pub password: String,
+    /// TOTP or recovery code, required once two-factor is enabled.
+    #[serde(default)]
+    pub totp_code: Option<String>,
+}
+
+/// A one-time password reset token. Only the token's hash is stored.
+#[derive(Debug, Clone, FromRow)]
+pub struct PasswordResetToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Hex-encoded SHA-256 of the token sent to the user.
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl PasswordResetToken {
+    /// Whether the token is unused and unexpired at `now`.
+    pub fn is_usable(&self, now: DateTime<Utc>) -> bool {
+        self.used_at.is_none() && self.expires_at > now
+    }
+}
+
+/// A one-time email verification token. Only the token's hash is stored.
+#[derive(Debug, Clone, FromRow)]
+pub struct EmailVerificationToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Hex-encoded SHA-256 of the token sent to the user.
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl EmailVerificationToken {
+    /// Whether the token is unused and unexpired at `now`.
+    pub fn is_usable(&self, now: DateTime<Utc>) -> bool {
+        self.used_at.is_none() && self.expires_at > now
+    }
 }
 
 impl From<User> for PublicUser {
@@ -74,6 +189,10 @@ impl From<User> for PublicUser {
             last_name: user.last_name,
             is_active: user.is_active,
             created_at: user.created_at,
+            role: user.role,
+            totp_enabled: user.totp_enabled,
+            email_verified: user.email_verified,
+            deleted_at: user.deleted_at,
         }
     }
 }
@@ -1,35 +1,19 @@
 //! Authentication models
+//!
+//! Accounts are the application-wide [`crate::models::User`]; this module only
+//! adds the sessions its tokens refer to.
 
 use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct User {
-    pub id: Uuid,
-    pub email: String,
-    pub password_hash: String,
-    pub created_at: chrono::DateTime<chrono::Utc>,
-    pub updated_at: chrono::DateTime<chrono::Utc>,
-    pub email_verified: bool,
-}
-
-impl User {
-    pub fn new(email: String, password_hash: String) -> Self {
-        let now = chrono::Utc::now();
-        Self {
-            id: Uuid::new_v4(),
-            email,
-            password_hash,
-            created_at: now,
-            updated_at: now,
-            email_verified: false,
-        }
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize)]
+/// A signed-in session; its id is the `jti` claim of the issued token.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
 pub struct AuthSession {
+    pub id: Uuid,
     pub user_id: Uuid,
-    pub token: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
     pub expires_at: chrono::DateTime<chrono::Utc>,
-}
\ No newline at end of file
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
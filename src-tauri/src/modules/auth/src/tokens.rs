@@ -0,0 +1,152 @@
+//! HS256 JSON Web Tokens identifying an auth session.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use thiserror::Error;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Encoded `{"alg":"HS256","typ":"JWT"}` header shared by every token.
+const HEADER: &str = r#"{"alg":"HS256","typ":"JWT"}"#;
+
+/// Registered claims carried by session tokens.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    /// User id.
+    pub sub: Uuid,
+    /// Session id, matching a row in `auth_sessions`.
+    pub jti: Uuid,
+    /// Issue time, seconds since the Unix epoch.
+    pub iat: i64,
+    /// Expiry time, seconds since the Unix epoch.
+    pub exp: i64,
+}
+
+/// Reasons a token is rejected.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TokenError {
+    #[error("Malformed token")]
+    Malformed,
+    #[error("Invalid token signature")]
+    InvalidSignature,
+    #[error("Token has expired")]
+    Expired,
+}
+
+fn mac(secret: &[u8]) -> HmacSha256 {
+    HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length")
+}
+
+/// Signs `claims` with `secret`.
+pub fn encode(claims: &Claims, secret: &[u8]) -> String {
+    let payload = serde_json::to_vec(claims).expect("claims always serialize");
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(HEADER),
+        URL_SAFE_NO_PAD.encode(payload)
+    );
+
+    let mut mac = mac(secret);
+    mac.update(signing_input.as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+
+    format!("{}.{}", signing_input, signature)
+}
+
+/// Checks the signature of `token` and returns its claims, ignoring expiry.
+pub fn verify(token: &str, secret: &[u8]) -> Result<Claims, TokenError> {
+    let mut parts = token.split('.');
+    let (Some(header), Some(payload), Some(signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(TokenError::Malformed);
+    };
+
+    let header = URL_SAFE_NO_PAD.decode(header).map_err(|_| TokenError::Malformed)?;
+    let header: serde_json::Value =
+        serde_json::from_slice(&header).map_err(|_| TokenError::Malformed)?;
+    if header["alg"] != "HS256" {
+        return Err(TokenError::Malformed);
+    }
+
+    let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| TokenError::Malformed)?;
+    let signing_input = &token[..token.rfind('.').unwrap_or_default()];
+    let mut mac = mac(secret);
+    mac.update(signing_input.as_bytes());
+    mac.verify_slice(&signature)
+        .map_err(|_| TokenError::InvalidSignature)?;
+
+    let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| TokenError::Malformed)?;
+    serde_json::from_slice(&payload).map_err(|_| TokenError::Malformed)
+}
+
+/// Checks the signature and expiry of `token` at `now`.
+pub fn decode(token: &str, secret: &[u8], now: DateTime<Utc>) -> Result<Claims, TokenError> {
+    let claims = verify(token, secret)?;
+
+    if claims.exp <= now.timestamp() {
+        return Err(TokenError::Expired);
+    }
+
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const SECRET: &[u8] = b"test-secret";
+
+    fn claims(now: DateTime<Utc>, lifetime: Duration) -> Claims {
+        Claims {
+            sub: Uuid::new_v4(),
+            jti: Uuid::new_v4(),
+            iat: now.timestamp(),
+            exp: (now + lifetime).timestamp(),
+        }
+    }
+
+    #[test]
+    fn token_round_trips() {
+        let now = Utc::now();
+        let claims = claims(now, Duration::hours(1));
+
+        let token = encode(&claims, SECRET);
+        assert_eq!(token.split('.').count(), 3);
+        assert_eq!(decode(&token, SECRET, now), Ok(claims));
+    }
+
+    #[test]
+    fn tampered_or_foreign_tokens_are_rejected() {
+        let now = Utc::now();
+        let token = encode(&claims(now, Duration::hours(1)), SECRET);
+
+        assert_eq!(decode(&token, b"other-secret", now), Err(TokenError::InvalidSignature));
+
+        let forged_payload = URL_SAFE_NO_PAD.encode(
+            serde_json::to_vec(&claims(now, Duration::days(365))).unwrap(),
+        );
+        let parts: Vec<&str> = token.split('.').collect();
+        let forged = format!("{}.{}.{}", parts[0], forged_payload, parts[2]);
+        assert_eq!(decode(&forged, SECRET, now), Err(TokenError::InvalidSignature));
+
+        assert_eq!(decode("not-a-token", SECRET, now), Err(TokenError::Malformed));
+        assert_eq!(decode("a.b.c.d", SECRET, now), Err(TokenError::Malformed));
+    }
+
+    #[test]
+    fn expired_tokens_still_verify() {
+        let issued = Utc::now() - Duration::hours(2);
+        let claims = claims(issued, Duration::hours(1));
+        let token = encode(&claims, SECRET);
+
+        assert_eq!(decode(&token, SECRET, Utc::now()), Err(TokenError::Expired));
+        assert_eq!(verify(&token, SECRET), Ok(claims));
+    }
+}
@@ -0,0 +1,222 @@
+//! Password hashing for user accounts.
+//!
+//! New hashes use the algorithm named by `AUTH_HASH_ALGORITHM` (the auth
+//! module's `hash_algorithm` setting). Argon2id and scrypt hashes are stored in
+//! PHC string format (`$argon2id$v=19$...`, `$scrypt$...`); bcrypt has no PHC
+//! encoding and keeps its own `$2b$...` format. Verification recognises all
+//! three from the stored string, so accounts keep working when the setting
+//! changes, and [`HashAlgorithm::needs_rehash`] tells a successful login when
+//! to upgrade the stored hash.
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use once_cell::sync::Lazy;
+use scrypt::Scrypt;
+use std::fmt;
+use std::str::FromStr;
+
+/// Errors raised while hashing or verifying a password.
+#[derive(Debug, thiserror::Error)]
+pub enum PasswordError {
+    #[error("Unknown password hash algorithm '{0}'")]
+    UnknownAlgorithm(String),
+    #[error("Unsupported password hash format")]
+    UnsupportedHash,
+    #[error("{0}")]
+    Hash(String),
+}
+
+/// Algorithm used to hash new passwords.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    #[default]
+    Argon2,
+    Bcrypt,
+    Scrypt,
+}
+
+impl HashAlgorithm {
+    /// Name used by the `hash_algorithm` setting.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            HashAlgorithm::Argon2 => "argon2",
+            HashAlgorithm::Bcrypt => "bcrypt",
+            HashAlgorithm::Scrypt => "scrypt",
+        }
+    }
+
+    /// Reads `AUTH_HASH_ALGORITHM`, falling back to Argon2id when it is unset
+    /// or names an unknown algorithm.
+    pub fn from_env() -> Self {
+        match std::env::var("AUTH_HASH_ALGORITHM") {
+            Ok(value) => value.parse().unwrap_or_else(|e| {
+                tracing::warn!("{}; using {}", e, Self::default());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Returns the algorithm `hash` was produced with.
+    pub fn of_hash(hash: &str) -> Option<Self> {
+        if hash.starts_with("$argon2") {
+            Some(HashAlgorithm::Argon2)
+        } else if hash.starts_with("$scrypt$") {
+            Some(HashAlgorithm::Scrypt)
+        } else if ["$2a$", "$2b$", "$2x$", "$2y$"]
+            .iter()
+            .any(|prefix| hash.starts_with(prefix))
+        {
+            Some(HashAlgorithm::Bcrypt)
+        } else {
+            None
+        }
+    }
+
+    /// Hashes `password` with a fresh random salt.
+    pub fn hash(self, password: &str) -> Result<String, PasswordError> {
+        match self {
+            HashAlgorithm::Argon2 => phc_hash(&Argon2::default(), password),
+            HashAlgorithm::Scrypt => phc_hash(&Scrypt, password),
+            HashAlgorithm::Bcrypt => bcrypt::hash(password, bcrypt::DEFAULT_COST)
+                .map_err(|e| PasswordError::Hash(e.to_string())),
+        }
+    }
+
+    /// Whether `hash` should be replaced by one made with this algorithm.
+    ///
+    /// Argon2 hashes are also replaced when they use the Argon2i or Argon2d
+    /// variant rather than Argon2id.
+    pub fn needs_rehash(self, hash: &str) -> bool {
+        match Self::of_hash(hash) {
+            Some(HashAlgorithm::Argon2) => {
+                self != HashAlgorithm::Argon2 || !hash.starts_with("$argon2id$")
+            }
+            Some(algorithm) => algorithm != self,
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = PasswordError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "argon2" | "argon2id" => Ok(HashAlgorithm::Argon2),
+            "bcrypt" => Ok(HashAlgorithm::Bcrypt),
+            "scrypt" => Ok(HashAlgorithm::Scrypt),
+            other => Err(PasswordError::UnknownAlgorithm(other.to_string())),
+        }
+    }
+}
+
+/// Checks `password` against a stored hash of any supported algorithm.
+pub fn verify_password(password: &str, hash: &str) -> Result<bool, PasswordError> {
+    match HashAlgorithm::of_hash(hash) {
+        Some(HashAlgorithm::Argon2) => phc_verify(&Argon2::default(), password, hash),
+        Some(HashAlgorithm::Scrypt) => phc_verify(&Scrypt, password, hash),
+        Some(HashAlgorithm::Bcrypt) => {
+            bcrypt::verify(password, hash).map_err(|e| PasswordError::Hash(e.to_string()))
+        }
+        None => Err(PasswordError::UnsupportedHash),
+    }
+}
+
+/// A hash of a random password in the configured algorithm, matching no account.
+static DUMMY_HASH: Lazy<String> = Lazy::new(|| {
+    let password = SaltString::generate(&mut OsRng);
+    HashAlgorithm::from_env()
+        .hash(password.as_str())
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to create the dummy password hash: {}", e);
+            String::new()
+        })
+});
+
+/// Verifies `password` against a hash that matches no account, so a login for
+/// an unknown email takes as long as one with a wrong password.
+pub fn verify_dummy_password(password: &str) {
+    let _ = verify_password(password, &DUMMY_HASH);
+}
+
+fn phc_hash(hasher: &impl PasswordHasher, password: &str) -> Result<String, PasswordError> {
+    let salt = SaltString::generate(&mut OsRng);
+    hasher
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| PasswordError::Hash(e.to_string()))
+}
+
+fn phc_verify(
+    verifier: &impl PasswordVerifier,
+    password: &str,
+    hash: &str,
+) -> Result<bool, PasswordError> {
+    let parsed = PasswordHash::new(hash).map_err(|e| PasswordError::Hash(e.to_string()))?;
+
+    match verifier.verify_password(password.as_bytes(), &parsed) {
+        Ok(()) => Ok(true),
+        Err(argon2::password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(PasswordError::Hash(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_algorithm_round_trips() {
+        for algorithm in [HashAlgorithm::Argon2, HashAlgorithm::Bcrypt, HashAlgorithm::Scrypt] {
+            let hash = algorithm.hash("Sup3r$ecret").expect("hashing should succeed");
+
+            assert_eq!(HashAlgorithm::of_hash(&hash), Some(algorithm));
+            assert!(verify_password("Sup3r$ecret", &hash).unwrap());
+            assert!(!verify_password("wrong", &hash).unwrap());
+            assert!(!algorithm.needs_rehash(&hash));
+        }
+    }
+
+    #[test]
+    fn argon2_hashes_are_argon2id_phc_strings() {
+        let hash = HashAlgorithm::Argon2.hash("Sup3r$ecret").unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$"));
+        assert!(PasswordHash::new(&hash).is_ok());
+    }
+
+    #[test]
+    fn legacy_hashes_are_flagged_for_rehash() {
+        let bcrypt_hash = bcrypt::hash("Sup3r$ecret", 4).unwrap();
+
+        assert!(HashAlgorithm::Argon2.needs_rehash(&bcrypt_hash));
+        assert!(!HashAlgorithm::Bcrypt.needs_rehash(&bcrypt_hash));
+        assert!(HashAlgorithm::Argon2.needs_rehash("$argon2i$v=19$m=16,t=2,p=1$c2FsdA$aGFzaA"));
+        assert!(matches!(
+            verify_password("Sup3r$ecret", "plaintext"),
+            Err(PasswordError::UnsupportedHash)
+        ));
+    }
+
+    #[test]
+    fn dummy_hash_matches_no_password() {
+        assert!(HashAlgorithm::of_hash(&DUMMY_HASH).is_some());
+        assert!(!verify_password("Sup3r$ecret", &DUMMY_HASH).unwrap());
+        verify_dummy_password("Sup3r$ecret");
+    }
+
+    #[test]
+    fn algorithm_names_match_the_setting() {
+        assert_eq!("argon2".parse::<HashAlgorithm>().unwrap(), HashAlgorithm::Argon2);
+        assert_eq!(" BCRYPT ".parse::<HashAlgorithm>().unwrap(), HashAlgorithm::Bcrypt);
+        assert_eq!(HashAlgorithm::Scrypt.to_string(), "scrypt");
+        assert!("md5".parse::<HashAlgorithm>().is_err());
+    }
+}
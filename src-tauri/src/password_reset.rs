@@ -0,0 +1,118 @@
+//! One-time tokens for resetting a forgotten password.
+//!
+//! A reset token is 32 random bytes, base64url-encoded. Only its SHA-256 hash
+//! is stored, so a leaked database cannot be used to reset passwords. Tokens
+//! reach the user through a [`ResetTokenSender`], by default an email sent
+//! through the configured [`crate::mail`] transport.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::mail::{mail_transport, MailMessage, MailTransport};
+
+/// Length of generated tokens before encoding, in bytes.
+const TOKEN_LEN: usize = 32;
+
+/// Lifetime of reset tokens when `AUTH_RESET_TOKEN_TTL_MINUTES` is unset.
+const DEFAULT_TOKEN_TTL_MINUTES: i64 = 30;
+
+/// Delivers reset tokens to the account holder.
+#[async_trait]
+pub trait ResetTokenSender: Send + Sync {
+    async fn send(&self, email: &str, token: &str, expires_at: DateTime<Utc>) -> Result<()>;
+}
+
+/// Emails reset tokens through a mail transport.
+pub struct MailResetSender {
+    transport: Arc<dyn MailTransport>,
+}
+
+impl MailResetSender {
+    pub fn new(transport: Arc<dyn MailTransport>) -> Self {
+        Self { transport }
+    }
+}
+
+#[async_trait]
+impl ResetTokenSender for MailResetSender {
+    async fn send(&self, email: &str, token: &str, expires_at: DateTime<Utc>) -> Result<()> {
+        self.transport
+            .send(&MailMessage {
+                to: email.to_string(),
+                subject: "Reset your password".to_string(),
+                body: format!(
+                    "Use this token to choose a new password:\n\n{}\n\nIt expires at {}. If you did not ask for a reset, you can ignore this email.",
+                    token,
+                    expires_at.to_rfc3339()
+                ),
+            })
+            .await
+    }
+}
+
+/// The sender used by reset commands, which emails tokens.
+pub fn reset_sender() -> MailResetSender {
+    MailResetSender::new(mail_transport())
+}
+
+/// How long new reset tokens stay valid, from `AUTH_RESET_TOKEN_TTL_MINUTES`.
+pub fn token_ttl() -> Duration {
+    let minutes = std::env::var("AUTH_RESET_TOKEN_TTL_MINUTES")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|minutes: &i64| *minutes > 0)
+        .unwrap_or(DEFAULT_TOKEN_TTL_MINUTES);
+    Duration::minutes(minutes)
+}
+
+/// Generates a new random token.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_LEN];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// The hex-encoded SHA-256 hash stored for `token`.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.trim().as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mail::FileMailTransport;
+
+    #[test]
+    fn tokens_are_unique_and_hash_stably() {
+        let token = generate_token();
+
+        assert_eq!(token.len(), 43);
+        assert_ne!(token, generate_token());
+        assert_eq!(hash_token(&token), hash_token(&format!(" {} ", token)));
+        assert_eq!(hash_token(&token).len(), 64);
+        assert_ne!(hash_token(&token), token);
+    }
+
+    #[tokio::test]
+    async fn mail_sender_emails_the_token() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("outbox.jsonl");
+        let sender = MailResetSender::new(Arc::new(FileMailTransport::new(&path)));
+        let expires_at = Utc::now() + Duration::minutes(30);
+
+        sender.send("user@example.com", "token-1", expires_at).await?;
+
+        let sent: serde_json::Value = serde_json::from_str(std::fs::read_to_string(&path)?.trim())?;
+        assert_eq!(sent["to"], "user@example.com");
+        assert_eq!(sent["subject"], "Reset your password");
+        assert!(sent["body"].as_str().unwrap().contains("token-1"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+//! Roles and the permissions commands can require.
+//!
+//! Each user has one role in `users.role`, and `role_permissions` lists what
+//! every role may do. [`default_permissions`] mirrors the rows seeded by the
+//! migrations; deployments can change the table without a code change.
+
+use std::fmt;
+
+/// Full access, including managing other accounts, purging logs and
+/// administering the database, logging configuration and vault.
+pub const ROLE_ADMIN: &str = "admin";
+/// Can view accounts and logs but not change them.
+pub const ROLE_OPERATOR: &str = "operator";
+/// Can only manage their own account. Assigned to new accounts.
+pub const ROLE_USER: &str = "user";
+
+/// Every role an account can be assigned.
+pub const ROLES: &[&str] = &[ROLE_ADMIN, ROLE_OPERATOR, ROLE_USER];
+
+/// An action a command can be restricted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    UsersRead,
+    UsersWrite,
+    UsersDelete,
+    LogsRead,
+    LogsPurge,
+    /// Database credentials and migrations, the logging configuration and
+    /// vault secrets.
+    SystemAdmin,
+}
+
+impl Permission {
+    /// Name stored in `role_permissions`, such as `users:delete`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Permission::UsersRead => "users:read",
+            Permission::UsersWrite => "users:write",
+            Permission::UsersDelete => "users:delete",
+            Permission::LogsRead => "logs:read",
+            Permission::LogsPurge => "logs:purge",
+            Permission::SystemAdmin => "system:admin",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Returns whether `role` is one of [`ROLES`].
+pub fn is_known_role(role: &str) -> bool {
+    ROLES.contains(&role)
+}
+
+/// Permissions granted to `role` by the seeded `role_permissions` rows.
+pub fn default_permissions(role: &str) -> &'static [Permission] {
+    match role {
+        ROLE_ADMIN => &[
+            Permission::UsersRead,
+            Permission::UsersWrite,
+            Permission::UsersDelete,
+            Permission::LogsRead,
+            Permission::LogsPurge,
+            Permission::SystemAdmin,
+        ],
+        ROLE_OPERATOR => &[Permission::UsersRead, Permission::LogsRead],
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operators_cannot_delete_or_purge() {
+        let operator = default_permissions(ROLE_OPERATOR);
+        assert!(operator.contains(&Permission::UsersRead));
+        assert!(!operator.contains(&Permission::UsersDelete));
+        assert!(!operator.contains(&Permission::LogsPurge));
+        assert!(!operator.contains(&Permission::SystemAdmin));
+
+        assert!(default_permissions(ROLE_ADMIN).contains(&Permission::LogsPurge));
+        assert!(default_permissions(ROLE_USER).is_empty());
+        assert!(default_permissions("unknown").is_empty());
+    }
+
+    #[test]
+    fn permission_names_match_the_seeded_rows() {
+        assert_eq!(Permission::UsersDelete.to_string(), "users:delete");
+        assert_eq!(Permission::LogsPurge.as_str(), "logs:purge");
+        assert_eq!(Permission::SystemAdmin.as_str(), "system:admin");
+        assert!(is_known_role("operator"));
+        assert!(!is_known_role("root"));
+    }
+}
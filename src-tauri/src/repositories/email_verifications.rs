@@ -0,0 +1,122 @@
+//! Email verification token storage.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::{get_pool_ref, with_pool, DbPool};
+use crate::models::EmailVerificationToken;
+
+const TOKEN_COLUMNS: &str = "id, user_id, token_hash, created_at, expires_at, used_at";
+
+/// Persistence operations for email verification tokens.
+#[async_trait]
+pub trait EmailVerificationRepository: Send + Sync {
+    /// Stores the hash of a newly issued token.
+    async fn create(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<EmailVerificationToken>;
+
+    /// Finds a token by hash, whether or not it is still usable.
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<EmailVerificationToken>>;
+
+    /// Marks a token used, returning whether it was still usable at `now`.
+    ///
+    /// Only one caller can succeed for a given token.
+    async fn consume(&self, id: Uuid, now: DateTime<Utc>) -> Result<bool>;
+
+    /// Marks every unused token of a user used, returning how many there were.
+    async fn invalidate_for_user(&self, user_id: Uuid, now: DateTime<Utc>) -> Result<u64>;
+}
+
+/// `EmailVerificationRepository` backed by the application database.
+#[derive(Clone)]
+pub struct SqlEmailVerificationRepository {
+    pool: Arc<DbPool>,
+}
+
+impl SqlEmailVerificationRepository {
+    pub fn new(pool: Arc<DbPool>) -> Self {
+        Self { pool }
+    }
+
+    /// Creates a repository over the global connection pool.
+    pub fn from_global() -> Result<Self> {
+        Ok(Self::new(get_pool_ref()?))
+    }
+}
+
+#[async_trait]
+impl EmailVerificationRepository for SqlEmailVerificationRepository {
+    async fn create(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<EmailVerificationToken> {
+        let sql = format!(
+            "INSERT INTO email_verification_tokens (id, user_id, token_hash, created_at, expires_at)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING {}",
+            TOKEN_COLUMNS
+        );
+
+        let token = with_pool!(self.pool.as_ref(), |pool| sqlx::query_as::<_, EmailVerificationToken>(&sql)
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(token_hash)
+            .bind(Utc::now())
+            .bind(expires_at)
+            .fetch_one(pool)
+            .await)?;
+
+        Ok(token)
+    }
+
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<EmailVerificationToken>> {
+        let sql = format!(
+            "SELECT {} FROM email_verification_tokens WHERE token_hash = $1",
+            TOKEN_COLUMNS
+        );
+
+        let token = with_pool!(self.pool.as_ref(), |pool| sqlx::query_as::<_, EmailVerificationToken>(&sql)
+            .bind(token_hash)
+            .fetch_optional(pool)
+            .await)?;
+
+        Ok(token)
+    }
+
+    async fn consume(&self, id: Uuid, now: DateTime<Utc>) -> Result<bool> {
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "UPDATE email_verification_tokens SET used_at = $2
+             WHERE id = $1 AND used_at IS NULL AND expires_at > $2"
+        )
+        .bind(id)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()))?;
+
+        Ok(rows_affected > 0)
+    }
+
+    async fn invalidate_for_user(&self, user_id: Uuid, now: DateTime<Utc>) -> Result<u64> {
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "UPDATE email_verification_tokens SET used_at = $2
+             WHERE user_id = $1 AND used_at IS NULL"
+        )
+        .bind(user_id)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()))?;
+
+        Ok(rows_affected)
+    }
+}
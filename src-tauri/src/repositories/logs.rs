@@ -0,0 +1,156 @@
+//! Application log storage.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::QueryBuilder;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::{get_pool_ref, with_pool, DbPool};
+use crate::models::AppLog;
+
+/// Validated fields for inserting a log entry.
+#[derive(Debug, Clone)]
+pub struct NewLog {
+    pub level: String,
+    pub message: String,
+    pub metadata: serde_json::Value,
+    pub user_id: Option<Uuid>,
+}
+
+/// Normalized filter for querying log entries.
+#[derive(Debug, Clone)]
+pub struct LogFilter {
+    pub level: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Persistence operations for database-backed application logs.
+#[async_trait]
+pub trait LogRepository: Send + Sync {
+    /// Inserts a log entry and returns the stored row.
+    async fn create(&self, log: NewLog) -> Result<AppLog>;
+
+    /// Returns log entries matching the filter, newest first.
+    async fn query(&self, filter: LogFilter) -> Result<Vec<AppLog>>;
+
+    /// Deletes entries created before `cutoff`, returning how many were removed.
+    async fn delete_older_than(&self, cutoff: DateTime<Utc>) -> Result<u64>;
+}
+
+/// `LogRepository` backed by the application database.
+#[derive(Clone)]
+pub struct SqlLogRepository {
+    pool: Arc<DbPool>,
+}
+
+impl SqlLogRepository {
+    pub fn new(pool: Arc<DbPool>) -> Self {
+        Self { pool }
+    }
+
+    /// Creates a repository over the global connection pool.
+    pub fn from_global() -> Result<Self> {
+        Ok(Self::new(get_pool_ref()?))
+    }
+}
+
+#[async_trait]
+impl LogRepository for SqlLogRepository {
+    async fn create(&self, log: NewLog) -> Result<AppLog> {
+        let NewLog {
+            level,
+            message,
+            metadata,
+            user_id,
+        } = log;
+
+        let log = with_pool!(self.pool.as_ref(), |pool| sqlx::query_as::<_, AppLog>(
+            r#"
+            INSERT INTO app_logs (id, level, message, metadata, user_id, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id,
+                      level,
+                      message,
+                      metadata,
+                      user_id,
+                      created_at
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(&level)
+        .bind(&message)
+        .bind(&metadata)
+        .bind(user_id)
+        .bind(Utc::now())
+        .fetch_one(pool)
+        .await)?;
+
+        Ok(log)
+    }
+
+    async fn query(&self, filter: LogFilter) -> Result<Vec<AppLog>> {
+        let LogFilter {
+            level,
+            user_id,
+            limit,
+            offset,
+        } = filter;
+
+        let logs = with_pool!(self.pool.as_ref(), |pool| {
+            let mut builder = QueryBuilder::new(
+                "SELECT id,
+                        level,
+                        message,
+                        metadata,
+                        user_id,
+                        created_at
+                 FROM app_logs",
+            );
+
+            let mut has_condition = false;
+
+            if let Some(level) = &level {
+                builder.push(" WHERE level = ");
+                builder.push_bind(level.clone());
+                has_condition = true;
+            }
+
+            if let Some(user_id) = user_id {
+                builder.push(if has_condition {
+                    " AND user_id = "
+                } else {
+                    " WHERE user_id = "
+                });
+                builder.push_bind(user_id);
+            }
+
+            builder.push(" ORDER BY created_at DESC LIMIT ");
+            builder.push_bind(limit);
+            builder.push(" OFFSET ");
+            builder.push_bind(offset);
+
+            builder
+                .build_query_as::<AppLog>()
+                .fetch_all(pool)
+                .await
+        })?;
+
+        Ok(logs)
+    }
+
+    async fn delete_older_than(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "DELETE FROM app_logs WHERE created_at < $1"
+        )
+        .bind(cutoff)
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()))?;
+
+        Ok(rows_affected)
+    }
+}
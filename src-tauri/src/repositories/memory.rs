@@ -0,0 +1,681 @@
+//! In-memory repository implementations for unit tests.
+//!
+//! They mirror the constraints the database enforces (unique emails and
+//! usernames, one settings row per user) closely enough for command logic tests.
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use super::{
+    EmailVerificationRepository, LogFilter, LogRepository, NewLog, NewUser, PasswordResetRepository,
+    SettingsChange, SettingsRepository, SettingsUpdate, UserChanges, UserFilter, UserRepository,
+};
+use crate::models::{
+    AppLog, EmailVerificationToken, PasswordResetToken, SortOrder, User, UserSettings, UserSortBy,
+};
+use crate::permissions::default_permissions;
+
+/// `UserRepository` kept in memory.
+#[derive(Clone, Default)]
+pub struct InMemoryUserRepository {
+    users: Arc<RwLock<HashMap<Uuid, User>>>,
+    /// Recovery code hashes per user, with whether each has been used.
+    recovery_codes: Arc<RwLock<HashMap<Uuid, Vec<(String, bool)>>>>,
+    /// When each user's token sessions were last revoked; the sessions
+    /// themselves are not kept.
+    sessions_revoked_at: Arc<RwLock<HashMap<Uuid, DateTime<Utc>>>>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// When the token sessions of `id` were last revoked, if ever.
+    pub async fn sessions_revoked_at(&self, id: Uuid) -> Option<DateTime<Utc>> {
+        self.sessions_revoked_at.read().await.get(&id).copied()
+    }
+
+    async fn matching(&self, filter: &UserFilter) -> Vec<User> {
+        let matches_search = |user: &User, search: &str| {
+            let name = format!(
+                "{} {}",
+                user.first_name.as_deref().unwrap_or(""),
+                user.last_name.as_deref().unwrap_or("")
+            );
+            [user.email.as_str(), user.username.as_str(), name.as_str()]
+                .iter()
+                .any(|field| field.to_lowercase().contains(search))
+        };
+
+        self.users
+            .read()
+            .await
+            .values()
+            .filter(|user| filter.include_deleted || user.deleted_at.is_none())
+            .filter(|user| filter.is_active.map_or(true, |active| user.is_active == active))
+            .filter(|user| filter.search.as_deref().map_or(true, |search| matches_search(user, search)))
+            .filter(|user| filter.created_after.map_or(true, |after| user.created_at >= after))
+            .filter(|user| filter.created_before.map_or(true, |before| user.created_at < before))
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn list(&self, include_deleted: bool) -> Result<Vec<User>> {
+        let mut users: Vec<User> = self
+            .users
+            .read()
+            .await
+            .values()
+            .filter(|user| include_deleted || user.deleted_at.is_none())
+            .cloned()
+            .collect();
+        users.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(users)
+    }
+
+    async fn query(&self, filter: &UserFilter) -> Result<Vec<User>> {
+        let mut users = self.matching(filter).await;
+        users.sort_by(|a, b| {
+            let ordering = match filter.sort_by {
+                UserSortBy::CreatedAt => a.created_at.cmp(&b.created_at),
+                UserSortBy::Email => a.email.cmp(&b.email),
+                UserSortBy::Username => a.username.cmp(&b.username),
+            }
+            .then_with(|| a.id.cmp(&b.id));
+            match filter.sort_order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            }
+        });
+
+        Ok(users
+            .into_iter()
+            .skip(filter.offset as usize)
+            .take(filter.limit as usize)
+            .collect())
+    }
+
+    async fn count_matching(&self, filter: &UserFilter) -> Result<i64> {
+        Ok(self.matching(filter).await.len() as i64)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>> {
+        Ok(self.users.read().await.get(&id).cloned())
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>> {
+        Ok(self
+            .users
+            .read()
+            .await
+            .values()
+            .find(|user| user.email == email)
+            .cloned())
+    }
+
+    async fn create(&self, mut user: NewUser, first_role: &str) -> Result<User> {
+        let mut users = self.users.write().await;
+
+        if users.is_empty() {
+            user.role = first_role.to_string();
+        }
+        if users.values().any(|existing| existing.email == user.email) {
+            bail!("duplicate key value violates unique constraint \"users_email_key\"");
+        }
+        if users.values().any(|existing| existing.username == user.username) {
+            bail!("duplicate key value violates unique constraint \"users_username_key\"");
+        }
+
+        let now = Utc::now();
+        let user = User {
+            id: Uuid::new_v4(),
+            email: user.email,
+            username: user.username,
+            password_hash: user.password_hash,
+            first_name: user.first_name,
+            last_name: user.last_name,
+            is_active: true,
+            created_at: now,
+            updated_at: now,
+            role: user.role,
+            failed_login_attempts: 0,
+            locked_until: None,
+            totp_enabled: false,
+            totp_last_step: None,
+            email_verified: false,
+            deleted_at: None,
+        };
+        users.insert(user.id, user.clone());
+
+        Ok(user)
+    }
+
+    async fn update(&self, id: Uuid, changes: UserChanges) -> Result<Option<User>> {
+        let mut users = self.users.write().await;
+
+        if let Some(email) = &changes.email {
+            if users.values().any(|other| other.id != id && &other.email == email) {
+                bail!("duplicate key value violates unique constraint \"users_email_key\"");
+            }
+        }
+        if let Some(username) = &changes.username {
+            if users.values().any(|other| other.id != id && &other.username == username) {
+                bail!("duplicate key value violates unique constraint \"users_username_key\"");
+            }
+        }
+
+        let Some(user) = users.get_mut(&id).filter(|user| user.deleted_at.is_none()) else {
+            return Ok(None);
+        };
+
+        if let Some(email) = changes.email {
+            if email != user.email {
+                user.email_verified = false;
+            }
+            user.email = email;
+        }
+        if let Some(username) = changes.username {
+            user.username = username;
+        }
+        if let Some(first_name) = changes.first_name {
+            user.first_name = Some(first_name);
+        }
+        if let Some(last_name) = changes.last_name {
+            user.last_name = Some(last_name);
+        }
+        user.updated_at = Utc::now();
+
+        Ok(Some(user.clone()))
+    }
+
+    async fn set_active(&self, id: Uuid, active: bool, now: DateTime<Utc>) -> Result<Option<User>> {
+        let mut users = self.users.write().await;
+        let Some(user) = users.get_mut(&id).filter(|user| user.deleted_at.is_none()) else {
+            return Ok(None);
+        };
+
+        user.is_active = active;
+        user.updated_at = now;
+        if !active {
+            self.sessions_revoked_at.write().await.insert(id, now);
+        }
+        Ok(Some(user.clone()))
+    }
+
+    async fn soft_delete(&self, id: Uuid, now: DateTime<Utc>) -> Result<bool> {
+        let mut users = self.users.write().await;
+        match users.get_mut(&id) {
+            Some(user) if user.deleted_at.is_none() => {
+                user.deleted_at = Some(now);
+                user.updated_at = now;
+                self.sessions_revoked_at.write().await.insert(id, now);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Option<User>> {
+        let mut users = self.users.write().await;
+        match users.get_mut(&id) {
+            Some(user) if user.deleted_at.is_some() => {
+                user.deleted_at = None;
+                user.updated_at = Utc::now();
+                Ok(Some(user.clone()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<u64> {
+        let mut users = self.users.write().await;
+        let purged: Vec<Uuid> = users
+            .values()
+            .filter(|user| user.deleted_at.is_some_and(|deleted_at| deleted_at < before))
+            .map(|user| user.id)
+            .collect();
+        for id in &purged {
+            users.remove(id);
+        }
+        self.recovery_codes
+            .write()
+            .await
+            .retain(|id, _| !purged.contains(id));
+
+        Ok(purged.len() as u64)
+    }
+
+    async fn set_role(&self, id: Uuid, role: &str) -> Result<Option<User>> {
+        let mut users = self.users.write().await;
+        let Some(user) = users.get_mut(&id) else {
+            return Ok(None);
+        };
+
+        user.role = role.to_string();
+        user.updated_at = Utc::now();
+        Ok(Some(user.clone()))
+    }
+
+    async fn permissions_for_role(&self, role: &str) -> Result<Vec<String>> {
+        let mut permissions: Vec<String> = default_permissions(role)
+            .iter()
+            .map(|permission| permission.as_str().to_string())
+            .collect();
+        permissions.sort();
+        Ok(permissions)
+    }
+
+    async fn set_password_hash(&self, id: Uuid, password_hash: &str) -> Result<bool> {
+        let mut users = self.users.write().await;
+        let Some(user) = users.get_mut(&id) else {
+            return Ok(false);
+        };
+
+        user.password_hash = password_hash.to_string();
+        user.updated_at = Utc::now();
+        Ok(true)
+    }
+
+    async fn record_failed_login(&self, id: Uuid) -> Result<i32> {
+        let mut users = self.users.write().await;
+        let Some(user) = users.get_mut(&id) else {
+            bail!("no rows returned by a query that expected to return at least one row");
+        };
+
+        user.failed_login_attempts += 1;
+        Ok(user.failed_login_attempts)
+    }
+
+    async fn lock_account(&self, id: Uuid, until: DateTime<Utc>) -> Result<()> {
+        if let Some(user) = self.users.write().await.get_mut(&id) {
+            user.locked_until = Some(until);
+        }
+        Ok(())
+    }
+
+    async fn clear_failed_logins(&self, id: Uuid) -> Result<()> {
+        if let Some(user) = self.users.write().await.get_mut(&id) {
+            user.failed_login_attempts = 0;
+            user.locked_until = None;
+        }
+        Ok(())
+    }
+
+    async fn enable_totp(&self, id: Uuid, recovery_code_hashes: &[String]) -> Result<bool> {
+        self.replace_recovery_codes(id, recovery_code_hashes).await?;
+
+        let mut users = self.users.write().await;
+        let Some(user) = users.get_mut(&id) else {
+            return Ok(false);
+        };
+
+        user.totp_enabled = true;
+        user.updated_at = Utc::now();
+        Ok(true)
+    }
+
+    async fn disable_totp(&self, id: Uuid) -> Result<bool> {
+        self.recovery_codes.write().await.remove(&id);
+
+        let mut users = self.users.write().await;
+        let Some(user) = users.get_mut(&id) else {
+            return Ok(false);
+        };
+
+        user.totp_enabled = false;
+        user.totp_last_step = None;
+        user.updated_at = Utc::now();
+        Ok(true)
+    }
+
+    async fn replace_recovery_codes(&self, id: Uuid, recovery_code_hashes: &[String]) -> Result<()> {
+        let codes = recovery_code_hashes.iter().map(|hash| (hash.clone(), false)).collect();
+        self.recovery_codes.write().await.insert(id, codes);
+        Ok(())
+    }
+
+    async fn consume_recovery_code(&self, id: Uuid, code_hash: &str, _now: DateTime<Utc>) -> Result<bool> {
+        let mut recovery_codes = self.recovery_codes.write().await;
+        let unused = recovery_codes
+            .get_mut(&id)
+            .and_then(|codes| codes.iter_mut().find(|(hash, used)| hash == code_hash && !*used));
+
+        match unused {
+            Some((_, used)) => {
+                *used = true;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn record_totp_step(&self, id: Uuid, step: i64) -> Result<bool> {
+        let mut users = self.users.write().await;
+        match users.get_mut(&id) {
+            Some(user) if user.totp_last_step.map_or(true, |last| last < step) => {
+                user.totp_last_step = Some(step);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn mark_email_verified(&self, id: Uuid) -> Result<bool> {
+        let mut users = self.users.write().await;
+        let Some(user) = users.get_mut(&id) else {
+            return Ok(false);
+        };
+
+        user.email_verified = true;
+        user.updated_at = Utc::now();
+        Ok(true)
+    }
+
+    async fn revoke_auth_sessions(&self, id: Uuid, now: DateTime<Utc>) -> Result<u64> {
+        self.sessions_revoked_at.write().await.insert(id, now);
+        Ok(0)
+    }
+}
+
+/// `LogRepository` kept in memory.
+#[derive(Clone, Default)]
+pub struct InMemoryLogRepository {
+    logs: Arc<RwLock<Vec<AppLog>>>,
+}
+
+impl InMemoryLogRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl LogRepository for InMemoryLogRepository {
+    async fn create(&self, log: NewLog) -> Result<AppLog> {
+        let log = AppLog {
+            id: Uuid::new_v4(),
+            level: log.level,
+            message: log.message,
+            metadata: log.metadata,
+            user_id: log.user_id,
+            created_at: Utc::now(),
+        };
+        self.logs.write().await.push(log.clone());
+
+        Ok(log)
+    }
+
+    async fn query(&self, filter: LogFilter) -> Result<Vec<AppLog>> {
+        let mut logs: Vec<AppLog> = self
+            .logs
+            .read()
+            .await
+            .iter()
+            .filter(|log| filter.level.as_ref().map_or(true, |level| &log.level == level))
+            .filter(|log| filter.user_id.map_or(true, |user_id| log.user_id == Some(user_id)))
+            .cloned()
+            .collect();
+        logs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+        Ok(logs
+            .into_iter()
+            .skip(filter.offset.max(0) as usize)
+            .take(filter.limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn delete_older_than(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let mut logs = self.logs.write().await;
+        let before = logs.len();
+        logs.retain(|log| log.created_at >= cutoff);
+
+        Ok((before - logs.len()) as u64)
+    }
+}
+
+/// `PasswordResetRepository` kept in memory.
+#[derive(Clone, Default)]
+pub struct InMemoryPasswordResetRepository {
+    tokens: Arc<RwLock<HashMap<Uuid, PasswordResetToken>>>,
+}
+
+impl InMemoryPasswordResetRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepository for InMemoryPasswordResetRepository {
+    async fn create(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<PasswordResetToken> {
+        let mut tokens = self.tokens.write().await;
+        if tokens.values().any(|token| token.token_hash == token_hash) {
+            bail!("duplicate token hash");
+        }
+
+        let token = PasswordResetToken {
+            id: Uuid::new_v4(),
+            user_id,
+            token_hash: token_hash.to_string(),
+            created_at: Utc::now(),
+            expires_at,
+            used_at: None,
+        };
+        tokens.insert(token.id, token.clone());
+
+        Ok(token)
+    }
+
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<PasswordResetToken>> {
+        Ok(self
+            .tokens
+            .read()
+            .await
+            .values()
+            .find(|token| token.token_hash == token_hash)
+            .cloned())
+    }
+
+    async fn consume(&self, id: Uuid, now: DateTime<Utc>) -> Result<bool> {
+        let mut tokens = self.tokens.write().await;
+        match tokens.get_mut(&id) {
+            Some(token) if token.is_usable(now) => {
+                token.used_at = Some(now);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn invalidate_for_user(&self, user_id: Uuid, now: DateTime<Utc>) -> Result<u64> {
+        let mut invalidated = 0;
+        for token in self.tokens.write().await.values_mut() {
+            if token.user_id == user_id && token.used_at.is_none() {
+                token.used_at = Some(now);
+                invalidated += 1;
+            }
+        }
+
+        Ok(invalidated)
+    }
+}
+
+/// `EmailVerificationRepository` kept in memory.
+#[derive(Clone, Default)]
+pub struct InMemoryEmailVerificationRepository {
+    tokens: Arc<RwLock<HashMap<Uuid, EmailVerificationToken>>>,
+}
+
+impl InMemoryEmailVerificationRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl EmailVerificationRepository for InMemoryEmailVerificationRepository {
+    async fn create(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<EmailVerificationToken> {
+        let mut tokens = self.tokens.write().await;
+        if tokens.values().any(|token| token.token_hash == token_hash) {
+            bail!("duplicate token hash");
+        }
+
+        let token = EmailVerificationToken {
+            id: Uuid::new_v4(),
+            user_id,
+            token_hash: token_hash.to_string(),
+            created_at: Utc::now(),
+            expires_at,
+            used_at: None,
+        };
+        tokens.insert(token.id, token.clone());
+
+        Ok(token)
+    }
+
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<EmailVerificationToken>> {
+        Ok(self
+            .tokens
+            .read()
+            .await
+            .values()
+            .find(|token| token.token_hash == token_hash)
+            .cloned())
+    }
+
+    async fn consume(&self, id: Uuid, now: DateTime<Utc>) -> Result<bool> {
+        let mut tokens = self.tokens.write().await;
+        match tokens.get_mut(&id) {
+            Some(token) if token.is_usable(now) => {
+                token.used_at = Some(now);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn invalidate_for_user(&self, user_id: Uuid, now: DateTime<Utc>) -> Result<u64> {
+        let mut invalidated = 0;
+        for token in self.tokens.write().await.values_mut() {
+            if token.user_id == user_id && token.used_at.is_none() {
+                token.used_at = Some(now);
+                invalidated += 1;
+            }
+        }
+
+        Ok(invalidated)
+    }
+}
+
+/// `SettingsRepository` kept in memory.
+#[derive(Clone, Default)]
+pub struct InMemorySettingsRepository {
+    settings: Arc<RwLock<HashMap<Uuid, UserSettings>>>,
+}
+
+impl InMemorySettingsRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SettingsRepository for InMemorySettingsRepository {
+    async fn find_by_user(&self, user_id: Uuid) -> Result<Option<UserSettings>> {
+        Ok(self.settings.read().await.get(&user_id).cloned())
+    }
+
+    async fn save(&self, settings: UserSettings) -> Result<UserSettings> {
+        let mut rows = self.settings.write().await;
+        let mut saved = settings;
+
+        // Mirror ON CONFLICT: the original row keeps its id and creation time.
+        if let Some(existing) = rows.get(&saved.user_id) {
+            saved.id = existing.id;
+            saved.created_at = existing.created_at;
+        }
+        saved.updated_at = Utc::now();
+        rows.insert(saved.user_id, saved.clone());
+
+        Ok(saved)
+    }
+
+    async fn update(&self, user_id: Uuid, change: SettingsChange<'_>) -> Result<SettingsUpdate> {
+        let mut rows = self.settings.write().await;
+        let previous = rows.get(&user_id).cloned();
+
+        let mut saved = match change(previous.as_ref()) {
+            Ok(settings) => settings,
+            Err(message) => return Ok(Err(message)),
+        };
+        saved.user_id = user_id;
+        if let Some(existing) = &previous {
+            saved.id = existing.id;
+            saved.created_at = existing.created_at;
+        }
+        saved.updated_at = Utc::now();
+        rows.insert(user_id, saved.clone());
+
+        Ok(Ok((previous, saved)))
+    }
+
+    async fn delete_by_user(&self, user_id: Uuid) -> Result<bool> {
+        Ok(self.settings.write().await.remove(&user_id).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_settings(user_id: Uuid, theme: &str) -> UserSettings {
+        let now = Utc::now();
+        UserSettings {
+            id: Uuid::new_v4(),
+            user_id,
+            theme: theme.to_string(),
+            language: "en".to_string(),
+            notifications_enabled: true,
+            settings_data: json!({}),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn settings_save_upserts_per_user() -> Result<()> {
+        let repo = InMemorySettingsRepository::new();
+        let user_id = Uuid::new_v4();
+
+        let first = repo.save(sample_settings(user_id, "light")).await?;
+        let second = repo.save(sample_settings(user_id, "dark")).await?;
+
+        assert_eq!(second.id, first.id);
+        assert_eq!(second.created_at, first.created_at);
+        assert_eq!(repo.find_by_user(user_id).await?.map(|s| s.theme), Some("dark".to_string()));
+
+        assert!(repo.delete_by_user(user_id).await?);
+        assert!(repo.find_by_user(user_id).await?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+//! Repository traits separating command logic from storage.
+//!
+//! Handlers talk to `UserRepository`, `LogRepository`, `SettingsRepository`,
+//! `PasswordResetRepository` and `EmailVerificationRepository` instead of
+//! issuing queries directly. The SQL implementations work against whichever
+//! backend the global pool is connected to; the in-memory implementations let
+//! command logic be unit-tested without a database.
+
+pub mod email_verifications;
+pub mod logs;
+#[cfg(test)]
+pub mod memory;
+pub mod password_resets;
+pub mod settings;
+pub mod users;
+
+pub use email_verifications::*;
+pub use logs::*;
+pub use password_resets::*;
+pub use settings::*;
+pub use users::*;
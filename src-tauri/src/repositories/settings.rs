@@ -0,0 +1,195 @@
+//! User settings storage.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::{get_pool_ref, with_pool, DatabaseBackend, DbPool};
+use crate::models::UserSettings;
+
+/// Columns selected for every `UserSettings` query.
+const SETTINGS_COLUMNS: &str = "id,
+                   user_id,
+                   theme,
+                   language,
+                   notifications_enabled,
+                   settings_data,
+                   created_at,
+                   updated_at";
+
+/// Change applied by [`SettingsRepository::update`]. It gets the stored row,
+/// if any, and returns the row to save, or a message rejecting the change.
+pub type SettingsChange<'a> = Box<dyn FnOnce(Option<&UserSettings>) -> Result<UserSettings, String> + Send + 'a>;
+
+/// Result of [`SettingsRepository::update`]: the stored row before and after
+/// the change, or the message `change` rejected it with.
+pub type SettingsUpdate = Result<(Option<UserSettings>, UserSettings), String>;
+
+/// Persistence operations for per-user settings rows.
+#[async_trait]
+pub trait SettingsRepository: Send + Sync {
+    /// Returns the settings row for a user, if one exists.
+    async fn find_by_user(&self, user_id: Uuid) -> Result<Option<UserSettings>>;
+
+    /// Inserts or replaces the settings row for `settings.user_id`.
+    async fn save(&self, settings: UserSettings) -> Result<UserSettings>;
+
+    /// Applies `change` to the row of `user_id` and saves the result in one
+    /// transaction. The row stays locked meanwhile, so concurrent updates are
+    /// applied one after the other instead of overwriting each other.
+    async fn update(&self, user_id: Uuid, change: SettingsChange<'_>) -> Result<SettingsUpdate>;
+
+    /// Deletes the settings row for a user, returning whether one existed.
+    async fn delete_by_user(&self, user_id: Uuid) -> Result<bool>;
+}
+
+/// `SettingsRepository` backed by the application database.
+#[derive(Clone)]
+pub struct SqlSettingsRepository {
+    pool: Arc<DbPool>,
+}
+
+impl SqlSettingsRepository {
+    pub fn new(pool: Arc<DbPool>) -> Self {
+        Self { pool }
+    }
+
+    /// Creates a repository over the global connection pool.
+    pub fn from_global() -> Result<Self> {
+        Ok(Self::new(get_pool_ref()?))
+    }
+}
+
+#[async_trait]
+impl SettingsRepository for SqlSettingsRepository {
+    async fn find_by_user(&self, user_id: Uuid) -> Result<Option<UserSettings>> {
+        let settings = with_pool!(self.pool.as_ref(), |pool| sqlx::query_as::<_, UserSettings>(
+            r#"
+            SELECT id,
+                   user_id,
+                   theme,
+                   language,
+                   notifications_enabled,
+                   settings_data,
+                   created_at,
+                   updated_at
+            FROM user_settings
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await)?;
+
+        Ok(settings)
+    }
+
+    async fn save(&self, settings: UserSettings) -> Result<UserSettings> {
+        let saved = with_pool!(self.pool.as_ref(), |pool| sqlx::query_as::<_, UserSettings>(
+            r#"
+            INSERT INTO user_settings
+                (id, user_id, theme, language, notifications_enabled, settings_data, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (user_id) DO UPDATE
+            SET theme = excluded.theme,
+                language = excluded.language,
+                notifications_enabled = excluded.notifications_enabled,
+                settings_data = excluded.settings_data,
+                updated_at = excluded.updated_at
+            RETURNING id,
+                      user_id,
+                      theme,
+                      language,
+                      notifications_enabled,
+                      settings_data,
+                      created_at,
+                      updated_at
+            "#,
+        )
+        .bind(settings.id)
+        .bind(settings.user_id)
+        .bind(&settings.theme)
+        .bind(&settings.language)
+        .bind(settings.notifications_enabled)
+        .bind(&settings.settings_data)
+        .bind(settings.created_at)
+        .bind(Utc::now())
+        .fetch_one(pool)
+        .await)?;
+
+        Ok(saved)
+    }
+
+    async fn update(&self, user_id: Uuid, change: SettingsChange<'_>) -> Result<SettingsUpdate> {
+        // SQLite takes its write lock with the insert below; PostgreSQL
+        // needs the row locked explicitly.
+        let lock = if self.pool.backend() == DatabaseBackend::Postgres { " FOR UPDATE" } else { "" };
+        let select = format!("SELECT {} FROM user_settings WHERE user_id = $1{}", SETTINGS_COLUMNS, lock);
+        let update = format!(
+            "UPDATE user_settings
+             SET theme = $2, language = $3, notifications_enabled = $4, settings_data = $5, updated_at = $6
+             WHERE user_id = $1
+             RETURNING {}",
+            SETTINGS_COLUMNS
+        );
+        let now = Utc::now();
+
+        let result = with_pool!(self.pool.as_ref(), |pool| async {
+            let mut tx = pool.begin().await?;
+
+            // A placeholder row gives users without settings a row to lock.
+            // It is rolled back if `change` rejects the update.
+            let inserted = sqlx::query(
+                "INSERT INTO user_settings (id, user_id, created_at, updated_at)
+                 VALUES ($1, $2, $3, $3)
+                 ON CONFLICT (user_id) DO NOTHING",
+            )
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+                > 0;
+            let stored = sqlx::query_as::<_, UserSettings>(&select)
+                .bind(user_id)
+                .fetch_one(&mut *tx)
+                .await?;
+            let previous = (!inserted).then_some(stored);
+
+            let settings = match change(previous.as_ref()) {
+                Ok(settings) => settings,
+                Err(message) => return Ok(Err(message)),
+            };
+            let saved = sqlx::query_as::<_, UserSettings>(&update)
+                .bind(user_id)
+                .bind(&settings.theme)
+                .bind(&settings.language)
+                .bind(settings.notifications_enabled)
+                .bind(&settings.settings_data)
+                .bind(now)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(Ok((previous, saved)))
+        }
+        .await)?;
+
+        Ok(result)
+    }
+
+    async fn delete_by_user(&self, user_id: Uuid) -> Result<bool> {
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "DELETE FROM user_settings WHERE user_id = $1"
+        )
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()))?;
+
+        Ok(rows_affected > 0)
+    }
+}
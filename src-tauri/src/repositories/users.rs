@@ -0,0 +1,669 @@
+//! User storage.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::database::{get_pool_ref, with_pool, DatabaseBackend, DbPool};
+use crate::models::{SortOrder, User, UserSortBy};
+
+/// Columns selected for every `User` query.
+const USER_COLUMNS: &str = "id,
+               email,
+               username,
+               password_hash,
+               first_name,
+               last_name,
+               is_active,
+               created_at,
+               updated_at,
+               role,
+               failed_login_attempts,
+               locked_until,
+               totp_enabled,
+               totp_last_step,
+               email_verified,
+               deleted_at";
+
+/// Advisory lock key that serializes inserts deciding the first user's role.
+const FIRST_USER_LOCK_KEY: i64 = 4_815_162_343;
+
+/// Revokes the unrevoked token sessions of user `$1` at `$2`.
+const REVOKE_AUTH_SESSIONS: &str = "UPDATE auth_sessions SET revoked_at = $2 WHERE user_id = $1 AND revoked_at IS NULL";
+
+/// Validated fields for inserting a new user.
+#[derive(Debug, Clone)]
+pub struct NewUser {
+    pub email: String,
+    pub username: String,
+    pub password_hash: String,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub role: String,
+}
+
+/// Validated fields to change on an existing user; `None` leaves a field as is.
+#[derive(Debug, Clone, Default)]
+pub struct UserChanges {
+    pub email: Option<String>,
+    pub username: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+}
+
+/// Normalized filter for listing users a page at a time.
+#[derive(Debug, Clone, Default)]
+pub struct UserFilter {
+    /// Lowercase text matched anywhere in the email, username or name.
+    pub search: Option<String>,
+    pub is_active: Option<bool>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub include_deleted: bool,
+    pub sort_by: UserSortBy,
+    pub sort_order: SortOrder,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Conditions shared by `query` and `count_matching`; `$1` to `$5` are bound
+/// by [`bind_filter`].
+const FILTER_CONDITIONS: &str = "($1 OR deleted_at IS NULL)
+               AND ($2 IS NULL OR is_active = $2)
+               AND ($3 IS NULL
+                    OR LOWER(email) LIKE $3 ESCAPE '\\'
+                    OR LOWER(username) LIKE $3 ESCAPE '\\'
+                    OR LOWER(COALESCE(first_name, '') || ' ' || COALESCE(last_name, '')) LIKE $3 ESCAPE '\\')
+               AND ($4 IS NULL OR created_at >= $4)
+               AND ($5 IS NULL OR created_at < $5)";
+
+/// Binds the parameters of [`FILTER_CONDITIONS`] to `$query`.
+macro_rules! bind_filter {
+    ($query:expr, $filter:expr) => {
+        $query
+            .bind($filter.include_deleted)
+            .bind($filter.is_active)
+            .bind(like_pattern($filter.search.as_deref()))
+            .bind($filter.created_after)
+            .bind($filter.created_before)
+    };
+}
+
+/// `LIKE` pattern matching `search` anywhere, with wildcards in it escaped.
+fn like_pattern(search: Option<&str>) -> Option<String> {
+    search.map(|search| {
+        let escaped = search
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        format!("%{}%", escaped)
+    })
+}
+
+/// `ORDER BY` clause for a filter. Ties are broken by id so pages are stable.
+fn order_clause(filter: &UserFilter) -> String {
+    let column = match filter.sort_by {
+        UserSortBy::CreatedAt => "created_at",
+        UserSortBy::Email => "email",
+        UserSortBy::Username => "username",
+    };
+    let direction = match filter.sort_order {
+        SortOrder::Asc => "ASC",
+        SortOrder::Desc => "DESC",
+    };
+    format!("{column} {direction}, id {direction}")
+}
+
+/// Persistence operations for user accounts.
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    /// Returns all users, newest first. Soft-deleted users are left out
+    /// unless `include_deleted` is set.
+    async fn list(&self, include_deleted: bool) -> Result<Vec<User>>;
+
+    /// Returns one page of the users matching `filter`.
+    async fn query(&self, filter: &UserFilter) -> Result<Vec<User>>;
+
+    /// Returns how many users match `filter`, ignoring its limit and offset.
+    async fn count_matching(&self, filter: &UserFilter) -> Result<i64>;
+
+    /// Finds a user by id.
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>>;
+
+    /// Finds a user by (normalized) email address.
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>>;
+
+    /// Inserts a new user and returns the stored row. The user gets
+    /// `first_role` instead of `user.role` if there are no users yet;
+    /// concurrent calls cannot both get it.
+    async fn create(&self, user: NewUser, first_role: &str) -> Result<User>;
+
+    /// Applies changes to a user, returning `None` if it does not exist or
+    /// has been deleted.
+    ///
+    /// Changing the email address marks it unverified.
+    async fn update(&self, id: Uuid, changes: UserChanges) -> Result<Option<User>>;
+
+    /// Activates or deactivates a user, returning `None` if it does not exist
+    /// or has been deleted. Deactivating also revokes the user's token
+    /// sessions in the same transaction.
+    async fn set_active(&self, id: Uuid, active: bool, now: DateTime<Utc>) -> Result<Option<User>>;
+
+    /// Marks a user deleted at `now` and revokes their token sessions in the
+    /// same transaction, returning `false` if it does not exist or is already
+    /// deleted.
+    async fn soft_delete(&self, id: Uuid, now: DateTime<Utc>) -> Result<bool>;
+
+    /// Clears the deletion mark, returning `None` if no deleted user has `id`.
+    async fn restore(&self, id: Uuid) -> Result<Option<User>>;
+
+    /// Permanently removes users deleted before `before`, returning how many.
+    async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<u64>;
+
+    /// Assigns a role, returning `None` if the user does not exist.
+    async fn set_role(&self, id: Uuid, role: &str) -> Result<Option<User>>;
+
+    /// Returns the permission names granted to `role`.
+    async fn permissions_for_role(&self, role: &str) -> Result<Vec<String>>;
+
+    /// Replaces a user's password hash, returning whether the user exists.
+    async fn set_password_hash(&self, id: Uuid, password_hash: &str) -> Result<bool>;
+
+    /// Counts a failed login, returning the new number of consecutive failures.
+    async fn record_failed_login(&self, id: Uuid) -> Result<i32>;
+
+    /// Refuses logins for the user until `until`.
+    async fn lock_account(&self, id: Uuid, until: DateTime<Utc>) -> Result<()>;
+
+    /// Resets the failure count and lifts any lock after a successful login.
+    async fn clear_failed_logins(&self, id: Uuid) -> Result<()>;
+
+    /// Turns on two-factor logins with a fresh set of recovery code hashes,
+    /// returning whether the user exists.
+    async fn enable_totp(&self, id: Uuid, recovery_code_hashes: &[String]) -> Result<bool>;
+
+    /// Turns off two-factor logins and deletes the recovery codes.
+    async fn disable_totp(&self, id: Uuid) -> Result<bool>;
+
+    /// Replaces all recovery codes of a user.
+    async fn replace_recovery_codes(&self, id: Uuid, recovery_code_hashes: &[String]) -> Result<()>;
+
+    /// Marks an unused recovery code used, returning whether one matched.
+    async fn consume_recovery_code(&self, id: Uuid, code_hash: &str, now: DateTime<Utc>) -> Result<bool>;
+
+    /// Records the time step of an accepted TOTP code, returning `false` if a
+    /// code from that step or a later one was already accepted.
+    async fn record_totp_step(&self, id: Uuid, step: i64) -> Result<bool>;
+
+    /// Marks the user's email address verified, returning whether the user exists.
+    async fn mark_email_verified(&self, id: Uuid) -> Result<bool>;
+
+    /// Revokes every unrevoked token session of a user, returning how many there were.
+    async fn revoke_auth_sessions(&self, id: Uuid, now: DateTime<Utc>) -> Result<u64>;
+}
+
+/// `UserRepository` backed by the application database.
+#[derive(Clone)]
+pub struct SqlUserRepository {
+    pool: Arc<DbPool>,
+}
+
+impl SqlUserRepository {
+    pub fn new(pool: Arc<DbPool>) -> Self {
+        Self { pool }
+    }
+
+    /// Creates a repository over the global connection pool.
+    pub fn from_global() -> Result<Self> {
+        Ok(Self::new(get_pool_ref()?))
+    }
+}
+
+#[async_trait]
+impl UserRepository for SqlUserRepository {
+    async fn list(&self, include_deleted: bool) -> Result<Vec<User>> {
+        let sql = format!(
+            "SELECT {} FROM users WHERE $1 OR deleted_at IS NULL ORDER BY created_at DESC",
+            USER_COLUMNS
+        );
+
+        let users = with_pool!(self.pool.as_ref(), |pool| sqlx::query_as::<_, User>(&sql)
+            .bind(include_deleted)
+            .fetch_all(pool)
+            .await)?;
+
+        Ok(users)
+    }
+
+    async fn query(&self, filter: &UserFilter) -> Result<Vec<User>> {
+        let sql = format!(
+            "SELECT {} FROM users
+             WHERE {}
+             ORDER BY {}
+             LIMIT $6 OFFSET $7",
+            USER_COLUMNS,
+            FILTER_CONDITIONS,
+            order_clause(filter)
+        );
+
+        let users = with_pool!(self.pool.as_ref(), |pool| bind_filter!(sqlx::query_as::<_, User>(&sql), filter)
+            .bind(filter.limit)
+            .bind(filter.offset)
+            .fetch_all(pool)
+            .await)?;
+
+        Ok(users)
+    }
+
+    async fn count_matching(&self, filter: &UserFilter) -> Result<i64> {
+        let sql = format!("SELECT COUNT(*) FROM users WHERE {}", FILTER_CONDITIONS);
+
+        let count = with_pool!(self.pool.as_ref(), |pool| bind_filter!(sqlx::query_scalar::<_, i64>(&sql), filter)
+            .fetch_one(pool)
+            .await)?;
+
+        Ok(count)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<User>> {
+        let sql = format!("SELECT {} FROM users WHERE id = $1", USER_COLUMNS);
+
+        let user = with_pool!(self.pool.as_ref(), |pool| sqlx::query_as::<_, User>(&sql)
+            .bind(id)
+            .fetch_optional(pool)
+            .await)?;
+
+        Ok(user)
+    }
+
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>> {
+        let sql = format!("SELECT {} FROM users WHERE email = $1 LIMIT 1", USER_COLUMNS);
+
+        let user = with_pool!(self.pool.as_ref(), |pool| sqlx::query_as::<_, User>(&sql)
+            .bind(email)
+            .fetch_optional(pool)
+            .await)?;
+
+        Ok(user)
+    }
+
+    async fn create(&self, user: NewUser, first_role: &str) -> Result<User> {
+        let sql = format!(
+            "INSERT INTO users (id, email, username, password_hash, first_name, last_name, role, created_at, updated_at)
+             SELECT $1, $2, $3, $4, $5, $6, CASE WHEN EXISTS (SELECT 1 FROM users) THEN $7 ELSE $9 END, $8, $8
+             RETURNING {}",
+            USER_COLUMNS
+        );
+        let NewUser {
+            email,
+            username,
+            password_hash,
+            first_name,
+            last_name,
+            role,
+        } = user;
+        // SQLite serializes writers; PostgreSQL needs a lock so two
+        // transactions cannot both see an empty table.
+        let lock = self.pool.backend() == DatabaseBackend::Postgres;
+
+        let user = with_pool!(self.pool.as_ref(), |pool| async {
+            let mut tx = pool.begin().await?;
+
+            if lock {
+                sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                    .bind(FIRST_USER_LOCK_KEY)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            let user = sqlx::query_as::<_, User>(&sql)
+                .bind(Uuid::new_v4())
+                .bind(&email)
+                .bind(&username)
+                .bind(&password_hash)
+                .bind(&first_name)
+                .bind(&last_name)
+                .bind(&role)
+                .bind(Utc::now())
+                .bind(first_role)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(user)
+        }
+        .await)?;
+
+        Ok(user)
+    }
+
+    async fn update(&self, id: Uuid, changes: UserChanges) -> Result<Option<User>> {
+        let sql = format!(
+            "UPDATE users
+             SET email_verified = CASE WHEN $2 IS NOT NULL AND $2 <> email THEN $7 ELSE email_verified END,
+                 email = COALESCE($2, email),
+                 username = COALESCE($3, username),
+                 first_name = COALESCE($4, first_name),
+                 last_name = COALESCE($5, last_name),
+                 updated_at = $6
+             WHERE id = $1 AND deleted_at IS NULL
+             RETURNING {}",
+            USER_COLUMNS
+        );
+        let UserChanges {
+            email,
+            username,
+            first_name,
+            last_name,
+        } = changes;
+
+        let user = with_pool!(self.pool.as_ref(), |pool| sqlx::query_as::<_, User>(&sql)
+            .bind(id)
+            .bind(&email)
+            .bind(&username)
+            .bind(&first_name)
+            .bind(&last_name)
+            .bind(Utc::now())
+            .bind(false)
+            .fetch_optional(pool)
+            .await)?;
+
+        Ok(user)
+    }
+
+    async fn set_active(&self, id: Uuid, active: bool, now: DateTime<Utc>) -> Result<Option<User>> {
+        let sql = format!(
+            "UPDATE users SET is_active = $2, updated_at = $3
+             WHERE id = $1 AND deleted_at IS NULL
+             RETURNING {}",
+            USER_COLUMNS
+        );
+
+        let user = with_pool!(self.pool.as_ref(), |pool| async {
+            let mut tx = pool.begin().await?;
+
+            let user = sqlx::query_as::<_, User>(&sql)
+                .bind(id)
+                .bind(active)
+                .bind(now)
+                .fetch_optional(&mut *tx)
+                .await?;
+            if user.is_some() && !active {
+                sqlx::query(REVOKE_AUTH_SESSIONS)
+                    .bind(id)
+                    .bind(now)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(user)
+        }
+        .await)?;
+
+        Ok(user)
+    }
+
+    async fn soft_delete(&self, id: Uuid, now: DateTime<Utc>) -> Result<bool> {
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| async {
+            let mut tx = pool.begin().await?;
+
+            let rows_affected = sqlx::query(
+                "UPDATE users SET deleted_at = $2, updated_at = $2 WHERE id = $1 AND deleted_at IS NULL",
+            )
+            .bind(id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            if rows_affected > 0 {
+                sqlx::query(REVOKE_AUTH_SESSIONS)
+                    .bind(id)
+                    .bind(now)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+
+            tx.commit().await?;
+            Ok::<_, sqlx::Error>(rows_affected)
+        }
+        .await)?;
+
+        Ok(rows_affected > 0)
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Option<User>> {
+        let sql = format!(
+            "UPDATE users SET deleted_at = NULL, updated_at = $2
+             WHERE id = $1 AND deleted_at IS NOT NULL
+             RETURNING {}",
+            USER_COLUMNS
+        );
+
+        let user = with_pool!(self.pool.as_ref(), |pool| sqlx::query_as::<_, User>(&sql)
+            .bind(id)
+            .bind(Utc::now())
+            .fetch_optional(pool)
+            .await)?;
+
+        Ok(user)
+    }
+
+    async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<u64> {
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "DELETE FROM users WHERE deleted_at IS NOT NULL AND deleted_at < $1"
+        )
+        .bind(before)
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()))?;
+
+        Ok(rows_affected)
+    }
+
+    async fn set_role(&self, id: Uuid, role: &str) -> Result<Option<User>> {
+        let sql = format!(
+            "UPDATE users SET role = $2, updated_at = $3 WHERE id = $1 RETURNING {}",
+            USER_COLUMNS
+        );
+
+        let user = with_pool!(self.pool.as_ref(), |pool| sqlx::query_as::<_, User>(&sql)
+            .bind(id)
+            .bind(role)
+            .bind(Utc::now())
+            .fetch_optional(pool)
+            .await)?;
+
+        Ok(user)
+    }
+
+    async fn permissions_for_role(&self, role: &str) -> Result<Vec<String>> {
+        let permissions = with_pool!(self.pool.as_ref(), |pool| sqlx::query_scalar::<_, String>(
+            "SELECT permission FROM role_permissions WHERE role = $1 ORDER BY permission"
+        )
+        .bind(role)
+        .fetch_all(pool)
+        .await)?;
+
+        Ok(permissions)
+    }
+
+    async fn set_password_hash(&self, id: Uuid, password_hash: &str) -> Result<bool> {
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "UPDATE users SET password_hash = $2, updated_at = $3 WHERE id = $1"
+        )
+        .bind(id)
+        .bind(password_hash)
+        .bind(Utc::now())
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()))?;
+
+        Ok(rows_affected > 0)
+    }
+
+    async fn record_failed_login(&self, id: Uuid) -> Result<i32> {
+        let failures = with_pool!(self.pool.as_ref(), |pool| sqlx::query_scalar::<_, i32>(
+            "UPDATE users
+             SET failed_login_attempts = failed_login_attempts + 1
+             WHERE id = $1
+             RETURNING failed_login_attempts"
+        )
+        .bind(id)
+        .fetch_one(pool)
+        .await)?;
+
+        Ok(failures)
+    }
+
+    async fn lock_account(&self, id: Uuid, until: DateTime<Utc>) -> Result<()> {
+        with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "UPDATE users SET locked_until = $2 WHERE id = $1"
+        )
+        .bind(id)
+        .bind(until)
+        .execute(pool)
+        .await)?;
+
+        Ok(())
+    }
+
+    async fn clear_failed_logins(&self, id: Uuid) -> Result<()> {
+        with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "UPDATE users SET failed_login_attempts = 0, locked_until = NULL WHERE id = $1"
+        )
+        .bind(id)
+        .execute(pool)
+        .await)?;
+
+        Ok(())
+    }
+
+    async fn enable_totp(&self, id: Uuid, recovery_code_hashes: &[String]) -> Result<bool> {
+        // Codes first, so a failure never leaves two-factor on without them.
+        self.replace_recovery_codes(id, recovery_code_hashes).await?;
+
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "UPDATE users SET totp_enabled = $2, updated_at = $3 WHERE id = $1"
+        )
+        .bind(id)
+        .bind(true)
+        .bind(Utc::now())
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()))?;
+
+        Ok(rows_affected > 0)
+    }
+
+    async fn disable_totp(&self, id: Uuid) -> Result<bool> {
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "UPDATE users SET totp_enabled = $2, totp_last_step = NULL, updated_at = $3 WHERE id = $1"
+        )
+        .bind(id)
+        .bind(false)
+        .bind(Utc::now())
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()))?;
+
+        with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "DELETE FROM recovery_codes WHERE user_id = $1"
+        )
+        .bind(id)
+        .execute(pool)
+        .await)?;
+
+        Ok(rows_affected > 0)
+    }
+
+    async fn replace_recovery_codes(&self, id: Uuid, recovery_code_hashes: &[String]) -> Result<()> {
+        let now = Utc::now();
+
+        with_pool!(self.pool.as_ref(), |pool| async {
+            let mut tx = pool.begin().await?;
+
+            sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+
+            for code_hash in recovery_code_hashes {
+                sqlx::query(
+                    "INSERT INTO recovery_codes (id, user_id, code_hash, created_at)
+                     VALUES ($1, $2, $3, $4)",
+                )
+                .bind(Uuid::new_v4())
+                .bind(id)
+                .bind(code_hash)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            tx.commit().await
+        }
+        .await)?;
+
+        Ok(())
+    }
+
+    async fn consume_recovery_code(&self, id: Uuid, code_hash: &str, now: DateTime<Utc>) -> Result<bool> {
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "UPDATE recovery_codes SET used_at = $3
+             WHERE id = (
+                 SELECT id FROM recovery_codes
+                 WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+                 LIMIT 1
+             ) AND used_at IS NULL"
+        )
+        .bind(id)
+        .bind(code_hash)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()))?;
+
+        Ok(rows_affected > 0)
+    }
+
+    async fn record_totp_step(&self, id: Uuid, step: i64) -> Result<bool> {
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "UPDATE users SET totp_last_step = $2
+             WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)"
+        )
+        .bind(id)
+        .bind(step)
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()))?;
+
+        Ok(rows_affected > 0)
+    }
+
+    async fn mark_email_verified(&self, id: Uuid) -> Result<bool> {
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| sqlx::query(
+            "UPDATE users SET email_verified = $2, updated_at = $3 WHERE id = $1"
+        )
+        .bind(id)
+        .bind(true)
+        .bind(Utc::now())
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()))?;
+
+        Ok(rows_affected > 0)
+    }
+
+    async fn revoke_auth_sessions(&self, id: Uuid, now: DateTime<Utc>) -> Result<u64> {
+        let rows_affected = with_pool!(self.pool.as_ref(), |pool| sqlx::query(REVOKE_AUTH_SESSIONS)
+        .bind(id)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map(|result| result.rows_affected()))?;
+
+        Ok(rows_affected)
+    }
+}
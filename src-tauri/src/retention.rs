@@ -0,0 +1,25 @@
+//! Retention of soft-deleted accounts.
+//!
+//! Deleting a user only marks the row with `deleted_at`; it can be restored
+//! until the retention period has passed, after which it is purged together
+//! with everything that cascades from it.
+
+use chrono::{DateTime, Duration, Utc};
+
+/// Days deleted accounts are kept when `AUTH_DELETED_USER_RETENTION_DAYS` is unset.
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+/// How long deleted accounts are kept, from `AUTH_DELETED_USER_RETENTION_DAYS`.
+pub fn deleted_user_retention() -> Duration {
+    let days = std::env::var("AUTH_DELETED_USER_RETENTION_DAYS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|days: &i64| *days >= 0)
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+    Duration::days(days)
+}
+
+/// Accounts deleted before the returned time are due to be purged at `now`.
+pub fn purge_cutoff(now: DateTime<Utc>) -> DateTime<Utc> {
+    now - deleted_user_retention()
+}
@@ -0,0 +1,361 @@
+//! Server-side sessions binding an authenticated user to the calling webview.
+//!
+//! A [`SessionStore`] is kept in managed state and keyed by webview label, so
+//! each window signs in independently and a frontend cannot claim another
+//! window's identity. Commands read the caller through the [`Session`] and
+//! [`CurrentUser`] command arguments; the latter rejects calls without a
+//! session before the command body runs.
+//!
+//! The role's permissions are loaded at sign-in, so a role change takes effect
+//! once the user signs in again.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, RwLock};
+use tauri::ipc::{CommandArg, CommandItem, InvokeError};
+use tauri::Runtime;
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::PublicUser;
+use crate::permissions::Permission;
+
+/// Error returned to commands that require a session.
+pub const NOT_AUTHENTICATED: &str = "Not authenticated";
+
+/// Identity of the user signed in to a webview.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionUser {
+    pub user_id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub role: String,
+    pub permissions: BTreeSet<String>,
+    pub signed_in_at: DateTime<Utc>,
+}
+
+impl SessionUser {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(permission.as_str())
+    }
+
+    /// Fails with `Forbidden` unless the user holds `permission`.
+    pub fn authorize(&self, permission: Permission) -> AppResult<()> {
+        if !self.has_permission(permission) {
+            return Err(AppError::forbidden(format!(
+                "Permission '{}' is required",
+                permission
+            ))
+            .with_context(serde_json::json!({ "permission": permission.as_str() })));
+        }
+        Ok(())
+    }
+
+    /// Parses `user_id` and allows it if it names the signed-in user or the
+    /// user holds `permission` over other accounts.
+    pub fn ensure_self_or(&self, user_id: &str, permission: Permission) -> AppResult<Uuid> {
+        let uuid = Uuid::parse_str(user_id)
+            .map_err(|e| AppError::invalid_input("user_id", format!("Invalid UUID: {}", e)))?;
+
+        if uuid != self.user_id {
+            self.authorize(permission)?;
+        }
+
+        Ok(uuid)
+    }
+}
+
+/// Signed-in users by webview label.
+#[derive(Debug, Default)]
+pub struct SessionStore {
+    sessions: RwLock<HashMap<String, SessionUser>>,
+}
+
+impl SessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Signs `user` in to the webview `label` with the given permission
+    /// names, replacing any previous session.
+    pub fn sign_in(&self, label: &str, user: &PublicUser, permissions: Vec<String>) -> SessionUser {
+        let session = SessionUser {
+            user_id: user.id,
+            email: user.email.clone(),
+            username: user.username.clone(),
+            role: user.role.clone(),
+            permissions: permissions.into_iter().collect(),
+            signed_in_at: Utc::now(),
+        };
+
+        self.write().insert(label.to_string(), session.clone());
+        tracing::info!("User {} signed in to window '{}'", user.id, label);
+        session
+    }
+
+    /// Ends the session of the webview `label`, returning who was signed in.
+    pub fn sign_out(&self, label: &str) -> Option<SessionUser> {
+        self.write().remove(label)
+    }
+
+    /// Ends every session of `user_id`, returning how many were removed.
+    pub fn sign_out_user(&self, user_id: Uuid) -> usize {
+        let mut sessions = self.write();
+        let before = sessions.len();
+        sessions.retain(|_, session| session.user_id != user_id);
+        before - sessions.len()
+    }
+
+    /// Ends every session of `user_id` except the one in the webview `label`,
+    /// returning how many were removed.
+    pub fn sign_out_other_windows(&self, user_id: Uuid, label: &str) -> usize {
+        let mut sessions = self.write();
+        let before = sessions.len();
+        sessions.retain(|window, session| session.user_id != user_id || window == label);
+        before - sessions.len()
+    }
+
+    /// Returns the user signed in to the webview `label`.
+    pub fn get(&self, label: &str) -> Option<SessionUser> {
+        self.sessions
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(label)
+            .cloned()
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, SessionUser>> {
+        self.sessions
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The calling webview and whoever is signed in to it, if anyone.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub label: String,
+    pub user: Option<SessionUser>,
+    store: Option<Arc<SessionStore>>,
+}
+
+impl Session {
+    /// Returns the signed-in user or the "Not authenticated" error.
+    pub fn require(self) -> Result<CurrentUser, String> {
+        match (self.user, self.store) {
+            (Some(user), Some(store)) => Ok(CurrentUser {
+                label: self.label,
+                user,
+                store,
+            }),
+            _ => Err(NOT_AUTHENTICATED.to_string()),
+        }
+    }
+
+    /// Returns the signed-in user if they hold `permission`.
+    ///
+    /// Anonymous callers get `Unauthorized`, signed-in ones without the
+    /// permission `Forbidden`.
+    pub fn authorize(&self, permission: Permission) -> AppResult<&SessionUser> {
+        let user = self
+            .user
+            .as_ref()
+            .ok_or_else(|| AppError::unauthorized(NOT_AUTHENTICATED))?;
+        user.authorize(permission)?;
+        Ok(user)
+    }
+
+    /// Key for per-user rate limiting; `None` for anonymous callers.
+    pub fn rate_limit_key(&self) -> Option<String> {
+        self.user.as_ref().map(|user| user.user_id.to_string())
+    }
+
+    /// The session store, or an error if it was never installed.
+    pub fn store(&self) -> Result<&Arc<SessionStore>, String> {
+        self.store
+            .as_ref()
+            .ok_or_else(|| "Session store not initialized".to_string())
+    }
+
+    #[cfg(test)]
+    pub fn for_tests(label: &str, store: Arc<SessionStore>) -> Self {
+        Self {
+            label: label.to_string(),
+            user: store.get(label),
+            store: Some(store),
+        }
+    }
+}
+
+impl<'de, R: Runtime> CommandArg<'de, R> for Session {
+    fn from_command(command: CommandItem<'de, R>) -> Result<Self, InvokeError> {
+        let label = command.message.webview_ref().label().to_string();
+        let store = command
+            .message
+            .state_ref()
+            .try_get::<Arc<SessionStore>>()
+            .map(|store| store.inner().clone());
+        let user = store.as_ref().and_then(|store| store.get(&label));
+
+        Ok(Self { label, user, store })
+    }
+}
+
+/// The signed-in user of the calling webview.
+///
+/// Taking this as a command argument rejects anonymous callers.
+#[derive(Debug, Clone)]
+pub struct CurrentUser {
+    label: String,
+    user: SessionUser,
+    store: Arc<SessionStore>,
+}
+
+impl CurrentUser {
+    /// Label of the calling webview.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The store holding every window's session.
+    pub fn sessions(&self) -> &SessionStore {
+        &self.store
+    }
+
+    /// A user signed in to the webview `main`.
+    #[cfg(test)]
+    pub fn for_tests(user: SessionUser, store: Arc<SessionStore>) -> Self {
+        Self {
+            label: "main".to_string(),
+            user,
+            store,
+        }
+    }
+}
+
+impl std::ops::Deref for CurrentUser {
+    type Target = SessionUser;
+
+    fn deref(&self) -> &SessionUser {
+        &self.user
+    }
+}
+
+impl<'de, R: Runtime> CommandArg<'de, R> for CurrentUser {
+    fn from_command(command: CommandItem<'de, R>) -> Result<Self, InvokeError> {
+        Session::from_command(command)?
+            .require()
+            .map_err(InvokeError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ErrorCode;
+    use crate::permissions::{default_permissions, ROLE_ADMIN, ROLE_OPERATOR, ROLE_USER};
+
+    fn public_user(email: &str, role: &str) -> PublicUser {
+        PublicUser {
+            id: Uuid::new_v4(),
+            email: email.to_string(),
+            username: email.split('@').next().unwrap().to_string(),
+            first_name: None,
+            last_name: None,
+            is_active: true,
+            created_at: Utc::now(),
+            role: role.to_string(),
+            totp_enabled: false,
+            email_verified: true,
+            deleted_at: None,
+        }
+    }
+
+    fn sign_in(store: &SessionStore, label: &str, user: &PublicUser) -> SessionUser {
+        let permissions = default_permissions(&user.role)
+            .iter()
+            .map(|permission| permission.as_str().to_string())
+            .collect();
+        store.sign_in(label, user, permissions)
+    }
+
+    #[test]
+    fn sessions_are_scoped_to_their_webview() {
+        let store = SessionStore::new();
+        let alice = public_user("alice@example.com", ROLE_USER);
+        let bob = public_user("bob@example.com", ROLE_USER);
+
+        sign_in(&store, "main", &alice);
+        sign_in(&store, "settings", &alice);
+        sign_in(&store, "other", &bob);
+
+        assert_eq!(store.get("main").map(|s| s.user_id), Some(alice.id));
+        assert_eq!(store.get("other").map(|s| s.user_id), Some(bob.id));
+        assert_eq!(store.get("unknown"), None);
+
+        assert_eq!(store.sign_out("other").map(|s| s.user_id), Some(bob.id));
+        assert_eq!(store.get("other"), None);
+
+        sign_in(&store, "other", &bob);
+        assert_eq!(store.sign_out_other_windows(alice.id, "main"), 1);
+        assert_eq!(store.get("settings"), None);
+        assert_eq!(store.get("other").map(|s| s.user_id), Some(bob.id));
+
+        assert_eq!(store.sign_out_user(alice.id), 1);
+        assert_eq!(store.get("main"), None);
+    }
+
+    #[test]
+    fn anonymous_sessions_are_rejected() {
+        let store = Arc::new(SessionStore::new());
+        let anonymous = Session {
+            label: "main".to_string(),
+            user: None,
+            store: Some(store.clone()),
+        };
+        assert_eq!(anonymous.rate_limit_key(), None);
+        assert!(matches!(
+            anonymous.authorize(Permission::UsersRead),
+            Err(AppError { code: ErrorCode::Unauthorized, .. })
+        ));
+        assert_eq!(anonymous.require().unwrap_err(), NOT_AUTHENTICATED);
+
+        let alice = public_user("alice@example.com", ROLE_USER);
+        let signed_in = Session {
+            label: "main".to_string(),
+            user: Some(sign_in(&store, "main", &alice)),
+            store: Some(store),
+        };
+        assert_eq!(signed_in.rate_limit_key(), Some(alice.id.to_string()));
+
+        let current = signed_in.require().expect("signed-in session should pass");
+        assert_eq!(
+            current.ensure_self_or(&alice.id.to_string(), Permission::UsersWrite).ok(),
+            Some(alice.id)
+        );
+        assert!(matches!(
+            current.ensure_self_or("not-a-uuid", Permission::UsersWrite),
+            Err(AppError { code: ErrorCode::InvalidInput, .. })
+        ));
+    }
+
+    #[test]
+    fn permissions_follow_the_role() {
+        let store = SessionStore::new();
+        let admin = sign_in(&store, "admin", &public_user("admin@example.com", ROLE_ADMIN));
+        let operator = sign_in(&store, "kiosk", &public_user("operator@example.com", ROLE_OPERATOR));
+        let other_user = Uuid::new_v4().to_string();
+
+        assert!(admin.ensure_self_or(&other_user, Permission::UsersDelete).is_ok());
+        assert!(operator.authorize(Permission::UsersRead).is_ok());
+
+        let denied = operator.ensure_self_or(&other_user, Permission::UsersDelete);
+        assert!(matches!(denied, Err(AppError { code: ErrorCode::Forbidden, .. })));
+        assert!(matches!(
+            operator.authorize(Permission::LogsPurge),
+            Err(AppError { code: ErrorCode::Forbidden, .. })
+        ));
+    }
+}
@@ -0,0 +1,235 @@
+//! Portable settings bundles.
+//!
+//! A bundle holds what a user set in `user_settings`, their effective
+//! [`AppSettings`] and the logging configuration, so it can be sent to
+//! support or copied to another machine. It carries no ids: importing applies
+//! it to whichever user imports it.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Component, Path};
+
+use super::schema::SettingsRegistry;
+use super::{app_defaults, merge_patch, validate_app_settings, DEFAULT_LANGUAGE, DEFAULT_THEME};
+use crate::logging::config::AppLogConfig;
+use crate::models::{AppSettings, UserSettings};
+use crate::validation::{validate_language, validate_theme};
+
+/// Identifies a JSON file as a settings bundle.
+pub const BUNDLE_FORMAT: &str = "ez-tauri-settings";
+
+/// Current bundle version. Bundles from newer versions are rejected.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// The user-set part of a `user_settings` row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundledUserSettings {
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    #[serde(default = "default_language")]
+    pub language: String,
+    #[serde(default = "default_notifications_enabled")]
+    pub notifications_enabled: bool,
+    /// Only the keys the user set, without defaults.
+    #[serde(default = "empty_object")]
+    pub settings_data: Value,
+}
+
+fn default_theme() -> String {
+    DEFAULT_THEME.to_string()
+}
+
+fn default_language() -> String {
+    DEFAULT_LANGUAGE.to_string()
+}
+
+fn default_notifications_enabled() -> bool {
+    true
+}
+
+fn empty_object() -> Value {
+    Value::Object(Default::default())
+}
+
+/// A versioned export of a user's configuration.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsBundle {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub user_settings: BundledUserSettings,
+    pub app_settings: AppSettings,
+    pub log_config: AppLogConfig,
+}
+
+impl SettingsBundle {
+    /// Builds a bundle from a user's stored settings, which hold only what
+    /// the user set.
+    ///
+    /// Rows saved before app settings were type-checked may hold values
+    /// [`AppSettings`] cannot read; those are exported as their defaults.
+    pub fn new(stored: &UserSettings, log_config: AppLogConfig, now: DateTime<Utc>) -> Result<Self> {
+        let mut data = stored.settings_data.clone();
+        if let (Err(errors), Value::Object(values)) = (validate_app_settings(&data, &data), &mut data) {
+            for error in errors {
+                tracing::warn!("Exporting default for invalid app setting of user {}: {}", stored.user_id, error);
+                values.remove(&error.field);
+            }
+        }
+        let mut effective = app_defaults();
+        merge_patch(&mut effective, &data);
+        let app_settings = serde_json::from_value(effective)
+            .map_err(|e| anyhow!("Failed to read app settings: {}", e))?;
+
+        Ok(Self {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            exported_at: now,
+            user_settings: BundledUserSettings {
+                theme: stored.theme.clone(),
+                language: stored.language.clone(),
+                notifications_enabled: stored.notifications_enabled,
+                settings_data: stored.settings_data.clone(),
+            },
+            app_settings,
+            log_config,
+        })
+    }
+
+    /// Parses and validates a bundle.
+    ///
+    /// Theme and language are normalized, and every registered namespace in
+    /// `settingsData` must satisfy its schema.
+    pub fn parse(json: &str, registry: &SettingsRegistry) -> Result<Self> {
+        let header: Value = serde_json::from_str(json).map_err(|e| anyhow!("Invalid settings bundle: {}", e))?;
+        if header.get("format").and_then(Value::as_str) != Some(BUNDLE_FORMAT) {
+            bail!("Invalid settings bundle: not a settings export");
+        }
+        match header.get("version").and_then(Value::as_u64) {
+            Some(version) if (1..=u64::from(BUNDLE_VERSION)).contains(&version) => {}
+            Some(version) => bail!("Unsupported settings bundle version {}", version),
+            None => bail!("Invalid settings bundle: missing version"),
+        }
+
+        let mut bundle: Self = serde_json::from_value(header).map_err(|e| anyhow!("Invalid settings bundle: {}", e))?;
+
+        let user_settings = &mut bundle.user_settings;
+        user_settings.theme = validate_theme(&user_settings.theme).map_err(|e| anyhow!("Invalid theme: {}", e))?;
+        user_settings.language =
+            validate_language(&user_settings.language).map_err(|e| anyhow!("Invalid language: {}", e))?;
+        if !user_settings.settings_data.is_object() {
+            bail!("Invalid settings data: must be a JSON object");
+        }
+        let data = &user_settings.settings_data;
+        registry.validate(data, data).map_err(|errors| {
+            let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            anyhow!("Invalid settings data: {}", errors.join("; "))
+        })?;
+
+        let directory = Path::new(&bundle.log_config.file.directory);
+        if directory
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            bail!("Invalid log configuration: the log directory must be a relative path inside the app directory");
+        }
+
+        Ok(bundle)
+    }
+
+    /// The `settings_data` to store: the user's keys with the bundled app
+    /// settings applied. App settings equal to their default are left unset.
+    pub fn settings_data(&self) -> Value {
+        let mut data = self.user_settings.settings_data.clone();
+        let defaults = app_defaults();
+
+        if let (Value::Object(data), Ok(Value::Object(app))) = (&mut data, serde_json::to_value(&self.app_settings)) {
+            for (key, value) in app.into_iter().filter(|(_, value)| !value.is_null()) {
+                if defaults.get(&key) == Some(&value) {
+                    data.remove(&key);
+                } else {
+                    data.insert(key, value);
+                }
+            }
+        }
+
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::unsaved;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn bundle_json(changes: Value) -> String {
+        let mut stored = unsaved(Uuid::new_v4(), Utc::now());
+        stored.theme = "dark".to_string();
+        stored.settings_data = json!({ "autoSave": false, "editor": { "tabSize": 4 } });
+        let bundle = SettingsBundle::new(&stored, AppLogConfig::default(), Utc::now()).unwrap();
+
+        let mut value = serde_json::to_value(bundle).unwrap();
+        merge_patch(&mut value, &changes);
+        value.to_string()
+    }
+
+    #[test]
+    fn bundles_round_trip() {
+        let json = bundle_json(json!({ "appSettings": { "sidebarCollapsed": true, "autoSave": true } }));
+        let bundle = SettingsBundle::parse(&json, &SettingsRegistry::new()).unwrap();
+
+        assert_eq!(bundle.version, BUNDLE_VERSION);
+        assert_eq!(bundle.user_settings.theme, "dark");
+        // autoSave is back at its default, so it is no longer stored.
+        assert_eq!(
+            bundle.settings_data(),
+            json!({ "sidebarCollapsed": true, "editor": { "tabSize": 4 } })
+        );
+    }
+
+    #[test]
+    fn invalid_stored_app_settings_export_as_defaults() {
+        let mut stored = unsaved(Uuid::new_v4(), Utc::now());
+        stored.settings_data = json!({ "autoSave": "yes", "sidebarCollapsed": true });
+
+        let bundle = SettingsBundle::new(&stored, AppLogConfig::default(), Utc::now()).unwrap();
+        assert_eq!(bundle.app_settings.auto_save, Some(true));
+        assert_eq!(bundle.app_settings.sidebar_collapsed, Some(true));
+
+        let json = serde_json::to_string(&bundle).unwrap();
+        let imported = SettingsBundle::parse(&json, &SettingsRegistry::new()).unwrap();
+        assert_eq!(imported.settings_data(), json!({ "sidebarCollapsed": true }));
+    }
+
+    #[test]
+    fn invalid_bundles_are_rejected() {
+        let registry = SettingsRegistry::new();
+        registry
+            .register_json("editor", r#"{ "tabSize": { "field_type": "integer", "min": 4 } }"#)
+            .unwrap();
+
+        for (changes, expected) in [
+            (json!({ "format": "other" }), "Invalid settings bundle: not a settings export"),
+            (json!({ "version": 2 }), "Unsupported settings bundle version 2"),
+            (json!({ "userSettings": { "theme": "neon" } }), "Invalid theme: Theme must be one of: light, dark, system"),
+            (
+                json!({ "userSettings": { "settingsData": { "editor": { "tabSize": 2 } } } }),
+                "Invalid settings data: editor.tabSize must be at least 4",
+            ),
+            (
+                json!({ "logConfig": { "file": { "directory": "../../etc" } } }),
+                "Invalid log configuration: the log directory must be a relative path inside the app directory",
+            ),
+        ] {
+            let error = SettingsBundle::parse(&bundle_json(changes), &registry).unwrap_err();
+            assert_eq!(error.to_string(), expected);
+        }
+        assert!(SettingsBundle::parse("not json", &registry).is_err());
+    }
+}
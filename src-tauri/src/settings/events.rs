@@ -0,0 +1,153 @@
+//! Settings change notifications.
+//!
+//! Settings writes publish a [`SettingsChanged`] to [`settings_events`].
+//! The application forwards each change as a Tauri event to the webviews
+//! signed in as the user whose settings changed.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+use crate::models::UserSettings;
+
+/// Name of the Tauri event emitted when a user's settings change.
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+/// A change to one user's settings.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsChanged {
+    pub user_id: Uuid,
+    /// Changed keys, such as `theme` or `settingsData.editor`, in the
+    /// spelling the frontend uses.
+    pub changed: Vec<String>,
+    /// The settings after the change, with defaults applied.
+    pub settings: UserSettings,
+}
+
+impl SettingsChanged {
+    /// Compares two snapshots of a user's settings, both with defaults
+    /// applied. Returns `None` when nothing changed.
+    pub fn between(before: &UserSettings, after: &UserSettings) -> Option<Self> {
+        let mut changed = Vec::new();
+        if before.theme != after.theme {
+            changed.push("theme".to_string());
+        }
+        if before.language != after.language {
+            changed.push("language".to_string());
+        }
+        if before.notifications_enabled != after.notifications_enabled {
+            changed.push("notificationsEnabled".to_string());
+        }
+
+        let keys: BTreeSet<&String> = [&before.settings_data, &after.settings_data]
+            .into_iter()
+            .filter_map(Value::as_object)
+            .flat_map(|data| data.keys())
+            .collect();
+        for key in keys {
+            if before.settings_data.get(key) != after.settings_data.get(key) {
+                changed.push(format!("settingsData.{}", key));
+            }
+        }
+
+        if changed.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            user_id: after.user_id,
+            changed,
+            settings: after.clone(),
+        })
+    }
+}
+
+type Subscriber = Arc<dyn Fn(&SettingsChanged) + Send + Sync>;
+
+/// Subscribers to settings changes.
+#[derive(Default)]
+pub struct SettingsEvents {
+    subscribers: RwLock<Vec<Subscriber>>,
+}
+
+impl SettingsEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls `subscriber` with every change published from now on.
+    ///
+    /// Subscribers run on the task that saved the settings, so anything slow
+    /// should be spawned onto the async runtime.
+    pub fn subscribe<F>(&self, subscriber: F)
+    where
+        F: Fn(&SettingsChanged) + Send + Sync + 'static,
+    {
+        self.subscribers
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Arc::new(subscriber));
+    }
+
+    /// Delivers `event` to every subscriber.
+    pub fn publish(&self, event: &SettingsChanged) {
+        // Subscribers may subscribe in turn, so call them unlocked.
+        let subscribers: Vec<Subscriber> = self.subscribers.read().unwrap_or_else(|e| e.into_inner()).clone();
+
+        for subscriber in subscribers {
+            subscriber(event);
+        }
+    }
+}
+
+static SETTINGS_EVENTS: Lazy<SettingsEvents> = Lazy::new(SettingsEvents::default);
+
+/// The process-wide subscribers notified by the settings commands.
+pub fn settings_events() -> &'static SettingsEvents {
+    &SETTINGS_EVENTS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::unsaved;
+    use chrono::Utc;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    #[test]
+    fn only_changed_keys_are_reported() {
+        let before = unsaved(Uuid::new_v4(), Utc::now());
+        assert_eq!(SettingsChanged::between(&before, &before), None);
+
+        let mut after = before.clone();
+        after.theme = "dark".to_string();
+        after.notifications_enabled = false;
+        after.settings_data = json!({ "editor": { "tabSize": 2 } });
+
+        let event = SettingsChanged::between(&before, &after).unwrap();
+        assert_eq!(event.user_id, before.user_id);
+        assert_eq!(event.changed, vec!["theme", "notificationsEnabled", "settingsData.editor"]);
+    }
+
+    #[test]
+    fn subscribers_receive_published_changes() {
+        let events = SettingsEvents::new();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recorded = received.clone();
+        events.subscribe(move |event: &SettingsChanged| recorded.lock().unwrap().push(event.changed.clone()));
+
+        let before = unsaved(Uuid::new_v4(), Utc::now());
+        let mut after = before.clone();
+        after.language = "de".to_string();
+        let event = SettingsChanged::between(&before, &after).unwrap();
+
+        events.publish(&event);
+
+        assert_eq!(*received.lock().unwrap(), vec![vec!["language".to_string()]]);
+    }
+}
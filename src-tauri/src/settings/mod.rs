@@ -0,0 +1,187 @@
+//! Per-user settings defaults and JSON merge patches.
+//!
+//! A user without a `user_settings` row gets [`unsaved`] settings until they
+//! save some. `settings_data` only stores what the user changed: it is updated
+//! with JSON merge patches (RFC 7386) and read back over the
+//! [`AppSettings`] defaults, so removing a key restores its default.
+//! Namespaces registered in [`schema`] add typed, validated settings, and
+//! changes are published through [`events`]. [`bundle`] exports and imports
+//! a user's configuration as a portable JSON file.
+
+pub mod bundle;
+pub mod events;
+pub mod schema;
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::models::{AppSettings, UserSettings};
+use schema::{FieldError, SettingsRegistry};
+
+/// Theme of users who have not picked one; matches the column default.
+pub const DEFAULT_THEME: &str = "light";
+
+/// Language of users who have not picked one; matches the column default.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Settings for a user without a stored row. The nil id marks them unsaved.
+pub fn unsaved(user_id: Uuid, now: DateTime<Utc>) -> UserSettings {
+    UserSettings {
+        id: Uuid::nil(),
+        user_id,
+        theme: DEFAULT_THEME.to_string(),
+        language: DEFAULT_LANGUAGE.to_string(),
+        notifications_enabled: true,
+        settings_data: Value::Object(Default::default()),
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+/// [`AppSettings::default`] as JSON.
+pub(crate) fn app_defaults() -> Value {
+    serde_json::to_value(AppSettings::default()).unwrap_or(Value::Null)
+}
+
+/// Checks that the [`AppSettings`] keys `patch` touches have the right type
+/// in `data`, the settings data after the patch was applied.
+pub fn validate_app_settings(data: &Value, patch: &Value) -> Result<(), Vec<FieldError>> {
+    let Value::Object(defaults) = app_defaults() else {
+        return Ok(());
+    };
+
+    let mut errors = Vec::new();
+    let touched = patch.as_object().into_iter().flat_map(|patch| patch.keys());
+    for key in touched {
+        let (Some(default), Some(value)) = (defaults.get(key), data.get(key)) else {
+            continue;
+        };
+        let single = Value::Object([(key.clone(), value.clone())].into_iter().collect());
+        if serde_json::from_value::<AppSettings>(single).is_err() {
+            let expected = match default {
+                Value::Bool(_) => "must be a boolean",
+                Value::Number(_) => "must be a number",
+                Value::String(_) => "must be a string",
+                _ => "has an invalid value",
+            };
+            errors.push(FieldError {
+                field: key.clone(),
+                message: expected.to_string(),
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Fills in `settings_data` keys the user has not set from [`AppSettings`]
+/// and the namespaces in `registry`.
+pub fn with_defaults(mut settings: UserSettings, registry: &SettingsRegistry) -> UserSettings {
+    let mut data = app_defaults();
+    merge_patch(&mut data, &settings.settings_data);
+    registry.apply_defaults(&mut data);
+    settings.settings_data = data;
+    settings
+}
+
+/// Applies a JSON merge patch to `target`: objects are merged key by key,
+/// `null` removes a key, and any other value replaces what was there.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merge_patch_follows_rfc_7386() {
+        let mut target = json!({
+            "title": "Goodbye!",
+            "author": { "givenName": "John", "familyName": "Doe" },
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        });
+
+        merge_patch(
+            &mut target,
+            &json!({
+                "title": "Hello!",
+                "phoneNumber": "+01-123-456-7890",
+                "author": { "familyName": null },
+                "tags": ["example"]
+            }),
+        );
+
+        assert_eq!(
+            target,
+            json!({
+                "title": "Hello!",
+                "author": { "givenName": "John" },
+                "tags": ["example"],
+                "content": "This will be unchanged",
+                "phoneNumber": "+01-123-456-7890"
+            })
+        );
+
+        merge_patch(&mut target, &json!({ "title": { "short": "Hi" } }));
+        assert_eq!(target["title"], json!({ "short": "Hi" }));
+    }
+
+    #[test]
+    fn defaults_fill_in_unset_keys() {
+        let mut settings = unsaved(Uuid::new_v4(), Utc::now());
+        settings.settings_data = json!({ "autoSave": false, "custom": 1 });
+
+        let registry = SettingsRegistry::new();
+        registry
+            .register_json("editor", r#"{ "tabSize": { "field_type": "integer", "default": 4 } }"#)
+            .unwrap();
+
+        let settings = with_defaults(settings, &registry);
+        assert_eq!(
+            settings.settings_data,
+            json!({
+                "sidebarCollapsed": false,
+                "autoSave": false,
+                "notifications": true,
+                "custom": 1,
+                "editor": { "tabSize": 4 }
+            })
+        );
+    }
+
+    #[test]
+    fn app_settings_are_type_checked_where_patched() {
+        let data = json!({ "autoSave": "yes", "notifications": 1, "custom": "anything" });
+
+        let errors = validate_app_settings(&data, &json!({ "autoSave": "yes", "custom": "anything" })).unwrap_err();
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(messages, vec!["autoSave must be a boolean"]);
+
+        assert_eq!(validate_app_settings(&json!({ "autoSave": false }), &json!({ "autoSave": false })), Ok(()));
+        assert_eq!(validate_app_settings(&data, &json!({ "custom": 2 })), Ok(()));
+    }
+}
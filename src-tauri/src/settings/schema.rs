@@ -0,0 +1,396 @@
+//! Typed settings namespaces.
+//!
+//! Modules and plugins register a namespace with a schema in the same field
+//! format as `config_schema` in `module.json`. The user's values for it live
+//! under `settings_data.<namespace>`: writes are checked against the schema,
+//! and fields the user has not set read back as the schema's defaults.
+
+use anyhow::{anyhow, bail, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::RwLock;
+
+/// A per-user settings namespace declared by a module.
+///
+/// This is separate from the module's `config_schema`, which configures the
+/// module for the whole app and is not stored with each user.
+#[derive(Debug, Clone, Copy)]
+pub struct ModuleSettings {
+    pub namespace: &'static str,
+    /// JSON object in `config_schema` format.
+    pub schema: &'static str,
+}
+
+/// User settings declared by the modules enabled in this build. Modules add
+/// an entry here, gated on their feature, when they store per-user values.
+pub const MODULE_SETTINGS: &[ModuleSettings] = &[];
+
+/// JSON type a setting holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FieldType::String => "a string",
+            FieldType::Number => "a number",
+            FieldType::Integer => "an integer",
+            FieldType::Boolean => "a boolean",
+            FieldType::Array => "an array",
+            FieldType::Object => "an object",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One setting in a namespace.
+///
+/// `min` and `max` bound numbers, and the length of strings and arrays.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldSchema {
+    pub field_type: FieldType,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    /// The field must have a value once the namespace is written, either set
+    /// by the user or from `default`.
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// Regular expression string values must match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enum_values: Option<Vec<Value>>,
+}
+
+/// Fields of a namespace by name.
+pub type SettingsSchema = BTreeMap<String, FieldSchema>;
+
+/// A setting that failed validation. `field` is `namespace.key`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.field, self.message)
+    }
+}
+
+impl FieldSchema {
+    /// Checks `value` against the field, returning why it does not fit.
+    fn check(&self, value: &Value) -> Option<String> {
+        let type_matches = match self.field_type {
+            FieldType::String => value.is_string(),
+            FieldType::Number => value.is_number(),
+            FieldType::Integer => value.is_i64() || value.is_u64(),
+            FieldType::Boolean => value.is_boolean(),
+            FieldType::Array => value.is_array(),
+            FieldType::Object => value.is_object(),
+        };
+        if !type_matches {
+            return Some(format!("must be {}", self.field_type));
+        }
+
+        if let Some(allowed) = &self.enum_values {
+            if !allowed.contains(value) {
+                let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+                return Some(format!("must be one of: {}", allowed.join(", ")));
+            }
+        }
+
+        let (size, unit) = match value {
+            Value::Number(number) => (number.as_f64(), ""),
+            Value::String(text) => (Some(text.chars().count() as f64), " characters"),
+            Value::Array(items) => (Some(items.len() as f64), " items"),
+            _ => (None, ""),
+        };
+        if let Some(size) = size {
+            if let Some(min) = self.min.filter(|min| size < *min) {
+                return Some(format!("must be at least {}{}", min, unit));
+            }
+            if let Some(max) = self.max.filter(|max| size > *max) {
+                return Some(format!("must be at most {}{}", max, unit));
+            }
+        }
+
+        if let (Some(pattern), Value::String(text)) = (&self.pattern, value) {
+            // Patterns are compiled when the namespace is registered.
+            if !Regex::new(pattern).map_or(false, |regex| regex.is_match(text)) {
+                return Some(format!("must match {}", pattern));
+            }
+        }
+
+        None
+    }
+}
+
+/// Registered settings namespaces.
+#[derive(Debug, Default)]
+pub struct SettingsRegistry {
+    namespaces: RwLock<BTreeMap<String, SettingsSchema>>,
+}
+
+impl SettingsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `schema` under `namespace`.
+    ///
+    /// Fails if the namespace is taken, clashes with a key of
+    /// [`crate::models::AppSettings`], or has a pattern that does not compile
+    /// or a default that does not satisfy its own field.
+    pub fn register(&self, namespace: &str, schema: SettingsSchema) -> Result<()> {
+        static NAMESPACE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z][a-z0-9_-]{0,63}$").unwrap());
+        if !NAMESPACE_REGEX.is_match(namespace) {
+            bail!("Invalid settings namespace '{}'", namespace);
+        }
+        if super::app_defaults().get(namespace).is_some() {
+            bail!("Settings namespace '{}' is reserved", namespace);
+        }
+
+        for (key, field) in &schema {
+            if let Some(pattern) = &field.pattern {
+                Regex::new(pattern).map_err(|e| anyhow!("Invalid pattern for {}.{}: {}", namespace, key, e))?;
+            }
+            if let Some(message) = field.default.as_ref().and_then(|default| field.check(default)) {
+                bail!("Invalid default for {}.{}: {}", namespace, key, message);
+            }
+        }
+
+        let mut namespaces = self.namespaces.write().unwrap_or_else(|e| e.into_inner());
+        if namespaces.contains_key(namespace) {
+            bail!("Settings namespace '{}' is already registered", namespace);
+        }
+        namespaces.insert(namespace.to_string(), schema);
+
+        Ok(())
+    }
+
+    /// Registers a namespace from a JSON object in `config_schema` format.
+    pub fn register_json(&self, namespace: &str, schema: &str) -> Result<()> {
+        let schema: SettingsSchema = serde_json::from_str(schema)
+            .map_err(|e| anyhow!("Invalid schema for settings namespace '{}': {}", namespace, e))?;
+        self.register(namespace, schema)
+    }
+
+    /// Registers each module's namespace. A module whose schema is rejected
+    /// is logged and skipped, and does not keep the others from registering.
+    pub fn register_modules(&self, modules: &[ModuleSettings]) {
+        for module in modules {
+            if let Err(e) = self.register_json(module.namespace, module.schema) {
+                tracing::warn!("Failed to register settings for module '{}': {}", module.namespace, e);
+            }
+        }
+    }
+
+    /// All registered namespaces and their schemas.
+    pub fn schemas(&self) -> BTreeMap<String, SettingsSchema> {
+        self.namespaces.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Validates the namespaces `patch` touches, as they stand in `data`
+    /// after the patch was applied.
+    ///
+    /// Untouched namespaces are not checked, so a schema change does not
+    /// block writes to unrelated settings. Keys that are not registered
+    /// namespaces are left alone.
+    pub fn validate(&self, data: &Value, patch: &Value) -> Result<(), Vec<FieldError>> {
+        let namespaces = self.namespaces.read().unwrap_or_else(|e| e.into_inner());
+        let mut errors = Vec::new();
+
+        let touched = patch.as_object().into_iter().flat_map(|patch| patch.keys());
+        for namespace in touched {
+            let (Some(schema), Some(value)) = (namespaces.get(namespace), data.get(namespace)) else {
+                continue;
+            };
+            let Some(values) = value.as_object() else {
+                errors.push(FieldError {
+                    field: namespace.clone(),
+                    message: "must be an object".to_string(),
+                });
+                continue;
+            };
+
+            for key in values.keys().filter(|key| !schema.contains_key(*key)) {
+                errors.push(FieldError {
+                    field: format!("{}.{}", namespace, key),
+                    message: "is not a known setting".to_string(),
+                });
+            }
+            for (key, field) in schema {
+                let message = match values.get(key) {
+                    Some(value) => field.check(value),
+                    None if field.required && field.default.is_none() => Some("is required".to_string()),
+                    None => None,
+                };
+                if let Some(message) = message {
+                    errors.push(FieldError {
+                        field: format!("{}.{}", namespace, key),
+                        message,
+                    });
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Fills in the defaults of every registered namespace that `data` does
+    /// not set.
+    pub fn apply_defaults(&self, data: &mut Value) {
+        let Value::Object(data) = data else {
+            return;
+        };
+
+        for (namespace, schema) in self.namespaces.read().unwrap_or_else(|e| e.into_inner()).iter() {
+            let defaults: Map<String, Value> = schema
+                .iter()
+                .filter_map(|(key, field)| field.default.clone().map(|default| (key.clone(), default)))
+                .collect();
+            if defaults.is_empty() {
+                continue;
+            }
+
+            let entry = data
+                .entry(namespace.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(values) = entry {
+                for (key, default) in defaults {
+                    values.entry(key).or_insert(default);
+                }
+            }
+        }
+    }
+}
+
+static SETTINGS_REGISTRY: Lazy<SettingsRegistry> = Lazy::new(SettingsRegistry::default);
+
+/// The process-wide registry used by the settings commands.
+pub fn settings_registry() -> &'static SettingsRegistry {
+    &SETTINGS_REGISTRY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn editor_registry() -> SettingsRegistry {
+        let registry = SettingsRegistry::new();
+        registry
+            .register_json(
+                "editor",
+                r#"{
+                    "tabSize": { "field_type": "integer", "default": 4, "min": 1, "max": 8 },
+                    "font": { "field_type": "string", "pattern": "^[A-Za-z ]+$", "max": 32 },
+                    "mode": { "field_type": "string", "default": "normal", "enum_values": ["normal", "vim"] },
+                    "license": { "field_type": "string", "required": true }
+                }"#,
+            )
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn registration_checks_the_schema() {
+        let registry = editor_registry();
+
+        let taken = registry.register("editor", SettingsSchema::new());
+        assert!(taken.unwrap_err().to_string().contains("already registered"));
+        let reserved = registry.register("notifications", SettingsSchema::new());
+        assert!(reserved.unwrap_err().to_string().contains("reserved"));
+        assert!(registry.register("Bad Name", SettingsSchema::new()).is_err());
+
+        let bad_default = registry.register_json("sync", r#"{ "interval": { "field_type": "integer", "default": "soon" } }"#);
+        assert_eq!(
+            bad_default.unwrap_err().to_string(),
+            "Invalid default for sync.interval: must be an integer"
+        );
+        let bad_pattern = registry.register_json("theme", r#"{ "accent": { "field_type": "string", "pattern": "(" } }"#);
+        assert!(bad_pattern.unwrap_err().to_string().starts_with("Invalid pattern for theme.accent"));
+    }
+
+    #[test]
+    fn touched_namespaces_are_validated_field_by_field() {
+        let registry = editor_registry();
+        let patch = json!({ "editor": {} });
+
+        let data = json!({
+            "editor": { "tabSize": 12, "font": "Fira<Code>", "mode": "emacs", "wrap": true },
+            "other": "anything"
+        });
+        let errors = registry.validate(&data, &patch).unwrap_err();
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "editor.wrap is not a known setting",
+                "editor.font must match ^[A-Za-z ]+$",
+                "editor.license is required",
+                "editor.mode must be one of: \"normal\", \"vim\"",
+                "editor.tabSize must be at most 8",
+            ]
+        );
+
+        let valid = json!({ "editor": { "tabSize": 2, "license": "MIT" } });
+        assert_eq!(registry.validate(&valid, &patch), Ok(()));
+        // Only namespaces in the patch are checked.
+        assert_eq!(registry.validate(&data, &json!({ "other": 1 })), Ok(()));
+        assert!(registry.validate(&json!({ "editor": 1 }), &patch).is_err());
+    }
+
+    #[test]
+    fn defaults_fill_in_unset_fields() {
+        let registry = editor_registry();
+        let mut data = json!({ "editor": { "tabSize": 2 } });
+
+        registry.apply_defaults(&mut data);
+        assert_eq!(data, json!({ "editor": { "tabSize": 2, "mode": "normal" } }));
+    }
+
+    #[test]
+    fn modules_register_independently() {
+        let registry = SettingsRegistry::new();
+        registry.register_modules(MODULE_SETTINGS);
+        registry.register_modules(&[
+            ModuleSettings {
+                namespace: "broken",
+                schema: r#"{ "size": { "field_type": "integer", "default": "big" } }"#,
+            },
+            ModuleSettings {
+                namespace: "reminders",
+                schema: r#"{ "enabled": { "field_type": "boolean", "default": true } }"#,
+            },
+        ]);
+
+        let schemas = registry.schemas();
+        assert!(!schemas.contains_key("broken"));
+        assert_eq!(schemas["reminders"]["enabled"].default, Some(json!(true)));
+        assert_eq!(schemas.len(), MODULE_SETTINGS.len() + 1);
+    }
+}
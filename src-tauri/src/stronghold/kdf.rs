@@ -0,0 +1,221 @@
+//! Key derivation for Stronghold snapshots.
+//!
+//! Each vault gets a random salt, saved with its Argon2 parameters in a
+//! `*.kdf.json` file next to the snapshot. Vaults created before this existed
+//! used an all-zero salt and the default parameters; [`KeyDerivation::legacy`]
+//! describes them so they can be opened once and re-keyed.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::Error;
+
+/// Length of generated salts and derived keys, in bytes.
+const SALT_LEN: usize = 32;
+const KEY_LEN: usize = 32;
+
+/// Argon2id cost parameters used to derive a snapshot key from a password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Reads `STRONGHOLD_ARGON2_MEMORY_KIB`, `STRONGHOLD_ARGON2_ITERATIONS` and
+    /// `STRONGHOLD_ARGON2_PARALLELISM`, keeping the defaults for unset or
+    /// unparseable values. Costs Argon2 rejects fall back to the defaults.
+    pub fn from_env() -> Self {
+        let parse = |key: &str| std::env::var(key).ok().and_then(|value| value.trim().parse().ok());
+        let defaults = Self::default();
+
+        let params = Self {
+            memory_kib: parse("STRONGHOLD_ARGON2_MEMORY_KIB").unwrap_or(defaults.memory_kib),
+            iterations: parse("STRONGHOLD_ARGON2_ITERATIONS").unwrap_or(defaults.iterations),
+            parallelism: parse("STRONGHOLD_ARGON2_PARALLELISM").unwrap_or(defaults.parallelism),
+        };
+        match params.validate() {
+            Ok(()) => params,
+            Err(e) => {
+                tracing::warn!("Invalid Stronghold Argon2 settings, using the defaults: {}", e);
+                defaults
+            }
+        }
+    }
+
+    /// Checks that Argon2 accepts these costs.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.argon2().map(|_| ())
+    }
+
+    fn argon2(&self) -> Result<Argon2<'static>, Error> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(KEY_LEN))
+            .map_err(|e| Error::KeyDerivation(e.to_string()))?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+/// Salt and parameters a snapshot key was derived with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyDerivation {
+    #[serde(with = "hex")]
+    pub salt: Vec<u8>,
+    pub params: KdfParams,
+}
+
+impl KeyDerivation {
+    /// Creates a derivation with a fresh random salt.
+    pub fn generate(params: KdfParams) -> Self {
+        let mut salt = vec![0u8; SALT_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        Self { salt, params }
+    }
+
+    /// The fixed derivation used by vaults created without a salt file.
+    pub fn legacy() -> Self {
+        Self {
+            salt: vec![0u8; SALT_LEN],
+            params: KdfParams::default(),
+        }
+    }
+
+    /// Reads the derivation saved at `path`, if the file exists.
+    pub fn load(path: &Path) -> Result<Option<Self>, Error> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents = std::fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&contents)?))
+    }
+
+    /// Reads the derivation at `path`, generating and saving one if missing.
+    pub fn load_or_create(path: &Path, params: KdfParams) -> Result<Self, Error> {
+        if let Some(existing) = Self::load(path)? {
+            return Ok(existing);
+        }
+
+        let created = Self::generate(params);
+        created.save(path)?;
+        Ok(created)
+    }
+
+    /// Writes the derivation to `path` through a temporary file.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let temp_path = with_suffix(path, ".tmp");
+        std::fs::write(&temp_path, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// Derives the snapshot key for `password`.
+    pub fn derive_key(&self, password: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut output = vec![0u8; KEY_LEN];
+        self.params
+            .argon2()?
+            .hash_password_into(password, &self.salt, &mut output)
+            .map_err(|e| Error::KeyDerivation(e.to_string()))?;
+        Ok(output)
+    }
+}
+
+/// Location of the key derivation file belonging to `snapshot_path`.
+pub fn kdf_path(snapshot_path: &Path) -> PathBuf {
+    snapshot_path.with_extension("kdf.json")
+}
+
+/// Returns `path` with `suffix` appended to its file name.
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast_params() -> KdfParams {
+        KdfParams {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+
+    #[test]
+    fn generated_salts_give_distinct_keys() -> Result<(), Error> {
+        let first = KeyDerivation::generate(fast_params());
+        let second = KeyDerivation::generate(fast_params());
+
+        assert_ne!(first.salt, second.salt);
+        assert_ne!(first.derive_key(b"password")?, second.derive_key(b"password")?);
+        assert_eq!(first.derive_key(b"password")?, first.derive_key(b"password")?);
+        Ok(())
+    }
+
+    #[test]
+    fn derivation_round_trips_through_its_file() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let path = kdf_path(&dir.path().join("vault.hold"));
+        assert!(path.ends_with("vault.kdf.json"));
+
+        let created = KeyDerivation::load_or_create(&path, fast_params())?;
+        let loaded = KeyDerivation::load_or_create(&path, KdfParams::default())?;
+
+        assert_eq!(created, loaded);
+        assert_eq!(loaded.params, fast_params());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_params_are_rejected() {
+        let derivation = KeyDerivation::generate(KdfParams {
+            memory_kib: 1,
+            iterations: 0,
+            parallelism: 1,
+        });
+
+        assert!(matches!(derivation.derive_key(b"password"), Err(Error::KeyDerivation(_))));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn invalid_env_params_fall_back_to_defaults() {
+        std::env::set_var("STRONGHOLD_ARGON2_ITERATIONS", "0");
+        let params = KdfParams::from_env();
+        std::env::remove_var("STRONGHOLD_ARGON2_ITERATIONS");
+
+        assert_eq!(params, KdfParams::default());
+    }
+
+    #[test]
+    fn legacy_derivation_matches_previous_key() -> Result<(), Error> {
+        let mut expected = [0u8; KEY_LEN];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default())
+            .hash_password_into(b"password", &[0; 32], &mut expected)
+            .unwrap();
+
+        assert_eq!(KeyDerivation::legacy().derive_key(b"password")?, expected.to_vec());
+        Ok(())
+    }
+}
@@ -0,0 +1,536 @@
+//! Stronghold integration for secure data storage.
+//!
+//! Provides a wrapper around Tauri's Stronghold plugin for managing
+//! encrypted storage of sensitive application data.
+
+use iota_stronghold::{KeyProvider, SnapshotPath, Store};
+use once_cell::sync::OnceCell;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use tauri_plugin_stronghold::stronghold::Stronghold;
+use thiserror::Error;
+use tokio::sync::Mutex;
+use zeroize::Zeroizing;
+
+pub mod kdf;
+pub mod plugin;
+
+pub use kdf::{KdfParams, KeyDerivation};
+pub use plugin::PluginKeys;
+
+/// Snapshot file holding the application vault, inside the app data directory.
+pub const VAULT_FILE: &str = "vault.hold";
+
+/// File the generated vault password is kept in when no OS keychain is available.
+pub const VAULT_KEY_FILE: &str = "vault.key";
+
+/// OS keychain service and account holding the generated vault password.
+pub const KEYCHAIN_SERVICE: &str = APP_IDENTIFIER;
+const KEYCHAIN_ACCOUNT: &str = "vault";
+
+/// Key derivation file for vaults opened through the Stronghold plugin from the frontend.
+pub const PLUGIN_KDF_FILE: &str = "stronghold.kdf.json";
+
+/// Bundle identifier from `tauri.conf.json`, which names the frontend's app
+/// data directories.
+const APP_IDENTIFIER: &str = "com.tavuc.eztauri";
+
+/// Errors that can occur during Stronghold operations.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Tauri(#[from] tauri::Error),
+    #[error(transparent)]
+    Stronghold(#[from] tauri_plugin_stronghold::stronghold::Error),
+    #[error(transparent)]
+    Client(#[from] iota_stronghold::ClientError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("Key derivation failed: {0}")]
+    KeyDerivation(String),
+    #[error("Stronghold vault is not initialized")]
+    NotInitialized,
+    #[error("Invalid {0} name: must be 1-128 characters")]
+    InvalidName(&'static str),
+}
+
+/// Longest accepted client or key name.
+const MAX_NAME_LEN: usize = 128;
+
+/// Wrapper around Stronghold for managing encrypted storage operations.
+pub struct StrongholdManager {
+    stronghold: Stronghold,
+    /// Clients opened since the last save, written back on [`StrongholdManager::save`].
+    clients: std::sync::Mutex<BTreeSet<Vec<u8>>>,
+}
+
+impl StrongholdManager {
+    /// Creates a new StrongholdManager with the given Stronghold instance.
+    pub fn new(stronghold: Stronghold) -> Self {
+        Self {
+            stronghold,
+            clients: std::sync::Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Opens (or creates) the snapshot at `snapshot_path` with Argon2 costs from the environment.
+    pub fn open(snapshot_path: impl AsRef<Path>, password: &str) -> Result<Self, Error> {
+        Self::open_with_params(snapshot_path, password, KdfParams::from_env())
+    }
+
+    /// Opens (or creates) the snapshot at `snapshot_path`, deriving its key from `password`.
+    ///
+    /// New vaults get a random salt saved next to the snapshot. Vaults without a
+    /// salt file, or whose saved costs differ from `params`, are re-keyed.
+    pub fn open_with_params(
+        snapshot_path: impl AsRef<Path>,
+        password: &str,
+        params: KdfParams,
+    ) -> Result<Self, Error> {
+        let snapshot_path = snapshot_path.as_ref();
+        let kdf_path = kdf::kdf_path(snapshot_path);
+        finish_interrupted_rekey(snapshot_path)?;
+
+        let derivation = match KeyDerivation::load(&kdf_path)? {
+            Some(derivation) => derivation,
+            None if snapshot_path.exists() => KeyDerivation::legacy(),
+            None => KeyDerivation::load_or_create(&kdf_path, params)?,
+        };
+
+        let key = derivation.derive_key(password.as_bytes())?;
+        let manager = Self::new(Stronghold::new(snapshot_path, key)?);
+
+        if !kdf_path.exists() || derivation.params != params {
+            tracing::info!("Re-keying Stronghold vault {}", snapshot_path.display());
+            return manager.rekey(snapshot_path, password, KeyDerivation::generate(params));
+        }
+
+        Ok(manager)
+    }
+
+    /// Re-encrypts the snapshot with a key derived through `derivation`.
+    ///
+    /// The new snapshot and derivation file are written next to the current
+    /// ones and then moved into place, so an interrupted re-key leaves a vault
+    /// that still opens.
+    pub fn rekey(
+        self,
+        snapshot_path: &Path,
+        password: &str,
+        derivation: KeyDerivation,
+    ) -> Result<Self, Error> {
+        let key = derivation.derive_key(password.as_bytes())?;
+        let kdf_path = kdf::kdf_path(snapshot_path);
+        let pending_snapshot = kdf::with_suffix(snapshot_path, REKEY_SUFFIX);
+        let pending_kdf = kdf::with_suffix(&kdf_path, REKEY_SUFFIX);
+
+        let keyprovider = KeyProvider::try_from(Zeroizing::new(key.clone()))
+            .map_err(|e| Error::KeyDerivation(format!("{:?}", e)))?;
+        self.stronghold
+            .inner()
+            .commit_with_keyprovider(&SnapshotPath::from_path(&pending_snapshot), &keyprovider)?;
+        derivation.save(&pending_kdf)?;
+        drop(self);
+
+        std::fs::rename(&pending_snapshot, snapshot_path)?;
+        std::fs::rename(&pending_kdf, &kdf_path)?;
+
+        Ok(Self::new(Stronghold::new(snapshot_path, key)?))
+    }
+
+    /// Returns a reference to the underlying Stronghold instance.
+    pub fn stronghold(&self) -> &Stronghold {
+        &self.stronghold
+    }
+
+    /// Returns a mutable reference to the underlying Stronghold instance.
+    pub fn stronghold_mut(&mut self) -> &mut Stronghold {
+        &mut self.stronghold
+    }
+
+    /// Stores `value` under `key` in the `client` store, replacing any previous value.
+    ///
+    /// Changes stay in memory until [`save`](Self::save) is called.
+    pub fn put_secret(&self, client: &str, key: &str, value: Vec<u8>) -> Result<(), Error> {
+        validate_name("key", key)?;
+        self.store(client)?.insert(key.as_bytes().to_vec(), value, None)?;
+        Ok(())
+    }
+
+    /// Returns the value stored under `key` in the `client` store.
+    pub fn get_secret(&self, client: &str, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        validate_name("key", key)?;
+        Ok(self.store(client)?.get(key.as_bytes())?)
+    }
+
+    /// Removes `key` from the `client` store, returning whether it existed.
+    pub fn delete_secret(&self, client: &str, key: &str) -> Result<bool, Error> {
+        validate_name("key", key)?;
+        Ok(self.store(client)?.delete(key.as_bytes())?.is_some())
+    }
+
+    /// Returns the keys in the `client` store, sorted.
+    pub fn list_keys(&self, client: &str) -> Result<Vec<String>, Error> {
+        let mut keys: Vec<String> = self
+            .store(client)?
+            .keys()?
+            .into_iter()
+            .map(|key| String::from_utf8_lossy(&key).into_owned())
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// Writes every client opened since the last save into the snapshot and
+    /// saves it to disk.
+    pub fn save(&self) -> Result<(), Error> {
+        let clients = std::mem::take(&mut *self.opened_clients());
+
+        for client in &clients {
+            self.stronghold.inner().write_client(client.as_slice())?;
+        }
+        self.stronghold.save()?;
+
+        Ok(())
+    }
+
+    /// Returns the key/value store of `client`, loading it from the snapshot
+    /// or creating it on first use.
+    fn store(&self, client: &str) -> Result<Store, Error> {
+        validate_name("client", client)?;
+        let stronghold = self.stronghold.inner();
+        let path = client.as_bytes();
+
+        let client = match stronghold.get_client(path) {
+            Ok(client) => client,
+            Err(_) => match stronghold.load_client(path) {
+                Ok(client) => client,
+                Err(_) => stronghold.create_client(path)?,
+            },
+        };
+        self.opened_clients().insert(path.to_vec());
+
+        Ok(client.store())
+    }
+
+    fn opened_clients(&self) -> std::sync::MutexGuard<'_, BTreeSet<Vec<u8>>> {
+        self.clients.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn validate_name(kind: &'static str, name: &str) -> Result<(), Error> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(Error::InvalidName(kind));
+    }
+    Ok(())
+}
+
+/// Suffix of the files a re-key writes before moving them into place.
+const REKEY_SUFFIX: &str = ".rekey";
+
+/// Completes or discards a re-key that stopped before both files were moved.
+///
+/// A pending snapshot means nothing was replaced yet, so the leftovers are
+/// removed. A pending derivation file alone means the new snapshot is already
+/// in place and only its derivation file still has to be moved.
+pub(crate) fn finish_interrupted_rekey(snapshot_path: &Path) -> Result<(), Error> {
+    let kdf_path = kdf::kdf_path(snapshot_path);
+    let pending_snapshot = kdf::with_suffix(snapshot_path, REKEY_SUFFIX);
+    let pending_kdf = kdf::with_suffix(&kdf_path, REKEY_SUFFIX);
+
+    if pending_snapshot.exists() {
+        std::fs::remove_file(&pending_snapshot)?;
+        if pending_kdf.exists() {
+            std::fs::remove_file(&pending_kdf)?;
+        }
+    } else if pending_kdf.exists() {
+        std::fs::rename(&pending_kdf, &kdf_path)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the password protecting the application vault.
+///
+/// Uses `STRONGHOLD_PASSWORD` when set; otherwise a random password is
+/// generated on first run and kept in the OS keychain. A `vault.key` left by
+/// an earlier version is moved into the keychain.
+///
+/// Without a usable keychain the password stays in `vault.key` next to the
+/// snapshot. That file is plaintext: it is created with mode 0600 on unix,
+/// and on Windows relies on the app data directory being readable only by
+/// the current user, so anyone who can read the user's files can open the
+/// vault.
+pub fn vault_password(data_dir: &Path) -> Result<String, Error> {
+    if let Ok(password) = std::env::var("STRONGHOLD_PASSWORD") {
+        if !password.is_empty() {
+            return Ok(password);
+        }
+    }
+
+    let keychain = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)
+        .map_err(|e| tracing::warn!("OS keychain is unavailable: {}", e))
+        .ok();
+    vault_password_in(data_dir, keychain.as_ref())
+}
+
+fn vault_password_in(data_dir: &Path, keychain: Option<&keyring::Entry>) -> Result<String, Error> {
+    let key_path = data_dir.join(VAULT_KEY_FILE);
+    let Some(keychain) = keychain else {
+        return key_file_password(&key_path);
+    };
+
+    match keychain.get_password() {
+        Ok(password) => Ok(password),
+        Err(keyring::Error::NoEntry) => {
+            let password = match read_key_file(&key_path)? {
+                Some(password) => password,
+                None => generate_password(),
+            };
+            match keychain.set_password(&password) {
+                Ok(()) => {
+                    if key_path.exists() {
+                        std::fs::remove_file(&key_path)?;
+                    }
+                    Ok(password)
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to store the vault password in the OS keychain, using {}: {}", VAULT_KEY_FILE, e);
+                    if !key_path.exists() {
+                        std::fs::create_dir_all(data_dir)?;
+                        write_private_file(&key_path, password.as_bytes())?;
+                    }
+                    Ok(password)
+                }
+            }
+        }
+        Err(e) => {
+            tracing::warn!("Failed to read the vault password from the OS keychain, using {}: {}", VAULT_KEY_FILE, e);
+            key_file_password(&key_path)
+        }
+    }
+}
+
+/// Reads the password in `key_path`, generating and writing one if missing.
+fn key_file_password(key_path: &Path) -> Result<String, Error> {
+    if let Some(password) = read_key_file(key_path)? {
+        return Ok(password);
+    }
+
+    let password = generate_password();
+    if let Some(parent) = key_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_private_file(key_path, password.as_bytes())?;
+    Ok(password)
+}
+
+fn read_key_file(key_path: &Path) -> Result<Option<String>, Error> {
+    if !key_path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(std::fs::read_to_string(key_path)?.trim().to_string()))
+}
+
+fn generate_password() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(64)
+        .map(char::from)
+        .collect()
+}
+
+#[cfg(unix)]
+fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(contents)
+}
+
+#[cfg(not(unix))]
+fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    std::fs::write(path, contents)
+}
+
+/// Directories the frontend keeps plugin snapshots in: `data_dir` and the
+/// app data and local data directories Tauri resolves for the frontend.
+pub fn plugin_snapshot_dirs(data_dir: &Path) -> Vec<std::path::PathBuf> {
+    let mut dirs = vec![data_dir.to_path_buf()];
+    if let Some(base) = directories::BaseDirs::new() {
+        dirs.push(base.data_dir().join(APP_IDENTIFIER));
+        dirs.push(base.data_local_dir().join(APP_IDENTIFIER));
+    }
+    dirs.dedup();
+    dirs
+}
+
+/// Process-wide vault used by backend code that has no access to Tauri state.
+static VAULT: OnceCell<RwLock<Option<Arc<Mutex<StrongholdManager>>>>> = OnceCell::new();
+
+fn vault_slot() -> &'static RwLock<Option<Arc<Mutex<StrongholdManager>>>> {
+    VAULT.get_or_init(|| RwLock::new(None))
+}
+
+/// Opens the application vault in `data_dir` and installs it as the global vault.
+pub fn initialize_vault(data_dir: &Path) -> Result<(), Error> {
+    let password = vault_password(data_dir)?;
+    let manager = StrongholdManager::open(data_dir.join(VAULT_FILE), &password)?;
+    install_vault(manager);
+    Ok(())
+}
+
+/// Installs `manager` as the global vault, replacing any previous one.
+pub fn install_vault(manager: StrongholdManager) {
+    if let Ok(mut guard) = vault_slot().write() {
+        *guard = Some(Arc::new(Mutex::new(manager)));
+    }
+}
+
+/// Returns the global vault, or [`Error::NotInitialized`] before [`initialize_vault`].
+pub fn vault() -> Result<Arc<Mutex<StrongholdManager>>, Error> {
+    vault_slot()
+        .read()
+        .ok()
+        .and_then(|guard| guard.as_ref().cloned())
+        .ok_or(Error::NotInitialized)
+}
+
+/// Removes the global vault for testing purposes.
+#[cfg(test)]
+pub fn reset_vault_for_tests() {
+    if let Ok(mut guard) = vault_slot().write() {
+        *guard = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast_params() -> KdfParams {
+        KdfParams {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+
+    fn put(manager: &StrongholdManager, value: &[u8]) -> Result<(), Error> {
+        manager.put_secret("test", "key", value.to_vec())?;
+        manager.save()
+    }
+
+    fn get(manager: &StrongholdManager) -> Result<Option<Vec<u8>>, Error> {
+        manager.get_secret("test", "key")
+    }
+
+    #[test]
+    fn new_vault_gets_random_salt() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let snapshot = dir.path().join(VAULT_FILE);
+
+        let manager = StrongholdManager::open_with_params(&snapshot, "password", fast_params())?;
+        put(&manager, b"secret")?;
+        drop(manager);
+
+        let derivation = KeyDerivation::load(&kdf::kdf_path(&snapshot))?.expect("salt file should exist");
+        assert_ne!(derivation.salt, KeyDerivation::legacy().salt);
+
+        let reopened = StrongholdManager::open_with_params(&snapshot, "password", fast_params())?;
+        assert_eq!(get(&reopened)?, Some(b"secret".to_vec()));
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_vault_is_rekeyed_on_open() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let snapshot = dir.path().join(VAULT_FILE);
+
+        let legacy_key = KeyDerivation::legacy().derive_key(b"password")?;
+        let legacy = StrongholdManager::new(Stronghold::new(&snapshot, legacy_key.clone())?);
+        put(&legacy, b"secret")?;
+        drop(legacy);
+
+        let migrated = StrongholdManager::open_with_params(&snapshot, "password", fast_params())?;
+        assert_eq!(get(&migrated)?, Some(b"secret".to_vec()));
+        drop(migrated);
+
+        let derivation = KeyDerivation::load(&kdf::kdf_path(&snapshot))?.expect("salt file should exist");
+        assert_eq!(derivation.params, fast_params());
+        assert!(Stronghold::new(&snapshot, legacy_key).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn changed_params_trigger_rekey() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let snapshot = dir.path().join(VAULT_FILE);
+
+        let manager = StrongholdManager::open_with_params(&snapshot, "password", fast_params())?;
+        put(&manager, b"secret")?;
+        drop(manager);
+
+        let stronger = KdfParams {
+            iterations: 2,
+            ..fast_params()
+        };
+        let rekeyed = StrongholdManager::open_with_params(&snapshot, "password", stronger)?;
+        assert_eq!(get(&rekeyed)?, Some(b"secret".to_vec()));
+        assert_eq!(
+            KeyDerivation::load(&kdf::kdf_path(&snapshot))?.map(|d| d.params),
+            Some(stronger)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn key_file_is_used_without_a_keychain() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+
+        let password = vault_password_in(dir.path(), None)?;
+        assert_eq!(password.len(), 64);
+        assert_eq!(vault_password_in(dir.path(), None)?, password);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join(VAULT_KEY_FILE))?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn secret_crud_round_trip() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let snapshot = dir.path().join(VAULT_FILE);
+
+        let manager = StrongholdManager::open_with_params(&snapshot, "password", fast_params())?;
+        manager.put_secret("api", "github", b"token-1".to_vec())?;
+        manager.put_secret("api", "gitlab", b"token-2".to_vec())?;
+        manager.put_secret("other", "github", b"unrelated".to_vec())?;
+        assert!(manager.delete_secret("api", "gitlab")?);
+        assert!(!manager.delete_secret("api", "gitlab")?);
+        manager.save()?;
+        drop(manager);
+
+        let reopened = StrongholdManager::open_with_params(&snapshot, "password", fast_params())?;
+        assert_eq!(reopened.list_keys("api")?, vec!["github".to_string()]);
+        assert_eq!(reopened.get_secret("api", "github")?, Some(b"token-1".to_vec()));
+        assert_eq!(reopened.get_secret("other", "github")?, Some(b"unrelated".to_vec()));
+        assert!(matches!(reopened.get_secret("", "github"), Err(Error::InvalidName("client"))));
+        assert!(matches!(reopened.put_secret("api", "", Vec::new()), Err(Error::InvalidName("key"))));
+        Ok(())
+    }
+}
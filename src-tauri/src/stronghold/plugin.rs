@@ -0,0 +1,181 @@
+//! Keys for vaults the frontend opens through the Stronghold plugin.
+//!
+//! The plugin only passes the password to its hash function, so every plugin
+//! vault shares the derivation saved in [`PLUGIN_KDF_FILE`].
+//! Snapshots created before that file existed were encrypted with the legacy
+//! zero-salt key. Whenever the frontend supplies a password, each such
+//! snapshot that opens with the legacy key for it is re-keyed to the shared
+//! derivation, which also writes its own `*.kdf.json` to mark it as migrated.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri_plugin_stronghold::stronghold::Stronghold;
+
+use super::{
+    finish_interrupted_rekey, kdf, Error, KdfParams, KeyDerivation, StrongholdManager, PLUGIN_KDF_FILE, VAULT_FILE,
+};
+
+/// File extensions of Stronghold snapshots.
+const SNAPSHOT_EXTENSIONS: [&str; 2] = ["hold", "stronghold"];
+
+/// Key derivation handed to the Stronghold plugin.
+pub struct PluginKeys {
+    derivation: KeyDerivation,
+    /// Snapshots still encrypted with the legacy key.
+    legacy_snapshots: Mutex<Vec<PathBuf>>,
+}
+
+impl PluginKeys {
+    /// Loads the shared derivation from `data_dir`, creating it with `params`
+    /// if missing, and looks for legacy snapshots in `snapshot_dirs`.
+    pub fn load(data_dir: &Path, params: KdfParams, snapshot_dirs: &[PathBuf]) -> Result<Self, Error> {
+        let derivation = KeyDerivation::load_or_create(&data_dir.join(PLUGIN_KDF_FILE), params)?;
+        derivation.params.validate()?;
+
+        let legacy_snapshots = legacy_snapshots(snapshot_dirs, &data_dir.join(VAULT_FILE))?;
+        if !legacy_snapshots.is_empty() {
+            tracing::info!(
+                "{} Stronghold snapshot(s) use the legacy key and will be re-keyed when next unlocked",
+                legacy_snapshots.len()
+            );
+        }
+
+        Ok(Self {
+            derivation,
+            legacy_snapshots: Mutex::new(legacy_snapshots),
+        })
+    }
+
+    /// Derives the key for `password`, first re-keying any legacy snapshot
+    /// that `password` unlocks.
+    pub fn derive_key(&self, password: &str) -> Result<Vec<u8>, Error> {
+        self.migrate_legacy_snapshots(password);
+        self.derivation.derive_key(password.as_bytes())
+    }
+
+    fn migrate_legacy_snapshots(&self, password: &str) {
+        let mut pending = self.legacy_snapshots.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.is_empty() {
+            return;
+        }
+
+        let legacy_key = match KeyDerivation::legacy().derive_key(password.as_bytes()) {
+            Ok(key) => key,
+            Err(e) => {
+                tracing::warn!("Failed to derive the legacy Stronghold key: {}", e);
+                return;
+            }
+        };
+
+        pending.retain(|snapshot| {
+            // A different password, or a snapshot the legacy key does not open.
+            let Ok(stronghold) = Stronghold::new(snapshot, legacy_key.clone()) else {
+                return true;
+            };
+            match StrongholdManager::new(stronghold).rekey(snapshot, password, self.derivation.clone()) {
+                Ok(_) => {
+                    tracing::info!("Re-keyed Stronghold snapshot {}", snapshot.display());
+                    false
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to re-key Stronghold snapshot {}: {}", snapshot.display(), e);
+                    true
+                }
+            }
+        });
+    }
+}
+
+/// Snapshots directly inside `dirs` that have no derivation file. The
+/// application vault at `app_vault` is skipped, as it is re-keyed when it is
+/// opened.
+fn legacy_snapshots(dirs: &[PathBuf], app_vault: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut snapshots = Vec::new();
+
+    for dir in dirs.iter().filter(|dir| dir.is_dir()) {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_snapshot = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| SNAPSHOT_EXTENSIONS.contains(&extension));
+            if !is_snapshot || !path.is_file() || path == app_vault {
+                continue;
+            }
+
+            finish_interrupted_rekey(&path)?;
+            if !kdf::kdf_path(&path).exists() && !snapshots.contains(&path) {
+                snapshots.push(path);
+            }
+        }
+    }
+
+    Ok(snapshots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use argon2::{Algorithm, Argon2, Params, Version};
+
+    fn fast_params() -> KdfParams {
+        KdfParams {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+
+    /// The key the plugin derived before vaults had salts.
+    fn baseline_key(password: &str) -> Vec<u8> {
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default());
+        let mut output = [0u8; 32];
+        argon2
+            .hash_password_into(password.as_bytes(), &[0; 32], &mut output)
+            .unwrap();
+        output.to_vec()
+    }
+
+    #[test]
+    fn baseline_snapshots_are_rekeyed_when_unlocked() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let snapshot = dir.path().join("frontend.hold");
+        let other = dir.path().join("other.stronghold");
+        for (path, password) in [(&snapshot, "password"), (&other, "another password")] {
+            let manager = StrongholdManager::new(Stronghold::new(path, baseline_key(password))?);
+            manager.put_secret("frontend", "token", b"secret".to_vec())?;
+            manager.save()?;
+        }
+
+        // The application vault has its own key and is left alone.
+        std::fs::write(dir.path().join(VAULT_FILE), b"not a plugin vault")?;
+
+        let keys = PluginKeys::load(dir.path(), fast_params(), &[dir.path().to_path_buf()])?;
+        let key = keys.derive_key("password")?;
+
+        let reopened = StrongholdManager::new(Stronghold::new(&snapshot, key)?);
+        assert_eq!(reopened.get_secret("frontend", "token")?, Some(b"secret".to_vec()));
+        assert!(kdf::kdf_path(&snapshot).exists());
+        // A snapshot with another password waits for that password.
+        assert_eq!(*keys.legacy_snapshots.lock().unwrap(), vec![other.clone()]);
+        assert!(Stronghold::new(&other, baseline_key("another password")).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_saved_params_are_rejected() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        KeyDerivation::generate(KdfParams {
+            memory_kib: 1,
+            iterations: 0,
+            parallelism: 1,
+        })
+        .save(&dir.path().join(PLUGIN_KDF_FILE))?;
+
+        assert!(matches!(
+            PluginKeys::load(dir.path(), fast_params(), &[]),
+            Err(Error::KeyDerivation(_))
+        ));
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use regex::Regex;
+use std::collections::HashSet;
 use std::sync::LazyLock;
 
 /// Input validation utilities for preventing security vulnerabilities.
@@ -15,6 +16,14 @@ static USERNAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^[a-zA-Z0-9_]{3,50}$").unwrap()
 });
 
+/// Language tag regex pattern, such as `en` or `pt-BR`.
+static LANGUAGE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[a-z]{2,3}(-[A-Z]{2})?$").unwrap()
+});
+
+/// Themes the UI can render.
+pub const THEMES: &[&str] = &["light", "dark", "system"];
+
 /// Name validation regex pattern (letters, spaces, apostrophes, hyphens).
 static NAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^[a-zA-Z\s'-]{1,100}$").unwrap()
@@ -33,8 +42,17 @@ static DANGEROUS_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
     ]
 });
 
+/// Passwords rejected regardless of policy, lowercased.
+static COMMON_PASSWORDS: LazyLock<HashSet<&'static str>> = LazyLock::new(|| {
+    include_str!("common_passwords.txt")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+});
+
 /// Validation errors that can occur during input validation.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ValidationError {
     InvalidEmail,
     InvalidUsername,
@@ -42,6 +60,44 @@ pub enum ValidationError {
     TooLong(usize),
     ContainsDangerousContent,
     Empty,
+    PasswordTooShort(usize),
+    PasswordTooLong(usize),
+    PasswordMissingCharacterClass(CharacterClass),
+    CommonPassword,
+    PasswordContainsPersonalInfo,
+    InvalidTheme,
+    InvalidLanguage,
+}
+
+/// Kinds of characters a password policy can require.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharacterClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    Symbol,
+}
+
+impl CharacterClass {
+    fn matches(self, c: char) -> bool {
+        match self {
+            CharacterClass::Lowercase => c.is_lowercase(),
+            CharacterClass::Uppercase => c.is_uppercase(),
+            CharacterClass::Digit => c.is_ascii_digit(),
+            CharacterClass::Symbol => !c.is_alphanumeric() && !c.is_whitespace(),
+        }
+    }
+}
+
+impl std::fmt::Display for CharacterClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CharacterClass::Lowercase => write!(f, "lowercase letter"),
+            CharacterClass::Uppercase => write!(f, "uppercase letter"),
+            CharacterClass::Digit => write!(f, "digit"),
+            CharacterClass::Symbol => write!(f, "symbol"),
+        }
+    }
 }
 
 impl std::fmt::Display for ValidationError {
@@ -53,6 +109,13 @@ impl std::fmt::Display for ValidationError {
             ValidationError::TooLong(max) => write!(f, "Input exceeds maximum length of {}", max),
             ValidationError::ContainsDangerousContent => write!(f, "Input contains potentially dangerous content"),
             ValidationError::Empty => write!(f, "Required field cannot be empty"),
+            ValidationError::PasswordTooShort(min) => write!(f, "Password must be at least {} characters", min),
+            ValidationError::PasswordTooLong(max) => write!(f, "Password must be at most {} characters", max),
+            ValidationError::PasswordMissingCharacterClass(class) => write!(f, "Password must contain at least one {}", class),
+            ValidationError::CommonPassword => write!(f, "Password is too common"),
+            ValidationError::PasswordContainsPersonalInfo => write!(f, "Password must not contain the username or email"),
+            ValidationError::InvalidTheme => write!(f, "Theme must be one of: {}", THEMES.join(", ")),
+            ValidationError::InvalidLanguage => write!(f, "Language must be a tag such as 'en' or 'pt-BR'"),
         }
     }
 }
@@ -126,6 +189,125 @@ pub fn validate_optional_name(name: Option<&str>) -> Result<Option<String>, Vali
     }
 }
 
+/// Rules a new password must satisfy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    /// Character classes that must each appear at least once.
+    pub required_classes: Vec<CharacterClass>,
+    /// Reject passwords on the built-in common-password list.
+    pub reject_common: bool,
+    /// Reject passwords containing the username or the email's local part.
+    pub reject_personal_info: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            required_classes: vec![
+                CharacterClass::Lowercase,
+                CharacterClass::Uppercase,
+                CharacterClass::Digit,
+            ],
+            reject_common: true,
+            reject_personal_info: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Reads `AUTH_PASSWORD_MIN_LENGTH`, `AUTH_PASSWORD_MAX_LENGTH`,
+    /// `AUTH_PASSWORD_REQUIRE_{LOWERCASE,UPPERCASE,DIGIT,SYMBOL}`,
+    /// `AUTH_PASSWORD_REJECT_COMMON` and `AUTH_PASSWORD_REJECT_PERSONAL_INFO`,
+    /// keeping the defaults for unset or unparseable values.
+    pub fn from_env() -> Self {
+        fn parse<T: std::str::FromStr>(key: &str) -> Option<T> {
+            std::env::var(key).ok().and_then(|value| value.trim().parse().ok())
+        }
+        let defaults = Self::default();
+
+        let required_classes = [
+            ("AUTH_PASSWORD_REQUIRE_LOWERCASE", CharacterClass::Lowercase),
+            ("AUTH_PASSWORD_REQUIRE_UPPERCASE", CharacterClass::Uppercase),
+            ("AUTH_PASSWORD_REQUIRE_DIGIT", CharacterClass::Digit),
+            ("AUTH_PASSWORD_REQUIRE_SYMBOL", CharacterClass::Symbol),
+        ]
+        .into_iter()
+        .filter(|(key, class)| {
+            parse(key).unwrap_or_else(|| defaults.required_classes.contains(class))
+        })
+        .map(|(_, class)| class)
+        .collect();
+
+        let min_length = parse("AUTH_PASSWORD_MIN_LENGTH")
+            .filter(|min: &usize| *min > 0)
+            .unwrap_or(defaults.min_length);
+
+        Self {
+            min_length,
+            max_length: parse("AUTH_PASSWORD_MAX_LENGTH")
+                .filter(|max: &usize| *max >= min_length)
+                .unwrap_or(defaults.max_length.max(min_length)),
+            required_classes,
+            reject_common: parse("AUTH_PASSWORD_REJECT_COMMON").unwrap_or(defaults.reject_common),
+            reject_personal_info: parse("AUTH_PASSWORD_REJECT_PERSONAL_INFO")
+                .unwrap_or(defaults.reject_personal_info),
+        }
+    }
+}
+
+/// Validate a new password against `policy`.
+///
+/// `username` and `email` belong to the account the password is for. Lengths
+/// count characters, not bytes, and unlike other fields the password is never
+/// trimmed.
+pub fn validate_password(
+    password: &str,
+    policy: &PasswordPolicy,
+    username: &str,
+    email: &str,
+) -> Result<(), ValidationError> {
+    let length = password.chars().count();
+
+    if length < policy.min_length {
+        return Err(ValidationError::PasswordTooShort(policy.min_length));
+    }
+
+    if length > policy.max_length {
+        return Err(ValidationError::PasswordTooLong(policy.max_length));
+    }
+
+    for class in &policy.required_classes {
+        if !password.chars().any(|c| class.matches(c)) {
+            return Err(ValidationError::PasswordMissingCharacterClass(*class));
+        }
+    }
+
+    let lowered = password.to_lowercase();
+
+    if policy.reject_common && COMMON_PASSWORDS.contains(lowered.as_str()) {
+        return Err(ValidationError::CommonPassword);
+    }
+
+    if policy.reject_personal_info {
+        let local_part = email.split('@').next().unwrap_or_default();
+        let contains_personal_info = [username, local_part]
+            .iter()
+            .map(|value| value.trim().to_lowercase())
+            .filter(|value| value.chars().count() >= 3)
+            .any(|value| lowered.contains(&value));
+
+        if contains_personal_info {
+            return Err(ValidationError::PasswordContainsPersonalInfo);
+        }
+    }
+
+    Ok(())
+}
+
 /// Validate log levels
 pub fn validate_log_level(level: &str) -> Result<String, ValidationError> {
     let level = level.trim().to_lowercase();
@@ -154,6 +336,28 @@ pub fn validate_log_message(message: &str) -> Result<String, ValidationError> {
     }
 }
 
+/// Validate UI themes
+pub fn validate_theme(theme: &str) -> Result<String, ValidationError> {
+    let theme = theme.trim().to_lowercase();
+
+    if THEMES.contains(&theme.as_str()) {
+        Ok(theme)
+    } else {
+        Err(ValidationError::InvalidTheme)
+    }
+}
+
+/// Validate language tags
+pub fn validate_language(language: &str) -> Result<String, ValidationError> {
+    let language = language.trim();
+
+    if LANGUAGE_REGEX.is_match(language) {
+        Ok(language.to_string())
+    } else {
+        Err(ValidationError::InvalidLanguage)
+    }
+}
+
 /// Checks if input contains potentially dangerous content patterns.
 ///
 /// Scans for common XSS and injection patterns including script tags,
@@ -222,6 +426,14 @@ mod tests {
         assert!(validate_log_message("").is_err());
     }
 
+    #[test]
+    fn test_settings_validation() {
+        assert_eq!(validate_theme(" Dark ").unwrap(), "dark");
+        assert_eq!(validate_theme("neon"), Err(ValidationError::InvalidTheme));
+        assert_eq!(validate_language("pt-BR").unwrap(), "pt-BR");
+        assert_eq!(validate_language("english"), Err(ValidationError::InvalidLanguage));
+    }
+
     #[test]
     fn test_dangerous_content_detection() {
         let dangerous_inputs = vec![
@@ -247,4 +459,43 @@ mod tests {
             assert!(check_dangerous_content(input).is_ok());
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_password_validation() {
+        let policy = PasswordPolicy::default();
+        let check = |password: &str| validate_password(password, &policy, "jane_doe", "jane.smith@example.com");
+
+        assert_eq!(check("Sup3r$ecret"), Ok(()));
+        assert_eq!(check("Sh0rt"), Err(ValidationError::PasswordTooShort(8)));
+        assert_eq!(check(&format!("Aa1{}", "x".repeat(126))), Err(ValidationError::PasswordTooLong(128)));
+        assert_eq!(
+            check("alllowercase1"),
+            Err(ValidationError::PasswordMissingCharacterClass(CharacterClass::Uppercase))
+        );
+        assert_eq!(check("Password123"), Err(ValidationError::CommonPassword));
+        assert_eq!(check("xJANE_DOE9x"), Err(ValidationError::PasswordContainsPersonalInfo));
+        assert_eq!(check("Jane.Smith42"), Err(ValidationError::PasswordContainsPersonalInfo));
+    }
+
+    #[test]
+    fn test_password_policy_is_configurable() {
+        let relaxed = PasswordPolicy {
+            min_length: 4,
+            required_classes: vec![CharacterClass::Symbol],
+            reject_common: false,
+            reject_personal_info: false,
+            ..PasswordPolicy::default()
+        };
+
+        assert_eq!(
+            validate_password("abcd", &relaxed, "user", "user@example.com"),
+            Err(ValidationError::PasswordMissingCharacterClass(CharacterClass::Symbol))
+        );
+        assert!(validate_password("user!", &relaxed, "user", "user@example.com").is_ok());
+        assert!(validate_password("password!", &relaxed, "user", "user@example.com").is_ok());
+        assert_eq!(
+            ValidationError::PasswordMissingCharacterClass(CharacterClass::Digit).to_string(),
+            "Password must contain at least one digit"
+        );
+    }
+}
@@ -11,6 +11,7 @@
  * and errors are handled gracefully with context information.
  */
 
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 import { safeInvoke } from '../utils/api-wrapper'
 import {
   sanitizeEmail,
@@ -24,6 +25,13 @@ import type {
   User,
   CreateUser,
   UpdateUser,
+  UserQuery,
+  UserPage,
+  UserSettings,
+  UpdateUserSettings,
+  ImportedSettings,
+  SettingsChanged,
+  SettingsSchema,
   LoginRequest,
   AppLog,
   CreateAppLog,
@@ -57,13 +65,30 @@ export const runMigrations = async (): Promise<string> => {
 
 // ==================== User Management ====================
 
-/** Retrieves all users from the database (excluding password hashes). */
-export const getAllUsers = async (): Promise<User[]> => {
-  return await safeInvoke<User[]>('get_all_users', undefined, {
+/**
+ * Retrieves all users from the database (excluding password hashes).
+ * Soft-deleted users are only included when `includeDeleted` is set.
+ */
+export const getAllUsers = async (includeDeleted = false): Promise<User[]> => {
+  return await safeInvoke<User[]>('get_all_users', { includeDeleted }, {
     context: { component: 'users', action: 'get_all' },
   })
 }
 
+/**
+ * Lists users a page at a time, with optional search, filters and sorting.
+ * The page includes the total number of matching users.
+ */
+export const listUsers = async (query: UserQuery = {}): Promise<UserPage> => {
+  return await safeInvoke<UserPage>(
+    'list_users',
+    { query },
+    {
+      context: { component: 'users', action: 'list' },
+    }
+  )
+}
+
 /** Retrieves a specific user by their unique identifier. */
 export const getUserById = async (userId: string): Promise<User | null> => {
   return await safeInvoke<User | null>(
@@ -110,7 +135,6 @@ export const updateUser = async (
       ? sanitizeName(userData.firstName)
       : undefined,
     lastName: userData.lastName ? sanitizeName(userData.lastName) : undefined,
-    isActive: userData.isActive,
   }
 
   return await safeInvoke<User>(
@@ -132,6 +156,102 @@ export const deleteUser = async (userId: string): Promise<string> => {
   )
 }
 
+// ==================== User Settings ====================
+
+/** Retrieves a user's settings, or the defaults if none have been saved. */
+export const getUserSettings = async (userId: string): Promise<UserSettings> => {
+  return await safeInvoke<UserSettings>(
+    'get_user_settings',
+    { userId },
+    {
+      context: { component: 'settings', action: 'get', userId },
+    }
+  )
+}
+
+/**
+ * Creates or updates a user's settings. `settingsData` is a JSON merge
+ * patch: nested objects are merged and `null` removes a key.
+ */
+export const upsertUserSettings = async (
+  userId: string,
+  settings: UpdateUserSettings
+): Promise<UserSettings> => {
+  return await safeInvoke<UserSettings>(
+    'upsert_user_settings',
+    { userId, settings },
+    {
+      context: { component: 'settings', action: 'upsert', userId },
+    }
+  )
+}
+
+/** Deletes a user's saved settings and returns the defaults. */
+export const resetUserSettings = async (userId: string): Promise<UserSettings> => {
+  return await safeInvoke<UserSettings>(
+    'reset_user_settings',
+    { userId },
+    {
+      context: { component: 'settings', action: 'reset', userId },
+    }
+  )
+}
+
+/**
+ * Calls `handler` whenever any window changes a user's settings, including
+ * this one. Returns a function that stops listening.
+ */
+export const onSettingsChanged = async (
+  handler: (change: SettingsChanged) => void
+): Promise<UnlistenFn> => {
+  return await listen<SettingsChanged>('settings-changed', (event) => handler(event.payload))
+}
+
+/**
+ * Exports a user's settings, app settings and logging configuration to a
+ * versioned JSON bundle at `path`, relative to the app data directory.
+ */
+export const exportSettings = async (userId: string, path: string): Promise<string> => {
+  return await safeInvoke<string>(
+    'export_settings',
+    { userId, path },
+    {
+      context: { component: 'settings', action: 'export', userId, path },
+    }
+  )
+}
+
+/**
+ * Validates the bundle at `path` and applies it to the user. Nothing is
+ * changed if any part of the bundle is invalid. The bundled logging
+ * configuration is only applied for callers with `system:admin`, and its
+ * level takes effect immediately; for other callers it is listed in
+ * `skipped`.
+ */
+export const importSettings = async (userId: string, path: string): Promise<ImportedSettings> => {
+  return await safeInvoke<ImportedSettings>(
+    'import_settings',
+    { userId, path },
+    {
+      context: { component: 'settings', action: 'import', userId, path },
+    }
+  )
+}
+
+/**
+ * Lists the registered settings namespaces. Values under
+ * `settingsData.<namespace>` are validated against these schemas.
+ */
+export const getSettingsSchemas = async (): Promise<Record<string, SettingsSchema>> => {
+  return await safeInvoke<Record<string, SettingsSchema>>(
+    'get_settings_schemas',
+    undefined,
+    {
+      context: { component: 'settings', action: 'get_schemas' },
+    }
+  )
+}
+
 export const authenticateUser = async (
   loginData: LoginRequest
 ): Promise<User | null> => {
@@ -139,6 +259,7 @@ export const authenticateUser = async (
   const sanitizedLoginData: LoginRequest = {
     email: sanitizeEmail(loginData.email),
     password: loginData.password, // Don't sanitize password
+    totpCode: loginData.totpCode?.trim(),
   }
 
   return await safeInvoke<User | null>(
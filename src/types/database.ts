@@ -8,6 +8,31 @@ export interface User {
   lastName?: string
   isActive: boolean
   createdAt: string
+  role: UserRole
+  totpEnabled: boolean
+  emailVerified: boolean
+  deletedAt?: string
+}
+
+export type UserRole = 'admin' | 'operator' | 'user'
+
+export interface UserQuery {
+  search?: string
+  isActive?: boolean
+  createdAfter?: string
+  createdBefore?: string
+  includeDeleted?: boolean
+  sortBy?: 'createdAt' | 'email' | 'username'
+  sortOrder?: 'asc' | 'desc'
+  limit?: number
+  offset?: number
+}
+
+export interface UserPage {
+  users: User[]
+  total: number
+  limit: number
+  offset: number
 }
 
 export interface CreateUser {
@@ -23,12 +48,13 @@ export interface UpdateUser {
   username?: string
   firstName?: string
   lastName?: string
-  isActive?: boolean
 }
 
 export interface LoginRequest {
   email: string
   password: string
+  /** TOTP or recovery code, required once two-factor is enabled. */
+  totpCode?: string
 }
 
 export interface UserSettings {
@@ -57,6 +83,37 @@ export interface UpdateUserSettings {
   settingsData?: Record<string, unknown>
 }
 
+/** Result of importing a settings bundle. */
+export interface ImportedSettings {
+  settings: UserSettings
+  /** Bundle parts that were not applied, such as `logConfig` for callers without `system:admin`. */
+  skipped: string[]
+}
+
+/** Payload of the `settings-changed` event. */
+export interface SettingsChanged {
+  userId: string
+  /** Changed keys, e.g. `theme` or `settingsData.editor`. */
+  changed: string[]
+  settings: UserSettings
+}
+
+export type SettingsFieldType = 'string' | 'number' | 'integer' | 'boolean' | 'array' | 'object'
+
+export interface SettingsFieldSchema {
+  field_type: SettingsFieldType
+  description: string
+  default?: unknown
+  required: boolean
+  min?: number
+  max?: number
+  pattern?: string
+  enum_values?: unknown[]
+}
+
+/** Fields of a registered settings namespace, keyed by name. */
+export type SettingsSchema = Record<string, SettingsFieldSchema>
+
 export interface AppLog {
   id: string
   level: string
@@ -80,11 +137,19 @@ export interface LogQuery {
   offset?: number
 }
 
+export interface PoolStats {
+  size: number
+  idle: number
+  inUse: number
+  maxConnections: number
+}
+
 export interface DatabaseStatus {
   connected: boolean
   databaseName?: string
   version?: string
   error?: string
+  pool?: PoolStats
 }
 
 export type LogLevel = 'error' | 'warn' | 'info' | 'debug' | 'trace'